@@ -0,0 +1,72 @@
+use crate::utils::constants::caching::HTTP_DATE_FORMAT;
+use chrono::{DateTime, NaiveDateTime};
+use rocket::http::{ContentType, Status};
+use rocket::response::{Responder, Response};
+use rocket::Request;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+/// A JSON response that supports conditional GET. The ETag is a hash of the body, so
+/// it changes exactly when the content does; `Last-Modified` comes from the
+/// `updated_at` columns behind the response. `If-None-Match` takes precedence over
+/// `If-Modified-Since`, and either one that matches turns the response into a
+/// bodiless `304 Not Modified`.
+pub struct Cached<T> {
+    pub value: T,
+    pub last_modified: Option<NaiveDateTime>,
+    pub cache_control: &'static str,
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Cached<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let body = serde_json::to_string(&self.value).map_err(|error| {
+            log::error!("Failed to serialize cached response: {}", error);
+            Status::InternalServerError
+        })?;
+        let etag = format!(
+            "\"{}\"",
+            hex::encode(&Sha256::digest(body.as_bytes())[..16])
+        );
+
+        let not_modified = match req.headers().get_one("If-None-Match") {
+            Some(header) => etag_matches(header, &etag),
+            None => match (
+                req.headers().get_one("If-Modified-Since"),
+                self.last_modified,
+            ) {
+                (Some(since), Some(modified)) => DateTime::parse_from_rfc2822(since)
+                    .is_ok_and(|since| modified.and_utc().timestamp() <= since.timestamp()),
+                _ => false,
+            },
+        };
+
+        let mut response = Response::build();
+        response
+            .raw_header("ETag", etag)
+            .raw_header("Cache-Control", self.cache_control)
+            .raw_header("Vary", "Authorization, Accept-Language");
+        if let Some(modified) = self.last_modified {
+            response.raw_header(
+                "Last-Modified",
+                modified.and_utc().format(HTTP_DATE_FORMAT).to_string(),
+            );
+        }
+
+        match not_modified {
+            true => response.status(Status::NotModified),
+            false => response
+                .header(ContentType::JSON)
+                .sized_body(body.len(), Cursor::new(body)),
+        };
+
+        response.ok()
+    }
+}
+
+/// Weak comparison, as RFC 9110 prescribes for `If-None-Match`.
+fn etag_matches(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
@@ -0,0 +1,26 @@
+use crate::utils::constants::caching::IMAGE_CACHE_CONTROL;
+use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::{Request, Response};
+
+/// Lets browsers keep locally served product images for good. Every stored file gets
+/// a fresh UUID name, so a URL never points at different content.
+pub struct ImmutableImages;
+
+#[rocket::async_trait]
+impl Fairing for ImmutableImages {
+    fn info(&self) -> Info {
+        Info {
+            name: "Immutable product images",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let images_prefix = format!("/{}/", PATH_PRODUCT_IMAGES);
+        if res.status() == Status::Ok && req.uri().path().starts_with(images_prefix.as_str()) {
+            res.set_raw_header("Cache-Control", IMAGE_CACHE_CONTROL);
+        }
+    }
+}
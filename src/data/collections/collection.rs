@@ -0,0 +1,42 @@
+use crate::data::products_components::product::Product;
+use crate::data::products_components::product_image::ProductImage;
+use chrono::NaiveDateTime;
+use rocket::serde::{Deserialize, Serialize};
+
+/// An admin-curated product list. Manual collections keep `product_ids` in display
+/// order; rule-based ones select products matching every condition in `rules`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: Option<i32>,
+    pub slug: String,
+    pub title: String,
+    pub kind: String,
+    pub product_ids: Option<Vec<i32>>,
+    pub rules: Option<CollectionRules>,
+    pub sort: Option<String>,
+    pub product_limit: Option<i32>,
+    pub starts_at: Option<NaiveDateTime>,
+    pub ends_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CollectionRules {
+    pub tag: Option<String>,
+    pub category_id: Option<i32>,
+    pub min_price: Option<f32>,
+    pub max_price: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionProduct {
+    #[serde(flatten)]
+    pub product: Product,
+    pub primary_image: Option<ProductImage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionWithProducts {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub products: Vec<CollectionProduct>,
+}
@@ -0,0 +1,46 @@
+use crate::data::products_components::product::Product;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Generated feeds by name, each kept with the catalog fingerprint it was built
+/// from. A feed is rebuilt as soon as the fingerprint changes.
+#[derive(Default)]
+pub struct FeedCache {
+    feeds: Mutex<HashMap<&'static str, (String, String)>>,
+}
+
+impl FeedCache {
+    pub fn get(&self, name: &str, fingerprint: &str) -> Option<String> {
+        self.feeds
+            .lock()
+            .unwrap()
+            .get(name)
+            .filter(|(cached_fingerprint, _)| cached_fingerprint == fingerprint)
+            .map(|(_, body)| body.clone())
+    }
+
+    pub fn insert(&self, name: &'static str, fingerprint: String, body: String) {
+        self.feeds.lock().unwrap().insert(name, (fingerprint, body));
+    }
+}
+
+pub struct FeedCategory {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+/// A published product with everything the feeds list about it. Prices are in the
+/// base currency with active sales applied.
+pub struct FeedItem {
+    pub product: Product,
+    pub image_urls: Vec<String>,
+    pub in_stock: bool,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+pub struct FeedCatalog {
+    pub items: Vec<FeedItem>,
+    pub categories: Vec<FeedCategory>,
+}
@@ -0,0 +1,72 @@
+use crate::utils::constants::locales::{DEFAULT_LOCALE, SUPPORTED_LOCALES};
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+
+/// The content locale of a request: the `lang` query parameter if it names a
+/// supported locale, otherwise the best supported match from `Accept-Language`,
+/// otherwise Ukrainian.
+#[derive(Debug, Clone, Copy)]
+pub struct Locale(pub &'static str);
+
+impl Locale {
+    pub fn parse(tag: &str) -> Option<&'static str> {
+        let language = tag.trim().split(['-', '_']).next()?.to_lowercase();
+        SUPPORTED_LOCALES
+            .iter()
+            .find(|locale| **locale == language)
+            .copied()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0 == DEFAULT_LOCALE
+    }
+
+    fn from_accept_language(header: &str) -> Option<&'static str> {
+        let mut languages = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next()?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |quality| quality.trim().parse::<f32>().ok())?;
+                Some((tag, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect::<Vec<(&str, f32)>>();
+        languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // `*` stands for any locale the header does not name, rejected ones included.
+        let named = header
+            .split(',')
+            .filter_map(|entry| Self::parse(entry.split(';').next()?))
+            .collect::<Vec<&str>>();
+        languages.into_iter().find_map(|(tag, _)| match tag.trim() {
+            "*" => SUPPORTED_LOCALES
+                .iter()
+                .find(|locale| !named.contains(locale))
+                .copied(),
+            tag => Self::parse(tag),
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Locale {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let locale = req
+            .query_value::<&str>("lang")
+            .and_then(Result::ok)
+            .and_then(Locale::parse)
+            .or_else(|| {
+                req.headers()
+                    .get_one("Accept-Language")
+                    .and_then(Locale::from_accept_language)
+            })
+            .unwrap_or(DEFAULT_LOCALE);
+
+        request::Outcome::Success(Locale(locale))
+    }
+}
@@ -0,0 +1,18 @@
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Translation {
+    pub locale: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// A product or category whose text is not yet translated into `locale`.
+#[derive(Debug, Serialize)]
+pub struct MissingTranslation {
+    pub entity: String,
+    pub id: i32,
+    pub name: String,
+    pub locale: String,
+    pub fields: Vec<String>,
+}
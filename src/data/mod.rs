@@ -1,3 +1,10 @@
+pub mod caching;
+pub mod collections;
+pub mod feeds;
+pub mod localization;
 pub mod orders;
+pub mod pricing;
 pub mod products_components;
+pub mod reviews;
 pub mod user_components;
+pub mod wishlists;
@@ -7,10 +7,17 @@ use rocket::serde::{Deserialize, Serialize};
 pub struct Order {
     pub id: Option<i32>,
     pub user_id: Option<i32>,
+    /// Computed by the server when the order is placed; any value sent is ignored.
+    #[serde(default)]
     pub total_price: f32,
+    /// New orders always start as pending; any value sent is ignored.
+    #[serde(default)]
     pub status: String,
     pub online_payment: bool,
     pub date: Option<NaiveDateTime>,
+    pub currency: Option<String>,
+    pub exchange_rate: Option<f32>,
+    pub shipping_price: Option<f32>,
 }
 #[derive(serde::Serialize, sqlx::FromRow)]
 pub struct OrderItemDetails {
@@ -19,15 +26,34 @@ pub struct OrderItemDetails {
     pub quantity: i32,
     pub size: Option<String>,
     pub total_price: f32,
+    #[sqlx(skip)]
+    pub components: Vec<OrderItemComponent>,
+}
+/// One component of a bundle order item; `quantity` counts units across all
+/// bundles of the item.
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct OrderItemComponent {
+    #[serde(skip)]
+    pub order_item_id: i32,
+    pub product_name: String,
+    pub quantity: i32,
+    pub size: Option<String>,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DataOrder {
     pub order: Order,
     pub order_items: Vec<OrderItem>,
     pub shipping: Shipping,
+    /// The total the customer saw at checkout. When set, the order is rejected if
+    /// the server arrives at a different total.
+    pub expected_total: Option<f32>,
 }
 #[derive(serde::Serialize)]
 pub struct OrderDetails {
     pub shipping: Shipping,
     pub items: Vec<OrderItemDetails>,
+    pub currency: String,
+    pub currency_symbol: String,
+    pub shipping_price: f32,
+    pub total_price: f32,
 }
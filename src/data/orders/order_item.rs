@@ -5,7 +5,19 @@ pub struct OrderItem {
     pub order_id: Option<i32>,
     pub product_id: i32,
     pub quantity: i32,
+    /// Looked up by the server when the order is placed; any value sent is ignored.
+    #[serde(default)]
     pub price: f32,
     pub size: Option<String>,
+    #[serde(default)]
     pub total_price: f32,
+    /// The size picked for each component when the item is a bundle.
+    #[serde(default)]
+    pub components: Vec<ComponentSelection>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentSelection {
+    pub product_id: i32,
+    pub size: Option<String>,
 }
@@ -0,0 +1,39 @@
+use crate::utils::constants::pricing::{ROUNDING_DOWN, ROUNDING_UP};
+use rocket::serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Exchange rate is stored as the amount of base currency (UAH) for one unit of `code`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Currency {
+    pub code: String,
+    pub symbol: String,
+    pub rate: f32,
+    pub rounding_step: f32,
+    pub rounding_mode: String,
+}
+
+impl Currency {
+    pub fn convert(&self, base_price: f32) -> f32 {
+        self.round(base_price / self.rate)
+    }
+
+    pub fn round(&self, amount: f32) -> f32 {
+        if self.rounding_step <= 0.0 {
+            return amount;
+        }
+        let steps = amount / self.rounding_step;
+        let steps = match self.rounding_mode.as_str() {
+            ROUNDING_UP => steps.ceil(),
+            ROUNDING_DOWN => steps.floor(),
+            _ => steps.round(),
+        };
+        steps * self.rounding_step
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductPrice {
+    pub product_id: i32,
+    pub currency_code: String,
+    pub price: f32,
+}
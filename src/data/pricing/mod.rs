@@ -0,0 +1,3 @@
+pub mod currency;
+pub mod price_history;
+pub mod sale;
@@ -0,0 +1,16 @@
+use chrono::NaiveDateTime;
+use rocket::serde::Serialize;
+use sqlx::FromRow;
+
+/// One recorded change of a product's price in the base currency. `size` is set when
+/// the change applied to a single variant only.
+#[derive(Debug, Serialize, FromRow)]
+pub struct PriceChange {
+    pub id: i32,
+    pub product_id: i32,
+    pub size: Option<String>,
+    pub old_price: Option<f32>,
+    pub new_price: f32,
+    pub source: String,
+    pub changed_at: Option<NaiveDateTime>,
+}
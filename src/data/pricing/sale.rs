@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use rocket::serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A time-boxed sale price for a whole product or, when `size` is set, for one variant.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Sale {
+    pub id: Option<i32>,
+    pub product_id: i32,
+    pub size: Option<String>,
+    pub sale_price: f32,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+}
+
+impl Sale {
+    pub fn applies_to(&self, size: Option<&str>) -> bool {
+        match (&self.size, size) {
+            (None, _) => true,
+            (Some(sale_size), Some(size)) => sale_size.eq_ignore_ascii_case(size),
+            (Some(_), None) => false,
+        }
+    }
+}
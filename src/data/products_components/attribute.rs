@@ -0,0 +1,35 @@
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attribute {
+    pub id: Option<i32>,
+    pub code: String,
+    pub name: String,
+    pub data_type: String,
+    pub options: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductAttributeValue {
+    pub attribute_id: i32,
+    pub code: Option<String>,
+    pub value: Option<String>,
+    pub number_value: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Facet {
+    pub code: String,
+    pub name: String,
+    pub data_type: String,
+    pub values: Vec<FacetValue>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FacetValue {
+    pub value: String,
+    pub count: i64,
+    pub selected: bool,
+}
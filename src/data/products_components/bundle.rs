@@ -0,0 +1,27 @@
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A product that is part of a bundle, `quantity` units per bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleComponent {
+    pub product_id: i32,
+    pub quantity: i32,
+}
+
+/// A bundle with its components and how many complete bundles the stock allows,
+/// picking the best stocked size of every component.
+#[derive(Debug, Serialize)]
+pub struct Bundle {
+    pub product_id: i32,
+    pub available: i32,
+    pub components: Vec<BundleComponentStock>,
+}
+
+/// A bundle component with the stock of each size that could fill a bundle.
+#[derive(Debug, Serialize)]
+pub struct BundleComponentStock {
+    pub product_id: i32,
+    pub name: String,
+    pub quantity: i32,
+    pub sizes: HashMap<String, i32>,
+}
@@ -0,0 +1,33 @@
+use rocket::serde::{Deserialize, Serialize};
+
+/// One product per line; `category` is a path of names such as `Одяг/Худі/Oversize`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CatalogRow {
+    pub id: Option<i32>,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: f32,
+    pub compare_at_price: Option<f32>,
+    pub category: Option<String>,
+    pub single_size: Option<i32>,
+    pub s: Option<i32>,
+    pub m: Option<i32>,
+    pub l: Option<i32>,
+    pub xl: Option<i32>,
+    pub xxl: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: i32,
+    pub updated: i32,
+    pub categories_created: i32,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub message: String,
+}
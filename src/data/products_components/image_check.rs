@@ -0,0 +1,31 @@
+use rocket::serde::Serialize;
+
+/// An image row that references a file the storage no longer has.
+#[derive(Debug, Serialize)]
+pub struct MissingImageFile {
+    pub image_id: i32,
+    pub product_id: Option<i32>,
+    pub filename: String,
+}
+
+/// Result of comparing `product_images` and `review_photos` with the stored files.
+/// The counters say what the chosen action actually cleaned up.
+#[derive(Debug, Default, Serialize)]
+pub struct ImageCheckReport {
+    pub action: String,
+    pub orphaned_files: Vec<String>,
+    pub missing_files: Vec<MissingImageFile>,
+    pub detached_images: Vec<i32>,
+    pub files_quarantined: usize,
+    pub files_removed: usize,
+    pub rows_quarantined: usize,
+    pub rows_removed: usize,
+}
+
+impl ImageCheckReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphaned_files.is_empty()
+            && self.missing_files.is_empty()
+            && self.detached_images.is_empty()
+    }
+}
@@ -1,4 +1,11 @@
+pub mod attribute;
+pub mod bundle;
+pub mod catalog;
 pub mod category;
+pub mod image_check;
 pub mod product;
 pub mod product_image;
 pub mod size;
+pub mod stock_alert;
+pub mod stock_movement;
+pub mod stock_subscription;
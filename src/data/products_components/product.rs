@@ -1,6 +1,7 @@
 use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Product {
     pub id: Option<i32>,
     pub name: String,
@@ -9,4 +10,33 @@ pub struct Product {
     pub price: f32,
     pub size_id: Option<i32>,
     pub category_id: Option<i32>,
+    pub currency: Option<String>,
+    pub compare_at_price: Option<f32>,
+    pub active_price: Option<f32>,
+    pub variant_prices: Option<HashMap<String, f32>>,
+    pub rating_average: Option<f32>,
+    pub rating_count: Option<i64>,
+    /// Drafts are hidden from customers until an admin publishes them.
+    pub draft: Option<bool>,
+    /// Lowest price of the 30 days before the running sale started, or before now
+    /// when nothing is on sale. Not given in a currency with a manual price.
+    pub lowest_price_30d: Option<f32>,
+}
+
+/// What to carry over when cloning a product; price is always copied. Copied sizes
+/// start with zero stock, copied images get files of their own and `bundle` copies
+/// the bundle components.
+#[derive(Debug, Deserialize)]
+pub struct ProductDuplicate {
+    pub name: String,
+    #[serde(default)]
+    pub description: bool,
+    #[serde(default)]
+    pub category: bool,
+    #[serde(default)]
+    pub sizes: bool,
+    #[serde(default)]
+    pub images: bool,
+    #[serde(default)]
+    pub bundle: bool,
 }
@@ -12,4 +12,16 @@ pub struct ProductImage {
     pub image_url: String,
     pub product_id: Option<i32>,
     pub position: Option<i32>,
+    pub variants: Option<Vec<ImageVariant>>,
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub name: String,
+    pub format: String,
+    pub url: String,
+}
+/// The complete gallery of a product, first image first.
+#[derive(Debug, Deserialize)]
+pub struct ImageOrder {
+    pub image_ids: Vec<i32>,
 }
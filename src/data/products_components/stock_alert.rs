@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use rocket::serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A product size whose stock fell to or below its low-stock threshold. An alert stays
+/// open until the stock rises above the threshold again, so each drop alerts once.
+#[derive(Debug, Serialize, FromRow)]
+pub struct StockAlert {
+    pub id: i32,
+    pub product_id: i32,
+    pub product_name: String,
+    pub size: String,
+    pub stock: i32,
+    pub threshold: i32,
+    pub created_at: Option<NaiveDateTime>,
+    pub notified_at: Option<NaiveDateTime>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockAlertSettings {
+    pub default_threshold: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockThreshold {
+    pub threshold: Option<i32>,
+}
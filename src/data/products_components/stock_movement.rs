@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use rocket::serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// One append-only change to the stock of a product size.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct StockMovement {
+    pub id: Option<i32>,
+    pub product_id: i32,
+    pub size: String,
+    pub delta: i32,
+    pub reason: String,
+    pub actor_id: Option<i32>,
+    pub order_id: Option<i32>,
+    pub note: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// A product size whose stock count disagrees with the sum of its ledger.
+#[derive(Debug, Serialize, FromRow)]
+pub struct StockDiscrepancy {
+    pub product_id: i32,
+    pub size: String,
+    pub stock: i32,
+    pub ledger: i32,
+}
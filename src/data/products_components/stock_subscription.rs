@@ -0,0 +1,9 @@
+use rocket::serde::Deserialize;
+
+/// A request to be emailed once a product size is back in stock. Logged-in users
+/// are subscribed with their account email; guests have to give one and confirm it.
+#[derive(Debug, Deserialize)]
+pub struct NewStockSubscription {
+    pub size: Option<String>,
+    pub email: Option<String>,
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDateTime;
+use rocket::fs::TempFile;
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct NewReview {
+    pub rating: i16,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, FromForm)]
+pub struct NewReviewPhoto<'r> {
+    pub image: TempFile<'r>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Review {
+    pub id: i32,
+    pub product_id: i32,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub rating: i16,
+    pub text: Option<String>,
+    pub status: String,
+    pub admin_reply: Option<String>,
+    pub replied_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub photos: Vec<ReviewPhoto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewPhoto {
+    pub thumbnail_url: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewPage {
+    pub items: Vec<Review>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
@@ -1,3 +1,4 @@
+use crate::data::wishlists::wishlist::NewWishlistItem;
 use rocket::serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
@@ -11,6 +12,7 @@ pub struct LoginResponse {
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    pub wishlist: Option<Vec<NewWishlistItem>>,
 }
 #[derive(Serialize)]
 pub struct RoleResponse {
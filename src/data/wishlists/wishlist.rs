@@ -0,0 +1,29 @@
+use crate::data::products_components::product::Product;
+use chrono::NaiveDateTime;
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct NewWishlistItem {
+    pub product_id: i32,
+    pub size: Option<String>,
+}
+
+/// A saved product with its current price and stock. Without a size, `stock` is
+/// the total over all sizes.
+#[derive(Debug, Serialize)]
+pub struct WishlistItem {
+    pub id: i32,
+    pub size: Option<String>,
+    pub stock: i32,
+    pub in_stock: bool,
+    pub added_at: Option<NaiveDateTime>,
+    pub product: Product,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WishlistStat {
+    pub product_id: i32,
+    pub product_name: String,
+    pub wishlist_count: i64,
+    pub last_added_at: Option<NaiveDateTime>,
+}
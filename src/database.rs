@@ -3,8 +3,11 @@ use eyre::Result;
 use sqlx::{postgres::PgPoolOptions, Connection, Executor, PgConnection, PgPool};
 
 pub async fn init_db_pool() -> Result<PgPool> {
-    let database_name = CONFIG.get().unwrap().database_name.clone();
+    open_db_pool(&CONFIG.get().unwrap().database_name).await
+}
 
+/// Connects to `database_name`, creating the database and its schema if needed.
+pub async fn open_db_pool(database_name: &str) -> Result<PgPool> {
     let main_database_url = format!(
         "postgres://{}:{}@{}:{}",
         CONFIG.get().unwrap().database_user.clone(),
@@ -135,6 +138,324 @@ pub async fn init_db_pool() -> Result<PgPool> {
                     payment_date TIMESTAMP DEFAULT CURRENT_TIMESTAMP
                 );
 
+                CREATE TABLE IF NOT EXISTS currencies (
+                    id SERIAL PRIMARY KEY,
+                    code VARCHAR(3) NOT NULL UNIQUE,
+                    symbol VARCHAR(10) NOT NULL,
+                    rate REAL NOT NULL,
+                    rounding_step REAL NOT NULL DEFAULT 0.01,
+                    rounding_mode VARCHAR(10) NOT NULL DEFAULT 'nearest',
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                INSERT INTO currencies (code, symbol, rate, rounding_step)
+                VALUES ('UAH', 'грн', 1, 0.01)
+                ON CONFLICT (code) DO NOTHING;
+
+                CREATE TABLE IF NOT EXISTS product_prices (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT REFERENCES products(id) ON DELETE CASCADE,
+                    currency_code VARCHAR(3) REFERENCES currencies(code) ON DELETE CASCADE,
+                    price REAL NOT NULL,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (product_id, currency_code)
+                );
+
+                ALTER TABLE orders ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'UAH';
+                ALTER TABLE orders ADD COLUMN IF NOT EXISTS exchange_rate REAL NOT NULL DEFAULT 1;
+                -- Orders placed before reservations existed never took stock. They keep a
+                -- NULL flag, set once when the column is added, so that neither cancelling
+                -- nor reopening them moves stock.
+                ALTER TABLE orders ADD COLUMN IF NOT EXISTS stock_reserved BOOLEAN;
+                ALTER TABLE orders ALTER COLUMN stock_reserved DROP NOT NULL;
+                ALTER TABLE orders ALTER COLUMN stock_reserved SET DEFAULT FALSE;
+                ALTER TABLE orders ADD COLUMN IF NOT EXISTS shipping_price REAL NOT NULL DEFAULT 0;
+
+                ALTER TABLE products ADD COLUMN IF NOT EXISTS compare_at_price REAL;
+                ALTER TABLE products ADD COLUMN IF NOT EXISTS low_stock_threshold INT;
+                ALTER TABLE products ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+                ALTER TABLE products ADD COLUMN IF NOT EXISTS draft BOOLEAN NOT NULL DEFAULT FALSE;
+
+                ALTER TABLE product_images ADD COLUMN IF NOT EXISTS image_key VARCHAR(64);
+                ALTER TABLE product_images ADD COLUMN IF NOT EXISTS quarantined_at TIMESTAMP;
+
+                CREATE TABLE IF NOT EXISTS product_sales (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25) DEFAULT NULL,
+                    sale_price REAL NOT NULL,
+                    starts_at TIMESTAMP NOT NULL,
+                    ends_at TIMESTAMP,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+                -- Single-size items are ordered without a size, so their sales are product-wide.
+                UPDATE product_sales SET size = NULL WHERE size = 'single_size';
+
+                ALTER TABLE categories ADD COLUMN IF NOT EXISTS parent_id INT REFERENCES categories(id) ON DELETE RESTRICT;
+                ALTER TABLE categories ADD COLUMN IF NOT EXISTS sort_order INT NOT NULL DEFAULT 0;
+                ALTER TABLE categories ADD COLUMN IF NOT EXISTS description TEXT;
+                ALTER TABLE categories ADD COLUMN IF NOT EXISTS image_url VARCHAR(255);
+                ALTER TABLE categories DROP CONSTRAINT IF EXISTS categories_name_key;
+                CREATE UNIQUE INDEX IF NOT EXISTS categories_parent_name_idx
+                    ON categories (COALESCE(parent_id, 0), name);
+
+                CREATE TABLE IF NOT EXISTS attributes (
+                    id SERIAL PRIMARY KEY,
+                    code VARCHAR(50) NOT NULL UNIQUE,
+                    name VARCHAR(255) NOT NULL,
+                    data_type VARCHAR(10) NOT NULL,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS attribute_options (
+                    id SERIAL PRIMARY KEY,
+                    attribute_id INT NOT NULL REFERENCES attributes(id) ON DELETE CASCADE,
+                    value VARCHAR(255) NOT NULL,
+                    sort_order INT NOT NULL DEFAULT 0,
+                    UNIQUE (attribute_id, value)
+                );
+
+                CREATE TABLE IF NOT EXISTS category_attributes (
+                    category_id INT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+                    attribute_id INT NOT NULL REFERENCES attributes(id) ON DELETE CASCADE,
+                    PRIMARY KEY (category_id, attribute_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS product_attribute_values (
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    attribute_id INT NOT NULL REFERENCES attributes(id) ON DELETE CASCADE,
+                    value VARCHAR(255),
+                    number_value REAL,
+                    PRIMARY KEY (product_id, attribute_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS reviews (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    user_id INT REFERENCES users(id) ON DELETE SET NULL,
+                    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+                    text TEXT,
+                    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+                    admin_reply TEXT,
+                    replied_at TIMESTAMP,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (product_id, user_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS review_photos (
+                    id SERIAL PRIMARY KEY,
+                    review_id INT NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+                    image_key VARCHAR(64) NOT NULL,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS product_recommendations (
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    recommended_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    score INT NOT NULL,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (product_id, recommended_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS stock_movements (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25) NOT NULL,
+                    delta INT NOT NULL,
+                    reason VARCHAR(30) NOT NULL,
+                    actor_id INT REFERENCES users(id) ON DELETE SET NULL,
+                    order_id INT,
+                    note TEXT,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE INDEX IF NOT EXISTS stock_movements_product_idx
+                    ON stock_movements (product_id, size);
+
+                -- Stock that existed before the ledger gets one opening-balance entry,
+                -- dated before the first recorded movement, so the ledger adds up.
+                ALTER TABLE product_sizes ADD COLUMN IF NOT EXISTS opening_recorded BOOLEAN NOT NULL DEFAULT FALSE;
+                ALTER TABLE product_sizes ALTER COLUMN opening_recorded SET DEFAULT TRUE;
+                INSERT INTO stock_movements (product_id, size, delta, reason, note, created_at)
+                SELECT ps.product_id, v.size,
+                    COALESCE(v.quantity, 0) - COALESCE(l.total, 0),
+                    'opening_balance', 'stock before the ledger',
+                    COALESCE(l.first_at - INTERVAL '1 second', NOW())
+                FROM product_sizes ps
+                CROSS JOIN LATERAL (
+                    VALUES ('single_size', ps.single_size), ('s', ps.s), ('m', ps.m),
+                           ('l', ps.l), ('xl', ps.xl), ('xxl', ps.xxl)
+                ) AS v(size, quantity)
+                LEFT JOIN LATERAL (
+                    SELECT SUM(delta) AS total, MIN(created_at) AS first_at
+                    FROM stock_movements
+                    WHERE product_id = ps.product_id AND size = v.size
+                ) l ON TRUE
+                JOIN products p ON p.id = ps.product_id
+                WHERE NOT ps.opening_recorded
+                    AND COALESCE(v.quantity, 0) != COALESCE(l.total, 0);
+                UPDATE product_sizes SET opening_recorded = TRUE WHERE NOT opening_recorded;
+
+                CREATE TABLE IF NOT EXISTS stock_alert_settings (
+                    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+                    default_threshold INT NOT NULL DEFAULT 2
+                );
+
+                INSERT INTO stock_alert_settings (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
+
+                CREATE TABLE IF NOT EXISTS stock_alerts (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25) NOT NULL,
+                    stock INT NOT NULL,
+                    threshold INT NOT NULL,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    notified_at TIMESTAMP,
+                    resolved_at TIMESTAMP
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS stock_alerts_open_idx
+                    ON stock_alerts (product_id, size) WHERE resolved_at IS NULL;
+
+                CREATE TABLE IF NOT EXISTS stock_subscriptions (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25) NOT NULL,
+                    user_id INT REFERENCES users(id) ON DELETE CASCADE,
+                    email VARCHAR(255) NOT NULL,
+                    token VARCHAR(64) NOT NULL UNIQUE,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    confirmed_at TIMESTAMP,
+                    notified_at TIMESTAMP
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS stock_subscriptions_pending_idx
+                    ON stock_subscriptions (product_id, size, LOWER(email))
+                    WHERE notified_at IS NULL;
+
+                CREATE TABLE IF NOT EXISTS product_translations (
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    locale VARCHAR(5) NOT NULL,
+                    name VARCHAR(255),
+                    description TEXT,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (product_id, locale)
+                );
+
+                CREATE TABLE IF NOT EXISTS category_translations (
+                    category_id INT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+                    locale VARCHAR(5) NOT NULL,
+                    name VARCHAR(255),
+                    description TEXT,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (category_id, locale)
+                );
+
+                CREATE TABLE IF NOT EXISTS collections (
+                    id SERIAL PRIMARY KEY,
+                    slug VARCHAR(100) NOT NULL UNIQUE,
+                    title VARCHAR(255) NOT NULL,
+                    kind VARCHAR(20) NOT NULL,
+                    rule_tag VARCHAR(100),
+                    rule_category_id INT REFERENCES categories(id) ON DELETE SET NULL,
+                    rule_min_price REAL,
+                    rule_max_price REAL,
+                    sort VARCHAR(20),
+                    product_limit INT,
+                    starts_at TIMESTAMP,
+                    ends_at TIMESTAMP,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS collection_products (
+                    collection_id INT NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    position INT NOT NULL,
+                    PRIMARY KEY (collection_id, product_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS wishlist_items (
+                    id SERIAL PRIMARY KEY,
+                    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25),
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS wishlist_items_unique_idx
+                    ON wishlist_items (user_id, product_id, COALESCE(size, ''));
+
+                CREATE TABLE IF NOT EXISTS price_history (
+                    id SERIAL PRIMARY KEY,
+                    product_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    size VARCHAR(25) DEFAULT NULL,
+                    old_price REAL,
+                    new_price REAL NOT NULL,
+                    source VARCHAR(50) NOT NULL,
+                    changed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+                CREATE INDEX IF NOT EXISTS price_history_product_idx
+                    ON price_history (product_id, changed_at);
+
+                ALTER TABLE product_sales ADD COLUMN IF NOT EXISTS start_recorded BOOLEAN NOT NULL DEFAULT TRUE;
+                ALTER TABLE product_sales ALTER COLUMN start_recorded SET DEFAULT FALSE;
+                ALTER TABLE product_sales ADD COLUMN IF NOT EXISTS end_recorded BOOLEAN NOT NULL DEFAULT FALSE;
+
+                CREATE TABLE IF NOT EXISTS bundle_components (
+                    bundle_id INT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+                    component_id INT NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+                    quantity INT NOT NULL CHECK (quantity > 0),
+                    PRIMARY KEY (bundle_id, component_id)
+                );
+
+                CREATE TABLE IF NOT EXISTS order_item_components (
+                    id SERIAL PRIMARY KEY,
+                    order_item_id INT NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+                    product_id INT REFERENCES products(id) ON DELETE SET NULL,
+                    size VARCHAR(25) DEFAULT NULL,
+                    quantity INT NOT NULL
+                );
+
+                -- Deleted rows leave no updated_at behind, so the tables behind cached
+                -- responses record when they last lost a row.
+                CREATE TABLE IF NOT EXISTS table_deletions (
+                    table_name VARCHAR(64) PRIMARY KEY,
+                    deleted_at TIMESTAMP NOT NULL
+                );
+                CREATE OR REPLACE FUNCTION record_table_deletion() RETURNS TRIGGER AS $$
+                BEGIN
+                    IF EXISTS (SELECT 1 FROM deleted_rows) THEN
+                        INSERT INTO table_deletions (table_name, deleted_at)
+                        VALUES (TG_TABLE_NAME, NOW())
+                        ON CONFLICT (table_name) DO UPDATE SET deleted_at = EXCLUDED.deleted_at;
+                    END IF;
+                    RETURN NULL;
+                END;
+                $$ LANGUAGE plpgsql;
+                DO $$
+                DECLARE
+                    source TEXT;
+                BEGIN
+                    FOREACH source IN ARRAY ARRAY[
+                        'categories', 'category_translations', 'products', 'product_prices',
+                        'currencies', 'product_sales', 'product_translations', 'reviews',
+                        'product_images', 'price_history'
+                    ] LOOP
+                        EXECUTE format(
+                            'CREATE OR REPLACE TRIGGER %I AFTER DELETE ON %I
+                                REFERENCING OLD TABLE AS deleted_rows
+                                FOR EACH STATEMENT EXECUTE FUNCTION record_table_deletion()',
+                            source || '_deletion', source
+                        );
+                    END LOOP;
+                END $$;
+
         "#,
     )
     .await?;
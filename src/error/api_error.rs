@@ -2,6 +2,7 @@ use rocket::http::Status;
 use rocket::response::{Responder, Response};
 use rocket::Request;
 use serde::Serialize;
+use std::borrow::Cow;
 use std::io::Cursor;
 use thiserror::Error;
 
@@ -17,7 +18,6 @@ pub enum ApiError {
     Unauthorized,
     #[error("Bad request")]
     BadRequest,
-    #[allow(dead_code)]
     #[error("HTTP error")]
     HttpError,
     #[allow(dead_code)]
@@ -29,23 +29,99 @@ pub enum ApiError {
     PhoneError,
     #[error("Username already exists")]
     UsernameError,
+    #[error("Unknown currency")]
+    UnknownCurrency,
+    #[error("Category still has subcategories or products")]
+    CategoryNotEmpty,
+    #[error("Unsupported or damaged image")]
+    InvalidImage,
+    #[error("Only customers with a delivered order may review this product")]
+    ReviewNotAllowed,
+    #[error("Review already exists")]
+    ReviewExists,
+    #[error("Not enough stock for {0}")]
+    OutOfStock(String),
+    #[error("Slug already exists")]
+    SlugTaken,
+    #[error("Product is already in stock")]
+    AlreadyInStock,
+    #[error("Product is a component of a bundle")]
+    ProductInBundle,
+    #[error("Bundle components were not selected")]
+    BundleSelection,
+    #[error("Order total changed to {0}")]
+    PriceMismatch(f32),
+    #[error("Product {0} not found")]
+    ProductNotFound(i32),
+    #[error("Invalid shipping details")]
+    InvalidShipping,
+    #[error("Failed to save order {0}: {1}")]
+    OrderFailed(&'static str, sqlx::Error),
 }
 
 impl<'r> Responder<'r, 'static> for ApiError {
     fn respond_to(self, _: &'r Request<'_>) -> rocket::response::Result<'static> {
         log::error!("API error occurred: {:?}", self);
 
-        let (status, message) = match self {
-            ApiError::DatabaseError(_) => (Status::InternalServerError, "Database error occurred"),
-            ApiError::NotFound => (Status::NotFound, "User not found"),
-            ApiError::InternalServerError => (Status::InternalServerError, "Internal server error"),
-            ApiError::Unauthorized => (Status::Unauthorized, "Unauthorized access"),
-            ApiError::BadRequest => (Status::BadRequest, "Bad request"),
-            ApiError::HttpError => (Status::InternalServerError, "HTTP error occurred"),
-            ApiError::PaymentError => (Status::PaymentRequired, "Payment failed"),
-            ApiError::EmailError => (Status::Conflict, "Таку пошту вже зареєстровано"),
-            ApiError::PhoneError => (Status::Conflict, "Такий телефон вже зареєстровано"),
-            ApiError::UsernameError => (Status::Conflict, "Такий логін вже зареєстровано"),
+        let (status, message): (Status, Cow<str>) = match self {
+            ApiError::DatabaseError(_) => (
+                Status::InternalServerError,
+                "Database error occurred".into(),
+            ),
+            ApiError::NotFound => (Status::NotFound, "User not found".into()),
+            ApiError::InternalServerError => {
+                (Status::InternalServerError, "Internal server error".into())
+            }
+            ApiError::Unauthorized => (Status::Unauthorized, "Unauthorized access".into()),
+            ApiError::BadRequest => (Status::BadRequest, "Bad request".into()),
+            ApiError::HttpError => (Status::InternalServerError, "HTTP error occurred".into()),
+            ApiError::PaymentError => (Status::PaymentRequired, "Payment failed".into()),
+            ApiError::EmailError => (Status::Conflict, "Таку пошту вже зареєстровано".into()),
+            ApiError::PhoneError => (Status::Conflict, "Такий телефон вже зареєстровано".into()),
+            ApiError::UsernameError => (Status::Conflict, "Такий логін вже зареєстровано".into()),
+            ApiError::UnknownCurrency => (Status::BadRequest, "Невідома валюта".into()),
+            ApiError::CategoryNotEmpty => (
+                Status::Conflict,
+                "Категорія містить підкатегорії або товари".into(),
+            ),
+            ApiError::InvalidImage => (
+                Status::BadRequest,
+                "Непідтримуваний або пошкоджений файл зображення".into(),
+            ),
+            ApiError::ReviewNotAllowed => (
+                Status::Forbidden,
+                "Відгук можна залишити лише після отримання замовлення".into(),
+            ),
+            ApiError::ReviewExists => (
+                Status::Conflict,
+                "Ви вже залишили відгук на цей товар".into(),
+            ),
+            ApiError::SlugTaken => (
+                Status::BadRequest,
+                "Така адреса вже використовується".into(),
+            ),
+            ApiError::AlreadyInStock => (Status::Conflict, "Товар уже є в наявності".into()),
+            ApiError::ProductInBundle => {
+                (Status::Conflict, "Товар входить до складу комплекту".into())
+            }
+            ApiError::PriceMismatch(total) => (
+                Status::Conflict,
+                format!("Ціни змінилися, нова сума замовлення: {:.2}", total).into(),
+            ),
+            ApiError::ProductNotFound(_) => (Status::NotFound, "Товар не знайдено".into()),
+            ApiError::InvalidShipping => (Status::BadRequest, "Перевірте дані доставки".into()),
+            ApiError::OrderFailed(_, _) => (
+                Status::InternalServerError,
+                "Не вдалося оформити замовлення, спробуйте ще раз".into(),
+            ),
+            ApiError::BundleSelection => (
+                Status::BadRequest,
+                "Оберіть розмір для кожного товару комплекту".into(),
+            ),
+            ApiError::OutOfStock(item) => (
+                Status::Conflict,
+                format!("Товару «{}» немає в потрібній кількості", item).into(),
+            ),
         };
 
         let body = serde_json::to_string(&ApiErrorBody {
@@ -0,0 +1,93 @@
+use crate::error::api_error::ApiError;
+use crate::query::pricing::price_history_query::record_sale_price_changes;
+use crate::query::products_components::image_check_query::check_images;
+use crate::query::products_components::recommendation_query::refresh_recommendations;
+use crate::query::products_components::stock_alert_query::{
+    check_low_stock, send_low_stock_digest,
+};
+use crate::query::products_components::stock_subscription_query::notify_back_in_stock;
+use crate::utils::constants::images::{IMAGE_CHECK_QUARANTINE, IMAGE_CHECK_REPORT};
+use crate::utils::constants::jobs::{
+    BACK_IN_STOCK_NOTIFY_INTERVAL, IMAGE_CHECK_INTERVAL, LOW_STOCK_CHECK_INTERVAL,
+    RECOMMENDATIONS_REFRESH_INTERVAL, SALE_PRICE_HISTORY_INTERVAL,
+};
+use crate::utils::env_configuration::CONFIG;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::Duration;
+
+pub fn spawn_jobs(db_pool: &PgPool) {
+    spawn_periodic(
+        "recommendations",
+        RECOMMENDATIONS_REFRESH_INTERVAL,
+        db_pool.clone(),
+        |db_pool| async move { refresh_recommendations(&db_pool).await },
+    );
+    spawn_periodic(
+        "low stock alerts",
+        LOW_STOCK_CHECK_INTERVAL,
+        db_pool.clone(),
+        |db_pool| async move {
+            check_low_stock(&db_pool, None).await?;
+            send_low_stock_digest(&db_pool).await
+        },
+    );
+    spawn_periodic(
+        "back in stock notifications",
+        BACK_IN_STOCK_NOTIFY_INTERVAL,
+        db_pool.clone(),
+        |db_pool| async move { notify_back_in_stock(&db_pool, None).await },
+    );
+    spawn_periodic(
+        "image consistency check",
+        IMAGE_CHECK_INTERVAL,
+        db_pool.clone(),
+        |db_pool| async move {
+            // Deleting is left to an admin; unattended runs only report or quarantine.
+            let action = match CONFIG.get().unwrap().image_check_action.as_str() {
+                IMAGE_CHECK_QUARANTINE => IMAGE_CHECK_QUARANTINE,
+                _ => IMAGE_CHECK_REPORT,
+            };
+            let report = check_images(&db_pool, action).await?;
+            if !report.is_clean() {
+                log::warn!(
+                    "Image check found {} orphaned files, {} missing files and {} detached images",
+                    report.orphaned_files.len(),
+                    report.missing_files.len(),
+                    report.detached_images.len()
+                );
+            }
+            Ok(())
+        },
+    );
+    spawn_periodic(
+        "sale price history",
+        SALE_PRICE_HISTORY_INTERVAL,
+        db_pool.clone(),
+        |db_pool| async move {
+            let mut tx = db_pool.begin().await?;
+            record_sale_price_changes(&mut tx, None).await?;
+            tx.commit().await?;
+            Ok(())
+        },
+    );
+}
+
+/// Runs `job` right away and then every `period`, logging failures instead of
+/// stopping the schedule.
+fn spawn_periodic<F, Fut>(name: &'static str, period: Duration, db_pool: PgPool, job: F)
+where
+    F: Fn(PgPool) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), ApiError>> + Send,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(error) = job(db_pool.clone()).await {
+                log::error!("Scheduled job {} failed: {}", name, error);
+            }
+        }
+    });
+}
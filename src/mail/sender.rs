@@ -1,23 +1,29 @@
-use crate::data::orders::order::OrderDetails;
+use crate::data::orders::order::{OrderDetails, OrderItemDetails};
+use crate::data::products_components::stock_alert::StockAlert;
 use crate::error::api_error::ApiError;
 use crate::utils::constants::routes::MAIN_URL;
 use crate::utils::env_configuration::CONFIG;
-use lettre::message::{Message, SinglePart};
+use lettre::message::{Mailbox, Message, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::transport::smtp::SmtpTransport;
 use lettre::Transport;
 use std::fmt::Write;
 
 pub fn generate_registration_link(token: String) -> String {
+    generate_api_link(&format!("registration?token={}", token))
+}
+
+/// Absolute link to an API route, e.g. for buttons in emails.
+pub fn generate_api_link(path: &str) -> String {
     if CONFIG.get().unwrap().local {
         format!(
-            "http://{}:{}/api/registration?token={}",
+            "http://{}:{}/api/{}",
             CONFIG.get().unwrap().server_address,
             CONFIG.get().unwrap().server_port,
-            token
+            path
         )
     } else {
-        format!("{}/api/registration?token={}", MAIN_URL, token)
+        format!("{}/api/{}", MAIN_URL, path)
     }
 }
 
@@ -110,6 +116,36 @@ pub fn send_mail_registration(to_email: String, active_link: String) -> Result<S
     ))
 }
 
+/// The product name, followed by the components and their sizes for bundles.
+fn product_cell(item: &OrderItemDetails) -> String {
+    let components = item
+        .components
+        .iter()
+        .map(|component| match &component.size {
+            Some(size) => format!(
+                "{} ({}) × {}",
+                escape_html(&component.product_name),
+                escape_html(&size.to_uppercase()),
+                component.quantity
+            ),
+            None => format!(
+                "{} × {}",
+                escape_html(&component.product_name),
+                component.quantity
+            ),
+        })
+        .collect::<Vec<String>>();
+
+    match components.is_empty() {
+        true => escape_html(&item.product_name),
+        false => format!(
+            "{}<br><small>{}</small>",
+            escape_html(&item.product_name),
+            components.join("<br>")
+        ),
+    }
+}
+
 pub fn send_mail_new_order(order_details: OrderDetails) -> Result<String, ApiError> {
     let smtp_address = CONFIG.get().unwrap().smtp_address.as_str();
     let smtp_port: u16 = CONFIG
@@ -130,19 +166,20 @@ pub fn send_mail_new_order(order_details: OrderDetails) -> Result<String, ApiErr
             <td>{}</td>
             <td>{}</td>
             <td>{}</td>
-            <td>{} грн</td>
+            <td>{} {}</td>
         </tr>"#,
-            item.product_name,
+            product_cell(item),
             item.quantity,
-            item.size.clone().unwrap_or_else(|| "N/A".to_string()),
-            item.total_price
+            escape_html(item.size.as_deref().unwrap_or("N/A")),
+            item.total_price,
+            order_details.currency_symbol
         )
         .map_err(|_| ApiError::EmailError)?;
     }
-    let address = format!(
+    let address = escape_html(&format!(
         "{}, {}",
         order_details.shipping.city, order_details.shipping.branch
-    );
+    ));
     let html_content = format!(
         r#"
     <!DOCTYPE html>
@@ -267,25 +304,26 @@ pub fn send_mail_new_order(order_details: OrderDetails) -> Result<String, ApiErr
                         {items}
                     </tbody>
                 </table>
-                <p style="text-align: right; font-size: 18px; margin-top: 20px; color: #FFA500;">
-                    <strong>Загальна сума:</strong> {total_price} грн
+                <p style="text-align: right; margin-top: 20px;">
+                    <strong>Доставка:</strong> {shipping_price} {currency}
+                </p>
+                <p style="text-align: right; font-size: 18px; color: #FFA500;">
+                    <strong>Загальна сума:</strong> {total_price} {currency}
                 </p>
             </div>
         </div>
     </body>
     </html>
     "#,
-        first_name = order_details.shipping.first_name,
-        last_name = order_details.shipping.last_name,
+        first_name = escape_html(&order_details.shipping.first_name),
+        last_name = escape_html(&order_details.shipping.last_name),
         address = address,
-        phone = order_details.shipping.phone_number,
-        email = order_details.shipping.email,
+        phone = escape_html(&order_details.shipping.phone_number),
+        email = escape_html(&order_details.shipping.email),
         items = items_html,
-        total_price = order_details
-            .items
-            .iter()
-            .map(|item| item.total_price)
-            .sum::<f32>(),
+        currency = order_details.currency_symbol,
+        shipping_price = order_details.shipping_price,
+        total_price = order_details.total_price,
     );
 
     let email = Message::builder()
@@ -317,3 +355,224 @@ pub fn send_mail_new_order(order_details: OrderDetails) -> Result<String, ApiErr
         order_details.shipping.email
     ))
 }
+
+pub fn send_mail_low_stock(
+    to_emails: &[String],
+    alerts: &[StockAlert],
+) -> Result<String, ApiError> {
+    let mut items_html = String::new();
+    for alert in alerts {
+        write!(
+            items_html,
+            r#"<tr>
+            <td>{}</td>
+            <td>{}</td>
+            <td>{}</td>
+            <td>{}</td>
+        </tr>"#,
+            escape_html(&alert.product_name),
+            escape_html(&alert.size.to_uppercase()),
+            alert.stock,
+            alert.threshold
+        )
+        .map_err(|_| ApiError::EmailError)?;
+    }
+    let html_content = format!(
+        r#"
+    <!DOCTYPE html>
+    <html>
+    <head>
+        <style>
+            body {{
+                background-color: #1c1c1c;
+                color: #FFA500;
+                font-family: 'Namu', sans-serif;
+                margin: 0;
+                padding: 20px;
+            }}
+            .container {{
+                max-width: 600px;
+                margin: auto;
+                padding: 20px;
+                border-radius: 8px;
+                border: 1px solid #FFA500;
+            }}
+            table {{
+                width: 100%;
+                border-collapse: collapse;
+            }}
+            th, td {{
+                border: 1px solid #FFA500;
+                padding: 8px;
+                text-align: center;
+                color: #e59400;
+            }}
+            th {{
+                background-color: #FFA500;
+                color: #000;
+            }}
+        </style>
+    </head>
+    <body>
+        <div class="container">
+            <h2>Товари закінчуються</h2>
+            <table>
+                <thead>
+                    <tr>
+                        <th>Назва</th>
+                        <th>Розмір</th>
+                        <th>Залишок</th>
+                        <th>Поріг</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {items}
+                </tbody>
+            </table>
+        </div>
+    </body>
+    </html>
+    "#,
+        items = items_html,
+    );
+
+    send_html_mail(
+        to_emails,
+        "Низький залишок товарів - Tyutyun Shop",
+        html_content,
+    )?;
+
+    Ok(format!(
+        "Low stock digest sent successfully to {} administrators",
+        to_emails.len()
+    ))
+}
+
+pub fn send_mail_stock_subscription_confirmation(
+    to_email: &str,
+    product_name: &str,
+    confirm_link: &str,
+) -> Result<String, ApiError> {
+    let html_content = format!(
+        r#"
+    <!DOCTYPE html>
+    <html>
+    <body style="background-color: #1a1a1a; color: #FFA500; font-family: Namu, sans-serif; padding: 20px;">
+        <div style="max-width: 600px; margin: 0 auto;">
+            <h2>Хелоу це Tyuntyun Shop!</h2>
+            <p>Підтвердь, що хочеш дізнатися, коли «{product}» знову буде в наявності:</p>
+            <a href="{link}" style="display: inline-block; padding: 10px 20px; color: #000000; background-color: #FFA500; border-radius: 5px; text-decoration: none;">Підтвердити</a>
+        </div>
+    </body>
+    </html>
+    "#,
+        product = escape_html(product_name),
+        link = confirm_link
+    );
+
+    send_html_mail(
+        &[to_email.to_string()],
+        "Підтвердження підписки - Tyutyun Shop",
+        html_content,
+    )?;
+
+    Ok(format!(
+        "Confirmation email sent successfully to {}",
+        to_email
+    ))
+}
+
+pub fn send_mail_back_in_stock(
+    to_email: &str,
+    product_name: &str,
+    size: &str,
+    unsubscribe_link: &str,
+) -> Result<String, ApiError> {
+    let html_content = format!(
+        r#"
+    <!DOCTYPE html>
+    <html>
+    <body style="background-color: #1a1a1a; color: #FFA500; font-family: Namu, sans-serif; padding: 20px;">
+        <div style="max-width: 600px; margin: 0 auto;">
+            <h2>«{product}» знову в наявності!</h2>
+            <p>Розмір: {size}</p>
+            <a href="{shop}" style="display: inline-block; padding: 10px 20px; color: #000000; background-color: #FFA500; border-radius: 5px; text-decoration: none;">До магазину</a>
+            <p style="font-size: 12px; margin-top: 20px;"><a href="{unsubscribe}" style="color: #e59400;">Відписатися</a></p>
+        </div>
+    </body>
+    </html>
+    "#,
+        product = escape_html(product_name),
+        size = size,
+        shop = MAIN_URL,
+        unsubscribe = unsubscribe_link
+    );
+
+    send_html_mail(
+        &[to_email.to_string()],
+        "Товар знову в наявності - Tyutyun Shop",
+        html_content,
+    )?;
+
+    Ok(format!(
+        "Back in stock email sent successfully to {}",
+        to_email
+    ))
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Sends one message to all of `to_emails`. With several recipients they go in Bcc,
+/// with the shop itself in To, so nobody sees the other addresses.
+fn send_html_mail(
+    to_emails: &[String],
+    subject: &str,
+    html_content: String,
+) -> Result<(), ApiError> {
+    let smtp_address = CONFIG.get().unwrap().smtp_address.as_str();
+    let smtp_port: u16 = CONFIG
+        .get()
+        .unwrap()
+        .smtp_port
+        .parse()
+        .map_err(|_| ApiError::EmailError)?;
+    let username = CONFIG.get().unwrap().mail_username.as_str();
+    let password = CONFIG.get().unwrap().mail_password.as_str();
+
+    let shop: Mailbox = "Tyutyun Shop <tyutyun-shop@yacode.dev>"
+        .parse()
+        .map_err(|_| ApiError::EmailError)?;
+    let mut builder = Message::builder().from(shop.clone());
+    builder = match to_emails {
+        [to_email] => builder.to(to_email.parse().map_err(|_| ApiError::EmailError)?),
+        _ => {
+            builder = builder.to(shop);
+            for to_email in to_emails {
+                builder = builder.bcc(to_email.parse().map_err(|_| ApiError::EmailError)?);
+            }
+            builder
+        }
+    };
+    let email = builder
+        .subject(subject)
+        .singlepart(SinglePart::html(html_content))
+        .map_err(|_| ApiError::EmailError)?;
+
+    let creds = Credentials::new(username.to_string(), password.to_string());
+    let mailer = SmtpTransport::starttls_relay(smtp_address)
+        .map_err(|_| ApiError::EmailError)?
+        .port(smtp_port)
+        .credentials(creds)
+        .build();
+
+    mailer.send(&email).map_err(|_| ApiError::EmailError)?;
+
+    Ok(())
+}
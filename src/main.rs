@@ -3,26 +3,45 @@ extern crate rocket;
 mod data;
 mod database;
 mod error;
+mod jobs;
 mod mail;
 mod query;
 mod server;
+mod storage;
 mod tests;
 mod utils;
 
 use crate::database::init_db_pool;
+use crate::query::products_components::image_check_query::check_images;
 use crate::server::set_up_rocket;
-use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
-use crate::utils::env_configuration::EnvConfiguration;
-use std::fs;
+use crate::storage::init_storage;
+use crate::utils::constants::images::{IMAGE_CHECK_COMMAND, IMAGE_CHECK_REPORT};
+use crate::utils::env_configuration::{EnvConfiguration, CONFIG};
 use std::path::Path;
+use std::{env, fs, process};
 
 #[tokio::main]
 async fn main() {
     EnvConfiguration::init_config();
     let db_pool = init_db_pool().await;
 
-    if !Path::new(PATH_PRODUCT_IMAGES).exists() {
-        fs::create_dir(PATH_PRODUCT_IMAGES).expect("Failed to create images directory");
+    let images_dir = &CONFIG.get().unwrap().images_dir;
+    if !Path::new(images_dir).exists() {
+        fs::create_dir_all(images_dir).expect("Failed to create images directory");
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(IMAGE_CHECK_COMMAND) {
+        init_storage();
+        let action = args.get(1).map_or(IMAGE_CHECK_REPORT, String::as_str);
+        match check_images(&db_pool.unwrap(), action).await {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Err(error) => {
+                eprintln!("Image check failed: {}", error);
+                process::exit(1);
+            }
+        }
+        return;
     }
 
     set_up_rocket(db_pool.unwrap()).await;
@@ -0,0 +1,56 @@
+use crate::error::api_error::ApiError;
+use crate::utils::constants::pricing::LOWEST_PRICE_WINDOW_DAYS;
+use chrono::NaiveDateTime;
+use sqlx::{PgPool, Row};
+
+/// The latest `updated_at` across `tables`, used as the `Last-Modified` of responses
+/// built from them. Sales count from the moment they start or end, because that is
+/// when they change prices, not only when they are edited. Likewise a price change
+/// counts again when it leaves the window of `lowest_price_30d`, and deleting a row
+/// counts as a change too.
+pub async fn last_modified(
+    db_pool: &PgPool,
+    tables: &[&str],
+) -> Result<Option<NaiveDateTime>, ApiError> {
+    let changes = tables
+        .iter()
+        .map(|table| match *table {
+            "product_sales" => r#"
+                (
+                    SELECT MAX(GREATEST(updated_at, starts_at,
+                        CASE WHEN ends_at <= NOW() THEN ends_at END))
+                    FROM product_sales
+                    WHERE starts_at <= NOW()
+                )
+                "#
+            .to_string(),
+            // A change also counts once it leaves the lowest-price window.
+            "price_history" => format!(
+                r#"
+                (
+                    SELECT MAX(GREATEST(changed_at, CASE
+                        WHEN changed_at + make_interval(days => {days}) <= NOW()
+                        THEN changed_at + make_interval(days => {days})
+                    END))
+                    FROM price_history
+                )
+                "#,
+                days = LOWEST_PRICE_WINDOW_DAYS
+            ),
+            table => format!("(SELECT MAX(updated_at) FROM {})", table),
+        })
+        .chain([
+            "(SELECT MAX(deleted_at) FROM table_deletions WHERE table_name = ANY($1))".to_string(),
+        ])
+        .collect::<Vec<String>>();
+
+    let row = sqlx::query(&format!(
+        "SELECT GREATEST(NULL::TIMESTAMP, {}) AS last_modified",
+        changes.join(", ")
+    ))
+    .bind(tables)
+    .fetch_one(db_pool)
+    .await?;
+
+    Ok(row.get("last_modified"))
+}
@@ -0,0 +1 @@
+pub mod cache_query;
@@ -0,0 +1,396 @@
+use crate::data::collections::collection::{
+    Collection, CollectionProduct, CollectionRules, CollectionWithProducts,
+};
+use crate::data::localization::locale::Locale;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::category_query::get_category_subtree_ids;
+use crate::query::products_components::product_image_query::get_primary_images;
+use crate::query::products_components::product_query::products_from_rows;
+use crate::utils::constants::collections::{
+    COLLECTION_MANUAL, COLLECTION_MAX_PRODUCT_LIMIT, COLLECTION_PRODUCT_LIMIT, COLLECTION_RULE,
+    COLLECTION_SORTS, COLLECTION_SORT_BESTSELLING, COLLECTION_SORT_PRICE_ASC,
+    COLLECTION_SORT_PRICE_DESC,
+};
+use crate::utils::constants::orders::ORDER_STATUS_CANCELLED;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::HashMap;
+
+#[post("/collection", data = "<collection>")]
+pub async fn create_collection(
+    db_pool: &State<PgPool>,
+    collection: Json<Collection>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let collection = collection.into_inner();
+    validate_collection(&collection)?;
+
+    let mut tx = db_pool.begin().await?;
+    let id = save_collection(&mut tx, None, &collection).await?;
+    tx.commit().await?;
+
+    Ok(Json(id))
+}
+
+#[put("/collection/update", data = "<collection>")]
+pub async fn update_collection(
+    db_pool: &State<PgPool>,
+    collection: Json<Collection>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let collection = collection.into_inner();
+    let id = collection.id.ok_or(ApiError::BadRequest)?;
+    validate_collection(&collection)?;
+
+    let mut tx = db_pool.begin().await?;
+    save_collection(&mut tx, Some(id), &collection).await?;
+    tx.commit().await?;
+
+    Ok("Collection successfully updated".to_string())
+}
+
+#[delete("/collection/<id>")]
+pub async fn delete_collection(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    sqlx::query("DELETE FROM collections WHERE id = $1")
+        .bind(id)
+        .execute(&**db_pool)
+        .await?;
+
+    Ok("Collection successfully deleted".to_string())
+}
+
+#[get("/collections")]
+pub async fn get_collections(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<Json<Vec<Collection>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    let rows = sqlx::query("SELECT * FROM collections ORDER BY title")
+        .fetch_all(&**db_pool)
+        .await?;
+
+    collections_from_rows(db_pool, rows).await.map(Json)
+}
+
+#[get("/collections/active")]
+pub async fn get_active_collections(
+    db_pool: &State<PgPool>,
+) -> Result<Json<Vec<Collection>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT * FROM collections
+        WHERE (starts_at IS NULL OR starts_at <= NOW())
+            AND (ends_at IS NULL OR ends_at > NOW())
+        ORDER BY title
+        "#,
+    )
+    .fetch_all(&**db_pool)
+    .await?;
+
+    collections_from_rows(db_pool, rows).await.map(Json)
+}
+
+/// A collection that is currently running, with its products priced, translated
+/// and paired with their primary image.
+#[get("/collection/<slug>?<currency>")]
+pub async fn get_collection(
+    db_pool: &State<PgPool>,
+    slug: &str,
+    currency: Option<&str>,
+    locale: Locale,
+) -> Result<Json<CollectionWithProducts>, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT * FROM collections
+        WHERE slug = $1
+            AND (starts_at IS NULL OR starts_at <= NOW())
+            AND (ends_at IS NULL OR ends_at > NOW())
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(&**db_pool)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    let collection = collections_from_rows(db_pool, vec![row])
+        .await?
+        .pop()
+        .ok_or(ApiError::NotFound)?;
+    let rows = resolve_collection_products(db_pool, &collection).await?;
+    let products = products_from_rows(db_pool, rows, currency, None, locale).await?;
+    let mut images = get_primary_images(
+        db_pool,
+        &products
+            .iter()
+            .filter_map(|product| product.id)
+            .collect::<Vec<i32>>(),
+    )
+    .await?;
+
+    Ok(Json(CollectionWithProducts {
+        collection,
+        products: products
+            .into_iter()
+            .map(|product| CollectionProduct {
+                primary_image: product.id.and_then(|id| images.remove(&id)),
+                product,
+            })
+            .collect(),
+    }))
+}
+
+/// Product rows of a collection in display order. Rule prices compare against the
+/// base price, before currency conversion and sales.
+async fn resolve_collection_products(
+    db_pool: &PgPool,
+    collection: &Collection,
+) -> Result<Vec<PgRow>, ApiError> {
+    let limit = collection.product_limit.unwrap_or(COLLECTION_PRODUCT_LIMIT);
+
+    if collection.kind == COLLECTION_MANUAL {
+        let rows = sqlx::query(
+            r#"
+            SELECT p.*
+            FROM collection_products cp
+            JOIN products p ON p.id = cp.product_id
+            WHERE cp.collection_id = $1 AND NOT p.draft
+            ORDER BY cp.position
+            LIMIT $2
+            "#,
+        )
+        .bind(collection.id)
+        .bind(limit)
+        .fetch_all(db_pool)
+        .await?;
+        return Ok(rows);
+    }
+
+    let default_rules = CollectionRules::default();
+    let rules = collection.rules.as_ref().unwrap_or(&default_rules);
+    let category_ids = match rules.category_id {
+        Some(id) => Some(get_category_subtree_ids(db_pool, id).await?),
+        None => None,
+    };
+    let order_by = match collection.sort.as_deref() {
+        Some(COLLECTION_SORT_PRICE_ASC) => "p.price, p.id",
+        Some(COLLECTION_SORT_PRICE_DESC) => "p.price DESC, p.id",
+        Some(COLLECTION_SORT_BESTSELLING) => "COALESCE(sales.sold, 0) DESC, p.id DESC",
+        _ => "p.created_at DESC, p.id DESC",
+    };
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT p.*
+        FROM products p
+        LEFT JOIN (
+            SELECT oi.product_id, SUM(oi.quantity) AS sold
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            WHERE o.status IS DISTINCT FROM $5
+            GROUP BY oi.product_id
+        ) sales ON sales.product_id = p.id
+        WHERE NOT p.draft
+            AND ($1::TEXT IS NULL OR $1 = ANY(p.tags))
+            AND ($2::INT[] IS NULL OR p.category_id = ANY($2))
+            AND ($3::REAL IS NULL OR p.price >= $3)
+            AND ($4::REAL IS NULL OR p.price <= $4)
+        ORDER BY {}
+        LIMIT $6
+        "#,
+        order_by
+    ))
+    .bind(rules.tag.as_ref().map(|tag| tag.trim().to_lowercase()))
+    .bind(category_ids)
+    .bind(rules.min_price)
+    .bind(rules.max_price)
+    .bind(ORDER_STATUS_CANCELLED)
+    .bind(limit)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows)
+}
+
+async fn save_collection(
+    conn: &mut PgConnection,
+    id: Option<i32>,
+    collection: &Collection,
+) -> Result<i32, ApiError> {
+    if let Some(id) = id {
+        sqlx::query("SELECT 1 FROM collections WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    }
+
+    let default_rules = CollectionRules::default();
+    let rules = collection.rules.as_ref().unwrap_or(&default_rules);
+
+    let id: i32 = sqlx::query(
+        r#"
+        INSERT INTO collections (
+            id, slug, title, kind, rule_tag, rule_category_id, rule_min_price, rule_max_price,
+            sort, product_limit, starts_at, ends_at, created_at, updated_at
+        )
+        VALUES (
+            COALESCE($1, nextval('collections_id_seq')::INT), $2, $3, $4, $5, $6, $7, $8,
+            $9, $10, $11, $12, NOW(), NOW()
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            slug = EXCLUDED.slug, title = EXCLUDED.title, kind = EXCLUDED.kind,
+            rule_tag = EXCLUDED.rule_tag, rule_category_id = EXCLUDED.rule_category_id,
+            rule_min_price = EXCLUDED.rule_min_price, rule_max_price = EXCLUDED.rule_max_price,
+            sort = EXCLUDED.sort, product_limit = EXCLUDED.product_limit,
+            starts_at = EXCLUDED.starts_at, ends_at = EXCLUDED.ends_at, updated_at = NOW()
+        RETURNING id
+        "#,
+    )
+    .bind(id)
+    .bind(&collection.slug)
+    .bind(&collection.title)
+    .bind(&collection.kind)
+    .bind(rules.tag.as_ref().map(|tag| tag.trim().to_lowercase()))
+    .bind(rules.category_id)
+    .bind(rules.min_price)
+    .bind(rules.max_price)
+    .bind(&collection.sort)
+    .bind(collection.product_limit)
+    .bind(collection.starts_at)
+    .bind(collection.ends_at)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|error| match &error {
+        // Only the slug can collide; conflicts on `id` are upserts.
+        sqlx::Error::Database(db_error) if db_error.is_unique_violation() => ApiError::SlugTaken,
+        _ => ApiError::DatabaseError(error),
+    })?
+    .get("id");
+
+    sqlx::query("DELETE FROM collection_products WHERE collection_id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    if collection.kind == COLLECTION_MANUAL {
+        sqlx::query(
+            r#"
+            INSERT INTO collection_products (collection_id, product_id, position)
+            SELECT $1, product_id, MIN(position)
+            FROM UNNEST($2::INT[]) WITH ORDINALITY AS ids(product_id, position)
+            WHERE EXISTS (SELECT 1 FROM products WHERE id = ids.product_id)
+            GROUP BY product_id
+            "#,
+        )
+        .bind(id)
+        .bind(collection.product_ids.as_deref().unwrap_or_default())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(id)
+}
+
+async fn collections_from_rows(
+    db_pool: &PgPool,
+    rows: Vec<PgRow>,
+) -> Result<Vec<Collection>, ApiError> {
+    let ids = rows.iter().map(|row| row.get("id")).collect::<Vec<i32>>();
+    let mut product_ids: HashMap<i32, Vec<i32>> = HashMap::new();
+    for row in sqlx::query(
+        r#"
+        SELECT collection_id, product_id
+        FROM collection_products
+        WHERE collection_id = ANY($1)
+        ORDER BY collection_id, position
+        "#,
+    )
+    .bind(&ids)
+    .fetch_all(db_pool)
+    .await?
+    {
+        product_ids
+            .entry(row.get("collection_id"))
+            .or_default()
+            .push(row.get("product_id"));
+    }
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let id: i32 = row.get("id");
+            let kind: String = row.get("kind");
+            let manual = kind == COLLECTION_MANUAL;
+            Collection {
+                id: Some(id),
+                slug: row.get("slug"),
+                title: row.get("title"),
+                product_ids: manual.then(|| product_ids.remove(&id).unwrap_or_default()),
+                rules: (!manual).then(|| CollectionRules {
+                    tag: row.get("rule_tag"),
+                    category_id: row.get("rule_category_id"),
+                    min_price: row.get("rule_min_price"),
+                    max_price: row.get("rule_max_price"),
+                }),
+                kind,
+                sort: row.get("sort"),
+                product_limit: row.get("product_limit"),
+                starts_at: row.get("starts_at"),
+                ends_at: row.get("ends_at"),
+            }
+        })
+        .collect())
+}
+
+fn validate_collection(collection: &Collection) -> Result<(), ApiError> {
+    let slug_valid = !collection.slug.is_empty()
+        && collection
+            .slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    let kind_valid =
+        match collection.kind.as_str() {
+            COLLECTION_MANUAL => collection.product_ids.is_some(),
+            COLLECTION_RULE => collection.rules.as_ref().is_none_or(|rules| {
+                match (rules.min_price, rules.max_price) {
+                    (Some(min), Some(max)) => min <= max,
+                    _ => true,
+                }
+            }),
+            _ => false,
+        };
+    let sort_valid = collection
+        .sort
+        .as_deref()
+        .is_none_or(|sort| COLLECTION_SORTS.contains(&sort));
+    let limit_valid = collection
+        .product_limit
+        .is_none_or(|limit| (1..=COLLECTION_MAX_PRODUCT_LIMIT).contains(&limit));
+    let dates_valid = match (collection.starts_at, collection.ends_at) {
+        (Some(starts_at), Some(ends_at)) => starts_at < ends_at,
+        _ => true,
+    };
+
+    if !slug_valid
+        || collection.title.trim().is_empty()
+        || !kind_valid
+        || !sort_valid
+        || !limit_valid
+        || !dates_valid
+    {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(())
+}
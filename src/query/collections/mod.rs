@@ -0,0 +1 @@
+pub mod collection_query;
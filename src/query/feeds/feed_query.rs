@@ -0,0 +1,402 @@
+use crate::data::feeds::feed::{FeedCache, FeedCatalog, FeedCategory, FeedItem};
+use crate::data::localization::locale::Locale;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::bundle_query::load_bundles;
+use crate::query::products_components::product_image_query::get_product_galleries;
+use crate::query::products_components::product_query::products_from_rows;
+use crate::storage::storage;
+use crate::utils::constants::feeds::{
+    CATEGORY_PAGE, FEED_GOOGLE, FEED_SITEMAP, FEED_YML, GOOGLE_FEED_MAX_ADDITIONAL_IMAGES,
+    PRODUCT_PAGE, SHOP_NAME,
+};
+use crate::utils::constants::locales::DEFAULT_LOCALE;
+use crate::utils::constants::pricing::BASE_CURRENCY;
+use crate::utils::constants::routes::MAIN_URL;
+use chrono::{NaiveDateTime, Utc};
+use rocket::http::ContentType;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Changes whenever anything the feeds show changes: rows added, edited or deleted
+/// in the catalog tables, stock moving, or a sale starting or ending.
+const CATALOG_FINGERPRINT: &str = r#"
+    SELECT concat_ws('|',
+        (SELECT COUNT(*) || ':' || COALESCE(MAX(updated_at)::TEXT, '') FROM products),
+        (SELECT COUNT(*) || ':' || COALESCE(MAX(updated_at)::TEXT, '') FROM categories),
+        (SELECT COUNT(*) || ':' || COALESCE(MAX(updated_at)::TEXT, '') FROM product_images),
+        (SELECT COUNT(*) || ':' || COALESCE(MAX(updated_at)::TEXT, '') FROM product_sizes),
+        (
+            SELECT COALESCE(string_agg(id::TEXT, ',' ORDER BY id), '')
+            FROM product_sales
+            WHERE starts_at <= NOW() AND (ends_at IS NULL OR ends_at > NOW())
+        )
+    ) AS fingerprint
+"#;
+
+#[get("/feeds/sitemap.xml")]
+pub async fn get_sitemap(
+    db_pool: &State<PgPool>,
+    cache: &State<FeedCache>,
+) -> Result<(ContentType, String), ApiError> {
+    cached_feed(db_pool, cache, FEED_SITEMAP, build_sitemap).await
+}
+
+#[get("/feeds/google.xml")]
+pub async fn get_google_feed(
+    db_pool: &State<PgPool>,
+    cache: &State<FeedCache>,
+) -> Result<(ContentType, String), ApiError> {
+    cached_feed(db_pool, cache, FEED_GOOGLE, build_google_feed).await
+}
+
+#[get("/feeds/yml.xml")]
+pub async fn get_yml_feed(
+    db_pool: &State<PgPool>,
+    cache: &State<FeedCache>,
+) -> Result<(ContentType, String), ApiError> {
+    cached_feed(db_pool, cache, FEED_YML, build_yml_feed).await
+}
+
+async fn cached_feed(
+    db_pool: &PgPool,
+    cache: &FeedCache,
+    name: &'static str,
+    build: fn(&FeedCatalog) -> String,
+) -> Result<(ContentType, String), ApiError> {
+    let fingerprint: String = sqlx::query(CATALOG_FINGERPRINT)
+        .fetch_one(db_pool)
+        .await?
+        .get("fingerprint");
+
+    // Signed image URLs expire, so a feed that embeds them is rebuilt on every request.
+    let cacheable = !storage().urls_expire();
+    if let Some(body) = cache.get(name, &fingerprint).filter(|_| cacheable) {
+        return Ok((ContentType::XML, body));
+    }
+
+    let body = build(&load_catalog(db_pool).await?);
+    if cacheable {
+        cache.insert(name, fingerprint, body.clone());
+    }
+
+    Ok((ContentType::XML, body))
+}
+
+async fn load_catalog(db_pool: &PgPool) -> Result<FeedCatalog, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT p.*,
+            COALESCE(ps.single_size, 0) + COALESCE(ps.s, 0) + COALESCE(ps.m, 0)
+                + COALESCE(ps.l, 0) + COALESCE(ps.xl, 0) + COALESCE(ps.xxl, 0) > 0 AS in_stock
+        FROM products p
+        LEFT JOIN product_sizes ps ON ps.product_id = p.id
+        WHERE NOT p.draft
+        ORDER BY p.id
+        "#,
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let stock: HashMap<i32, (bool, Option<NaiveDateTime>)> = rows
+        .iter()
+        .map(|row| (row.get("id"), (row.get("in_stock"), row.get("updated_at"))))
+        .collect();
+    let ids: Vec<i32> = stock.keys().copied().collect();
+    let mut galleries = get_product_galleries(db_pool, &ids).await?;
+    let bundles = load_bundles(db_pool, &ids).await?;
+    let products = products_from_rows(db_pool, rows, None, None, Locale(DEFAULT_LOCALE)).await?;
+
+    let items = products
+        .into_iter()
+        .map(|product| {
+            let id = product.id.unwrap_or_default();
+            let (in_stock, updated_at) = stock.get(&id).copied().unwrap_or_default();
+            let in_stock = bundles
+                .get(&id)
+                .map_or(in_stock, |bundle| bundle.available > 0);
+            FeedItem {
+                image_urls: galleries
+                    .remove(&id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|image| absolute_url(&image.image_url))
+                    .collect(),
+                product,
+                in_stock,
+                updated_at,
+            }
+        })
+        .collect();
+
+    let categories = sqlx::query("SELECT id, parent_id, name FROM categories ORDER BY id")
+        .fetch_all(db_pool)
+        .await?
+        .iter()
+        .map(|row| FeedCategory {
+            id: row.get("id"),
+            parent_id: row.get("parent_id"),
+            name: row.get("name"),
+        })
+        .collect();
+
+    Ok(FeedCatalog { items, categories })
+}
+
+fn build_sitemap(catalog: &FeedCatalog) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+    );
+    let _ = writeln!(xml, "  <url><loc>{}</loc></url>", escape(MAIN_URL));
+    for category in catalog.categories.iter() {
+        let _ = writeln!(
+            xml,
+            "  <url><loc>{}</loc></url>",
+            escape(&category_url(category.id))
+        );
+    }
+    for item in catalog.items.iter() {
+        let _ = write!(
+            xml,
+            "  <url><loc>{}</loc>",
+            escape(&product_url(item.product.id.unwrap_or_default()))
+        );
+        if let Some(updated_at) = item.updated_at {
+            let _ = write!(xml, "<lastmod>{}</lastmod>", updated_at.format("%Y-%m-%d"));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// RSS 2.0 feed with the `g:` attributes Google Merchant Center expects.
+fn build_google_feed(catalog: &FeedCatalog) -> String {
+    let paths = category_paths(&catalog.categories);
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:g="http://base.google.com/ns/1.0">
+<channel>
+  <title>{shop}</title>
+  <link>{url}</link>
+  <description>{shop}</description>
+"#,
+        shop = escape(SHOP_NAME),
+        url = escape(MAIN_URL)
+    );
+
+    for item in catalog.items.iter() {
+        let product = &item.product;
+        let (regular_price, sale_price) = feed_prices(item);
+        let id = product.id.unwrap_or_default();
+
+        xml.push_str("  <item>\n");
+        let _ = writeln!(xml, "    <g:id>{}</g:id>", id);
+        let _ = writeln!(xml, "    <g:title>{}</g:title>", escape(&product.name));
+        let _ = writeln!(
+            xml,
+            "    <g:description>{}</g:description>",
+            escape(product.description.as_deref().unwrap_or(&product.name))
+        );
+        let _ = writeln!(xml, "    <g:link>{}</g:link>", escape(&product_url(id)));
+        let mut images = item.image_urls.iter();
+        if let Some(image_url) = images.next() {
+            let _ = writeln!(
+                xml,
+                "    <g:image_link>{}</g:image_link>",
+                escape(image_url)
+            );
+        }
+        for image_url in images.take(GOOGLE_FEED_MAX_ADDITIONAL_IMAGES) {
+            let _ = writeln!(
+                xml,
+                "    <g:additional_image_link>{}</g:additional_image_link>",
+                escape(image_url)
+            );
+        }
+        let _ = writeln!(
+            xml,
+            "    <g:availability>{}</g:availability>",
+            match item.in_stock {
+                true => "in_stock",
+                false => "out_of_stock",
+            }
+        );
+        let _ = writeln!(
+            xml,
+            "    <g:price>{:.2} {}</g:price>",
+            regular_price, BASE_CURRENCY
+        );
+        if let Some(sale_price) = sale_price {
+            let _ = writeln!(
+                xml,
+                "    <g:sale_price>{:.2} {}</g:sale_price>",
+                sale_price, BASE_CURRENCY
+            );
+        }
+        if let Some(path) = product.category_id.and_then(|id| paths.get(&id)) {
+            let _ = writeln!(
+                xml,
+                "    <g:product_type>{}</g:product_type>",
+                escape(&path.join(" > "))
+            );
+        }
+        let _ = writeln!(xml, "    <g:brand>{}</g:brand>", escape(SHOP_NAME));
+        xml.push_str("    <g:condition>new</g:condition>\n");
+        xml.push_str("    <g:identifier_exists>no</g:identifier_exists>\n");
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// YML (Yandex Market Language) catalog, the import format of Prom.ua and Rozetka.
+fn build_yml_feed(catalog: &FeedCatalog) -> String {
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<yml_catalog date="{date}">
+<shop>
+  <name>{shop}</name>
+  <company>{shop}</company>
+  <url>{url}</url>
+  <currencies>
+    <currency id="{currency}" rate="1"/>
+  </currencies>
+  <categories>
+"#,
+        date = Utc::now().format("%Y-%m-%d %H:%M"),
+        shop = escape(SHOP_NAME),
+        url = escape(MAIN_URL),
+        currency = BASE_CURRENCY
+    );
+
+    for category in catalog.categories.iter() {
+        let _ = match category.parent_id {
+            Some(parent_id) => writeln!(
+                xml,
+                r#"    <category id="{}" parentId="{}">{}</category>"#,
+                category.id,
+                parent_id,
+                escape(&category.name)
+            ),
+            None => writeln!(
+                xml,
+                r#"    <category id="{}">{}</category>"#,
+                category.id,
+                escape(&category.name)
+            ),
+        };
+    }
+    xml.push_str("  </categories>\n  <offers>\n");
+
+    for item in catalog.items.iter() {
+        let product = &item.product;
+        let (regular_price, sale_price) = feed_prices(item);
+        let id = product.id.unwrap_or_default();
+
+        let _ = writeln!(
+            xml,
+            r#"    <offer id="{}" available="{}">"#,
+            id, item.in_stock
+        );
+        let _ = writeln!(xml, "      <url>{}</url>", escape(&product_url(id)));
+        match sale_price {
+            Some(sale_price) => {
+                let _ = writeln!(xml, "      <price>{:.2}</price>", sale_price);
+                let _ = writeln!(xml, "      <oldprice>{:.2}</oldprice>", regular_price);
+            }
+            None => {
+                let _ = writeln!(xml, "      <price>{:.2}</price>", regular_price);
+            }
+        }
+        let _ = writeln!(xml, "      <currencyId>{}</currencyId>", BASE_CURRENCY);
+        if let Some(category_id) = product.category_id {
+            let _ = writeln!(xml, "      <categoryId>{}</categoryId>", category_id);
+        }
+        for image_url in item.image_urls.iter() {
+            let _ = writeln!(xml, "      <picture>{}</picture>", escape(image_url));
+        }
+        let _ = writeln!(xml, "      <name>{}</name>", escape(&product.name));
+        let _ = writeln!(xml, "      <vendor>{}</vendor>", escape(SHOP_NAME));
+        if let Some(description) = product.description.as_deref() {
+            let _ = writeln!(
+                xml,
+                "      <description>{}</description>",
+                escape(description)
+            );
+        }
+        xml.push_str("    </offer>\n");
+    }
+
+    xml.push_str("  </offers>\n</shop>\n</yml_catalog>\n");
+    xml
+}
+
+/// The regular price and, while the product is discounted, the price it sells for.
+fn feed_prices(item: &FeedItem) -> (f32, Option<f32>) {
+    let product = &item.product;
+    let active_price = product.active_price.unwrap_or(product.price);
+    let regular_price = product
+        .compare_at_price
+        .filter(|compare_at| *compare_at > active_price)
+        .unwrap_or(product.price.max(active_price));
+
+    (
+        regular_price,
+        (active_price < regular_price).then_some(active_price),
+    )
+}
+
+/// Category names from the root down to each category.
+fn category_paths(categories: &[FeedCategory]) -> HashMap<i32, Vec<String>> {
+    let by_id: HashMap<i32, &FeedCategory> = categories
+        .iter()
+        .map(|category| (category.id, category))
+        .collect();
+
+    categories
+        .iter()
+        .map(|category| {
+            let mut path = vec![category.name.clone()];
+            let mut parent_id = category.parent_id;
+            while let Some(parent) = parent_id.and_then(|id| by_id.get(&id)) {
+                if path.len() > categories.len() {
+                    break;
+                }
+                path.push(parent.name.clone());
+                parent_id = parent.parent_id;
+            }
+            path.reverse();
+            (category.id, path)
+        })
+        .collect()
+}
+
+fn product_url(id: i32) -> String {
+    format!("{}/{}/{}", MAIN_URL, PRODUCT_PAGE, id)
+}
+
+fn category_url(id: i32) -> String {
+    format!("{}/{}/{}", MAIN_URL, CATEGORY_PAGE, id)
+}
+
+/// Local storage hands out paths relative to the site; feeds need full URLs.
+fn absolute_url(url: &str) -> String {
+    match url.starts_with('/') {
+        true => format!("{}{}", MAIN_URL, url),
+        false => url.to_string(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1 @@
+pub mod translation_query;
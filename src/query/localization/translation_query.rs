@@ -0,0 +1,375 @@
+use crate::data::localization::locale::Locale;
+use crate::data::localization::translation::{MissingTranslation, Translation};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::utils::constants::locales::{DEFAULT_LOCALE, SUPPORTED_LOCALES};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+/// Text that can be translated. The default locale lives in the entity's own
+/// `name`/`description` columns, every other locale in its translations table.
+#[derive(Clone, Copy)]
+enum Entity {
+    Product,
+    Category,
+}
+
+impl Entity {
+    fn name(self) -> &'static str {
+        match self {
+            Entity::Product => "product",
+            Entity::Category => "category",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Entity::Product => "products",
+            Entity::Category => "categories",
+        }
+    }
+
+    fn translations_table(self) -> &'static str {
+        match self {
+            Entity::Product => "product_translations",
+            Entity::Category => "category_translations",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Entity::Product => "product_id",
+            Entity::Category => "category_id",
+        }
+    }
+}
+
+#[get("/product/<id>/translations")]
+pub async fn get_product_translations(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<Json<Vec<Translation>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    get_translations(db_pool, Entity::Product, id)
+        .await
+        .map(Json)
+}
+
+#[put("/product/<id>/translations/<locale>", data = "<translation>")]
+pub async fn set_product_translation(
+    db_pool: &State<PgPool>,
+    id: i32,
+    locale: &str,
+    translation: Json<Translation>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    set_translation(
+        db_pool,
+        Entity::Product,
+        id,
+        locale,
+        translation.into_inner(),
+    )
+    .await?;
+
+    Ok("Translation successfully saved".to_string())
+}
+
+#[delete("/product/<id>/translations/<locale>")]
+pub async fn delete_product_translation(
+    db_pool: &State<PgPool>,
+    id: i32,
+    locale: &str,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    delete_translation(db_pool, Entity::Product, id, locale).await?;
+
+    Ok("Translation successfully deleted".to_string())
+}
+
+#[get("/category/<id>/translations")]
+pub async fn get_category_translations(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<Json<Vec<Translation>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    get_translations(db_pool, Entity::Category, id)
+        .await
+        .map(Json)
+}
+
+#[put("/category/<id>/translations/<locale>", data = "<translation>")]
+pub async fn set_category_translation(
+    db_pool: &State<PgPool>,
+    id: i32,
+    locale: &str,
+    translation: Json<Translation>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    set_translation(
+        db_pool,
+        Entity::Category,
+        id,
+        locale,
+        translation.into_inner(),
+    )
+    .await?;
+
+    Ok("Translation successfully saved".to_string())
+}
+
+#[delete("/category/<id>/translations/<locale>")]
+pub async fn delete_category_translation(
+    db_pool: &State<PgPool>,
+    id: i32,
+    locale: &str,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    delete_translation(db_pool, Entity::Category, id, locale).await?;
+
+    Ok("Translation successfully deleted".to_string())
+}
+
+/// Lists products and categories with an untranslated name, or an untranslated
+/// description where the default locale has one.
+#[get("/translations/missing?<locale>")]
+pub async fn get_missing_translations(
+    db_pool: &State<PgPool>,
+    locale: Option<&str>,
+    claims: Claims,
+) -> Result<Json<Vec<MissingTranslation>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let locales = match locale {
+        Some(locale) => vec![translated_locale(locale)?],
+        None => SUPPORTED_LOCALES
+            .iter()
+            .filter(|locale| **locale != DEFAULT_LOCALE)
+            .copied()
+            .collect(),
+    };
+
+    let mut missing = Vec::new();
+    for entity in [Entity::Category, Entity::Product] {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT * FROM (
+                SELECT
+                    e.id,
+                    e.name,
+                    l.locale,
+                    COALESCE(t.name, '') = '' AS name_missing,
+                    COALESCE(e.description, '') != ''
+                        AND COALESCE(t.description, '') = '' AS description_missing
+                FROM {table} e
+                CROSS JOIN UNNEST($1::TEXT[]) AS l(locale)
+                LEFT JOIN {translations} t ON t.{key} = e.id AND t.locale = l.locale
+            ) checked
+            WHERE name_missing OR description_missing
+            ORDER BY id, locale
+            "#,
+            table = entity.table(),
+            translations = entity.translations_table(),
+            key = entity.key(),
+        ))
+        .bind(&locales)
+        .fetch_all(&**db_pool)
+        .await?;
+
+        missing.extend(rows.into_iter().map(|row| {
+            let fields = [
+                ("name", "name_missing"),
+                ("description", "description_missing"),
+            ]
+            .into_iter()
+            .filter(|(_, column)| row.get::<bool, &str>(column))
+            .map(|(field, _)| field.to_string())
+            .collect();
+            MissingTranslation {
+                entity: entity.name().to_string(),
+                id: row.get("id"),
+                name: row.get("name"),
+                locale: row.get("locale"),
+                fields,
+            }
+        }));
+    }
+
+    Ok(Json(missing))
+}
+
+/// Translated product text in `locale`, keyed by product id. Empty for the default
+/// locale, whose text is already on the product row.
+pub async fn get_product_text(
+    db_pool: &PgPool,
+    product_ids: &[i32],
+    locale: Locale,
+) -> Result<HashMap<i32, Translation>, ApiError> {
+    if locale.is_default() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT product_id, NULLIF(name, '') AS name, NULLIF(description, '') AS description
+        FROM product_translations
+        WHERE product_id = ANY($1) AND locale = $2
+        "#,
+    )
+    .bind(product_ids)
+    .bind(locale.0)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("product_id"),
+                Translation {
+                    locale: Some(locale.0.to_string()),
+                    name: row.get("name"),
+                    description: row.get("description"),
+                },
+            )
+        })
+        .collect())
+}
+
+async fn get_translations(
+    db_pool: &PgPool,
+    entity: Entity,
+    id: i32,
+) -> Result<Vec<Translation>, ApiError> {
+    let base = sqlx::query(&format!(
+        "SELECT name, description FROM {} WHERE id = $1",
+        entity.table()
+    ))
+    .bind(id)
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    let mut translated = sqlx::query(&format!(
+        "SELECT locale, name, description FROM {} WHERE {} = $1",
+        entity.translations_table(),
+        entity.key()
+    ))
+    .bind(id)
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        (
+            row.get::<String, &str>("locale"),
+            (row.get("name"), row.get("description")),
+        )
+    })
+    .collect::<HashMap<String, (Option<String>, Option<String>)>>();
+
+    Ok(SUPPORTED_LOCALES
+        .iter()
+        .map(|locale| {
+            let (name, description) = if *locale == DEFAULT_LOCALE {
+                (base.get("name"), base.get("description"))
+            } else {
+                translated.remove(*locale).unwrap_or_default()
+            };
+            Translation {
+                locale: Some(locale.to_string()),
+                name,
+                description,
+            }
+        })
+        .collect())
+}
+
+async fn set_translation(
+    db_pool: &PgPool,
+    entity: Entity,
+    id: i32,
+    locale: &str,
+    translation: Translation,
+) -> Result<(), ApiError> {
+    let locale = Locale::parse(locale).ok_or(ApiError::BadRequest)?;
+    let name = translation
+        .name
+        .filter(|name| !name.trim().is_empty())
+        .ok_or(ApiError::BadRequest)?;
+
+    let updated = if locale == DEFAULT_LOCALE {
+        sqlx::query(&format!(
+            r#"
+            UPDATE {} SET name = $2, description = $3, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            entity.table()
+        ))
+        .bind(id)
+        .bind(&name)
+        .bind(&translation.description)
+        .execute(db_pool)
+        .await?
+        .rows_affected()
+    } else {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {translations} ({key}, locale, name, description, updated_at)
+            SELECT id, $2, $3, $4, NOW() FROM {table} WHERE id = $1
+            ON CONFLICT ({key}, locale)
+            DO UPDATE SET name = EXCLUDED.name, description = EXCLUDED.description,
+                updated_at = NOW()
+            "#,
+            translations = entity.translations_table(),
+            table = entity.table(),
+            key = entity.key(),
+        ))
+        .bind(id)
+        .bind(locale)
+        .bind(&name)
+        .bind(&translation.description)
+        .execute(db_pool)
+        .await?
+        .rows_affected()
+    };
+
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+async fn delete_translation(
+    db_pool: &PgPool,
+    entity: Entity,
+    id: i32,
+    locale: &str,
+) -> Result<(), ApiError> {
+    let locale = translated_locale(locale)?;
+
+    sqlx::query(&format!(
+        "DELETE FROM {} WHERE {} = $1 AND locale = $2",
+        entity.translations_table(),
+        entity.key()
+    ))
+    .bind(id)
+    .bind(locale)
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
+/// A supported locale other than the default one, which has no translation rows.
+fn translated_locale(locale: &str) -> Result<&'static str, ApiError> {
+    Locale::parse(locale)
+        .filter(|locale| *locale != DEFAULT_LOCALE)
+        .ok_or(ApiError::BadRequest)
+}
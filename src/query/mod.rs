@@ -1,4 +1,11 @@
+pub mod caching;
+pub mod collections;
+pub mod feeds;
+pub mod localization;
 pub mod orders;
 pub mod payment;
+pub mod pricing;
 pub mod products_components;
+pub mod reviews;
 pub mod user;
+pub mod wishlists;
@@ -1,64 +1,208 @@
-use crate::data::orders::order::{DataOrder, Order, OrderDetails, OrderItemDetails};
+use crate::data::orders::order::{
+    DataOrder, Order, OrderDetails, OrderItemComponent, OrderItemDetails,
+};
 use crate::data::orders::shipping::Shipping;
+use crate::data::pricing::currency::Currency;
 use crate::data::user_components::claims::Claims;
 use crate::error::api_error::ApiError;
 use crate::mail::sender::send_mail_new_order;
-use crate::query::orders::shipping_query::{add_shipping, get_shipping_by_id};
+use crate::query::orders::shipping_query::{add_shipping, get_shipping_by_id, validate_shipping};
+use crate::query::pricing::currency_query::find_currency;
+use crate::query::pricing::sale_query::get_checkout_price;
+use crate::query::products_components::bundle_query::bundle_stock_lines;
+use crate::query::products_components::stock_query::{release_stock, reserve_stock};
+use crate::query::products_components::stock_subscription_query::spawn_back_in_stock_notifications;
+use crate::utils::constants::orders::{
+    ORDER_STATUSES, ORDER_STATUS_CANCELLED, ORDER_STATUS_PENDING, ORDER_TOTAL_TOLERANCE,
+};
+use crate::utils::env_configuration::CONFIG;
 use rocket::serde::json::Json;
 use rocket::State;
 use serde_json::Value;
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 
+/// Places an order in one transaction: the order row, its items, the stock they take
+/// and the shipping address are all written or none are, at prices read under the
+/// same transaction. The confirmation email is
+/// sent only after commit, and its failure does not undo the order.
 #[post("/order", data = "<data_order>")]
 pub async fn place_new_order(
     db_pool: &State<PgPool>,
     data_order: Json<DataOrder>,
 ) -> Result<Json<Option<i32>>, ApiError> {
     let mut data_order = data_order.into_inner();
+    validate_shipping(&data_order.shipping)?;
+    let currency = find_currency(db_pool, data_order.order.currency.as_deref()).await?;
 
-    let id: Option<i32> = sqlx::query(
+    let mut tx = db_pool.begin().await?;
+    let shipping_price = price_order(&mut tx, &mut data_order, &currency).await?;
+
+    let mut bundle_lines = Vec::new();
+    for item in data_order.order_items.iter() {
+        bundle_lines.push(bundle_stock_lines(&mut tx, item.product_id, &item.components).await?);
+    }
+    let mut stock_lines = data_order
+        .order_items
+        .iter()
+        .zip(bundle_lines.iter())
+        .flat_map(|(item, lines)| match lines {
+            Some(lines) => lines
+                .iter()
+                .map(|(product_id, size, quantity)| {
+                    (*product_id, size.clone(), quantity * item.quantity)
+                })
+                .collect(),
+            None => vec![(item.product_id, item.size.clone(), item.quantity)],
+        })
+        .collect::<Vec<(i32, Option<String>, i32)>>();
+    // Lock stock rows in a stable order so concurrent checkouts cannot deadlock.
+    stock_lines.sort();
+
+    let id: i32 = sqlx::query(
         r#"
             INSERT INTO orders (
-                user_id, total_price, status, online_payment, created_at, updated_at
+                user_id, total_price, status, online_payment, currency, exchange_rate,
+                shipping_price, stock_reserved, created_at, updated_at
             )
-            VALUES ($1, $2, $3, $4, NOW(), NOW())
+            VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE, NOW(), NOW())
             RETURNING id
         "#,
     )
     .bind(data_order.order.user_id)
     .bind(data_order.order.total_price)
-    .bind(data_order.order.status)
+    .bind(ORDER_STATUS_PENDING)
     .bind(data_order.order.online_payment)
-    .fetch_one(&**db_pool)
+    .bind(&currency.code)
+    .bind(currency.rate)
+    .bind(shipping_price)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(ApiError::DatabaseError)?
+    .map_err(|error| ApiError::OrderFailed("order", error))?
     .get("id");
 
-    for item in data_order.order_items.into_iter() {
-        sqlx::query(
+    for (product_id, size, quantity) in stock_lines.iter() {
+        reserve_stock(
+            &mut tx,
+            *product_id,
+            size.as_deref(),
+            *quantity,
+            id,
+            data_order.order.user_id,
+        )
+        .await?;
+    }
+
+    for (item, lines) in data_order.order_items.into_iter().zip(bundle_lines) {
+        let item_id: i32 = sqlx::query(
             r#"
             INSERT INTO order_items (
                 order_id, product_id, quantity, price, size
             )
             VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
         "#,
         )
         .bind(id)
         .bind(item.product_id)
         .bind(item.quantity)
         .bind(item.price)
-        .bind(item.size)
-        .execute(&**db_pool)
+        .bind(lines.is_none().then_some(item.size).flatten())
+        .fetch_one(&mut *tx)
         .await
-        .map_err(ApiError::DatabaseError)?;
+        .map_err(|error| ApiError::OrderFailed("items", error))?
+        .get("id");
+
+        for (product_id, size, quantity) in lines.into_iter().flatten() {
+            sqlx::query(
+                r#"
+                INSERT INTO order_item_components (order_item_id, product_id, size, quantity)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(item_id)
+            .bind(product_id)
+            .bind(size)
+            .bind(quantity * item.quantity)
+            .execute(&mut *tx)
+            .await
+            .map_err(|error| ApiError::OrderFailed("items", error))?;
+        }
     }
-    data_order.shipping.order_id = id.unwrap();
-    add_shipping(db_pool, Json(data_order.shipping)).await?;
 
-    send_mail_new_order(get_order_details(db_pool, id.unwrap()).await?.into_inner())?;
+    data_order.shipping.order_id = id;
+    add_shipping(&mut tx, &data_order.shipping)
+        .await
+        .map_err(|error| ApiError::OrderFailed("shipping", error))?;
+    tx.commit()
+        .await
+        .map_err(|error| ApiError::OrderFailed("commit", error))?;
+
+    let sent = match get_order_details(db_pool, id).await {
+        Ok(details) => send_mail_new_order(details.into_inner()).map(|_| ()),
+        Err(error) => Err(error),
+    };
+    if let Err(error) = sent {
+        log::error!("Confirmation email for order {} failed: {}", id, error);
+    }
 
-    Ok(Json(id))
+    Ok(Json(Some(id)))
 }
+
+/// Prices every item from the catalog, with active sales applied, and totals the
+/// order including shipping. Prices sent by the client are overwritten; a supplied
+/// `expected_total` that differs from the result rejects the order. Returns the
+/// shipping price.
+async fn price_order(
+    conn: &mut PgConnection,
+    data_order: &mut DataOrder,
+    currency: &Currency,
+) -> Result<f32, ApiError> {
+    if data_order.order_items.is_empty()
+        || data_order.order_items.iter().any(|item| item.quantity <= 0)
+    {
+        return Err(ApiError::BadRequest);
+    }
+
+    for item in data_order.order_items.iter_mut() {
+        // Bundles are priced as a whole; their sizes belong to the components.
+        let size = match item.components.is_empty() {
+            true => item.size.as_deref(),
+            false => None,
+        };
+        item.price = get_checkout_price(conn, item.product_id, size, currency)
+            .await
+            .map_err(|error| match error {
+                ApiError::NotFound => ApiError::ProductNotFound(item.product_id),
+                error => error,
+            })?;
+        item.total_price = item.price * item.quantity as f32;
+    }
+    let items_total: f32 = data_order
+        .order_items
+        .iter()
+        .map(|item| item.total_price)
+        .sum();
+
+    let config = CONFIG.get().unwrap();
+    let free_shipping = config
+        .free_shipping_threshold
+        .is_some_and(|threshold| items_total >= currency.convert(threshold));
+    let shipping_price = match free_shipping {
+        true => 0.0,
+        false => currency.convert(config.shipping_price),
+    };
+
+    let total = ((items_total + shipping_price) * 100.0).round() / 100.0;
+    if let Some(expected_total) = data_order.expected_total {
+        if (expected_total - total).abs() >= ORDER_TOTAL_TOLERANCE {
+            return Err(ApiError::PriceMismatch(total));
+        }
+    }
+    data_order.order.total_price = total;
+
+    Ok(shipping_price)
+}
+
 #[get("/orders?<status>&<user_id>")]
 pub async fn get_orders(
     db_pool: &State<PgPool>,
@@ -118,6 +262,9 @@ pub async fn get_orders(
                 status: row.get("status"),
                 online_payment: row.get("online_payment"),
                 date: row.get("created_at"),
+                currency: row.get("currency"),
+                exchange_rate: row.get("exchange_rate"),
+                shipping_price: row.get("shipping_price"),
             })
             .collect::<Vec<Order>>(),
     ))
@@ -129,7 +276,7 @@ pub async fn get_order_details(
 ) -> Result<Json<OrderDetails>, ApiError> {
     let shipping_details: Json<Shipping> = get_shipping_by_id(db_pool, order_id).await?;
 
-    let order_items: Vec<OrderItemDetails> = sqlx::query_as::<_, OrderItemDetails>(
+    let mut order_items: Vec<OrderItemDetails> = sqlx::query_as::<_, OrderItemDetails>(
         r#"
         SELECT
             oi.id,
@@ -147,9 +294,49 @@ pub async fn get_order_details(
     .await
     .map_err(ApiError::DatabaseError)?;
 
+    let components = sqlx::query_as::<_, OrderItemComponent>(
+        r#"
+        SELECT c.order_item_id, COALESCE(p.name, '') AS product_name, c.quantity, c.size
+        FROM order_item_components c
+        JOIN order_items oi ON oi.id = c.order_item_id
+        LEFT JOIN products p ON p.id = c.product_id
+        WHERE oi.order_id = $1
+        ORDER BY c.id
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&**db_pool)
+    .await?;
+    for component in components {
+        if let Some(item) = order_items
+            .iter_mut()
+            .find(|item| item.id == component.order_item_id)
+        {
+            item.components.push(component);
+        }
+    }
+
+    let order = sqlx::query(
+        r#"
+        SELECT o.currency, COALESCE(c.symbol, o.currency) AS symbol, o.shipping_price,
+            o.total_price
+        FROM orders o
+        LEFT JOIN currencies c ON c.code = o.currency
+        WHERE o.id = $1
+        "#,
+    )
+    .bind(order_id)
+    .fetch_one(&**db_pool)
+    .await
+    .map_err(ApiError::DatabaseError)?;
+
     Ok(Json(OrderDetails {
         shipping: shipping_details.into_inner(),
         items: order_items,
+        currency: order.get("currency"),
+        currency_symbol: order.get("symbol"),
+        shipping_price: order.get("shipping_price"),
+        total_price: order.get("total_price"),
     }))
 }
 
@@ -160,13 +347,17 @@ pub async fn update_order_status(
     id: i32,
     claims: Claims,
 ) -> Result<String, ApiError> {
+    let actor_id = claims.sub;
     Claims::check_admin(db_pool, claims).await?;
 
     let status = status
         .get("status")
         .and_then(Value::as_str)
+        .filter(|status| ORDER_STATUSES.contains(status))
         .ok_or(ApiError::BadRequest)?;
 
+    let mut tx = db_pool.begin().await?;
+
     sqlx::query(
         r#"
             UPDATE orders
@@ -176,9 +367,20 @@ pub async fn update_order_status(
     )
     .bind(id)
     .bind(status)
-    .execute(&**db_pool)
+    .execute(&mut *tx)
     .await?;
 
+    let restocked = if status == ORDER_STATUS_CANCELLED {
+        restore_order_stock(&mut tx, id, actor_id).await?
+    } else {
+        reserve_order_stock(&mut tx, id, actor_id).await?;
+        Vec::new()
+    };
+    tx.commit().await?;
+    for product_id in restocked {
+        spawn_back_in_stock_notifications(db_pool, Some(product_id));
+    }
+
     Ok("Succeed update status".to_string())
 }
 #[delete("/order/<id>")]
@@ -187,7 +389,12 @@ pub async fn delete_order(
     id: i32,
     claims: Claims,
 ) -> Result<String, ApiError> {
+    let actor_id = claims.sub;
     Claims::check_admin(db_pool, claims).await?;
+
+    let mut tx = db_pool.begin().await?;
+    let restocked = restore_order_stock(&mut tx, id, actor_id).await?;
+
     sqlx::query(
         r#"
             DELETE FROM orders
@@ -195,8 +402,109 @@ pub async fn delete_order(
         "#,
     )
     .bind(id)
-    .execute(&**db_pool)
+    .execute(&mut *tx)
     .await?;
+    tx.commit().await?;
+    for product_id in restocked {
+        spawn_back_in_stock_notifications(db_pool, Some(product_id));
+    }
 
     Ok("Succeed delete order".to_string())
 }
+
+/// Returns the items of an order to stock, once, if the order still holds them.
+/// Returns the restocked products, so subscribers can be notified after commit.
+async fn restore_order_stock(
+    conn: &mut PgConnection,
+    order_id: i32,
+    actor_id: i32,
+) -> Result<Vec<i32>, ApiError> {
+    let mut restocked = Vec::new();
+    for (product_id, size, quantity) in take_order_stock_flag(conn, order_id, false).await? {
+        release_stock(
+            conn,
+            product_id,
+            size.as_deref(),
+            quantity,
+            order_id,
+            Some(actor_id),
+        )
+        .await?;
+        restocked.push(product_id);
+    }
+    restocked.dedup();
+    Ok(restocked)
+}
+
+/// Takes the items of an order out of stock again, e.g. when a cancelled order
+/// is reopened.
+async fn reserve_order_stock(
+    conn: &mut PgConnection,
+    order_id: i32,
+    actor_id: i32,
+) -> Result<(), ApiError> {
+    for (product_id, size, quantity) in take_order_stock_flag(conn, order_id, true).await? {
+        reserve_stock(
+            conn,
+            product_id,
+            size.as_deref(),
+            quantity,
+            order_id,
+            Some(actor_id),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Flips `orders.stock_reserved` to `reserved` and returns the stock the order
+/// holds, with bundles broken down into their components, if the flag actually
+/// changed, or nothing if the order was already in that state. Legacy orders with a
+/// NULL flag never took stock and are left alone.
+async fn take_order_stock_flag(
+    conn: &mut PgConnection,
+    order_id: i32,
+    reserved: bool,
+) -> Result<Vec<(i32, Option<String>, i32)>, ApiError> {
+    let changed = sqlx::query(
+        r#"
+            UPDATE orders
+            SET stock_reserved = $2
+            WHERE id = $1 AND stock_reserved != $2
+            RETURNING id
+        "#,
+    )
+    .bind(order_id)
+    .bind(reserved)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if changed.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let items = sqlx::query(
+        r#"
+            SELECT oi.product_id, oi.size, oi.quantity
+            FROM order_items oi
+            WHERE oi.order_id = $1 AND oi.product_id IS NOT NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM order_item_components c WHERE c.order_item_id = oi.id
+                )
+            UNION ALL
+            SELECT c.product_id, c.size, c.quantity
+            FROM order_item_components c
+            JOIN order_items oi ON oi.id = c.order_item_id
+            WHERE oi.order_id = $1 AND c.product_id IS NOT NULL
+            ORDER BY product_id, size
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(items
+        .into_iter()
+        .map(|row| (row.get("product_id"), row.get("size"), row.get("quantity")))
+        .collect())
+}
@@ -1,14 +1,34 @@
 use crate::data::orders::shipping::Shipping;
 use crate::error::api_error::ApiError;
+use crate::utils::constants::orders::{
+    SHIPPING_FIELD_MAX_LEN, SHIPPING_NAME_MAX_LEN, SHIPPING_PHONE_MAX_LEN,
+};
+use lettre::Address;
 use rocket::serde::json::Json;
 use rocket::State;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
-pub async fn add_shipping(
-    db_pool: &State<PgPool>,
-    shipping_data: Json<Shipping>,
-) -> Result<String, ApiError> {
-    let shipping_data = shipping_data.into_inner();
+/// Validates the delivery details of a new order before anything is written.
+pub fn validate_shipping(shipping: &Shipping) -> Result<(), ApiError> {
+    let fields = [
+        (&shipping.city, SHIPPING_FIELD_MAX_LEN),
+        (&shipping.branch, SHIPPING_FIELD_MAX_LEN),
+        (&shipping.first_name, SHIPPING_NAME_MAX_LEN),
+        (&shipping.last_name, SHIPPING_NAME_MAX_LEN),
+        (&shipping.phone_number, SHIPPING_PHONE_MAX_LEN),
+        (&shipping.email, SHIPPING_FIELD_MAX_LEN),
+    ];
+    let invalid_field = fields.iter().any(|(value, max_len)| {
+        let value = value.trim();
+        value.is_empty() || value.chars().count() > *max_len
+    });
+    if invalid_field || shipping.email.trim().parse::<Address>().is_err() {
+        return Err(ApiError::InvalidShipping);
+    }
+    Ok(())
+}
+
+pub async fn add_shipping(conn: &mut PgConnection, shipping: &Shipping) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
             INSERT INTO shipping_addresses (
@@ -16,17 +36,16 @@ pub async fn add_shipping(
             VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
         "#,
     )
-    .bind(shipping_data.order_id)
-    .bind(shipping_data.city)
-    .bind(shipping_data.branch)
-    .bind(shipping_data.first_name)
-    .bind(shipping_data.last_name)
-    .bind(shipping_data.phone_number)
-    .bind(shipping_data.email)
-    .execute(&**db_pool)
-    .await
-    .map_err(ApiError::DatabaseError)?;
-    Ok("Succeed shipping address added".to_string())
+    .bind(shipping.order_id)
+    .bind(shipping.city.trim())
+    .bind(shipping.branch.trim())
+    .bind(shipping.first_name.trim())
+    .bind(shipping.last_name.trim())
+    .bind(shipping.phone_number.trim())
+    .bind(shipping.email.trim())
+    .execute(conn)
+    .await?;
+    Ok(())
 }
 
 #[get("/shipping/<order_id>")]
@@ -42,9 +61,10 @@ pub async fn get_shipping_by_id(
         "#,
     )
     .bind(order_id)
-    .fetch_one(&**db_pool)
+    .fetch_optional(&**db_pool)
     .await
-    .map_err(ApiError::DatabaseError)?;
+    .map_err(ApiError::DatabaseError)?
+    .ok_or(ApiError::NotFound)?;
 
     Ok(Json(shipping))
 }
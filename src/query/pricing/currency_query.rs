@@ -0,0 +1,249 @@
+use crate::data::pricing::currency::{Currency, ProductPrice};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::utils::constants::pricing::{
+    BASE_CURRENCY, ROUNDING_DOWN, ROUNDING_NEAREST, ROUNDING_UP,
+};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+#[post("/currency", data = "<currency>")]
+pub async fn create_currency(
+    db_pool: &State<PgPool>,
+    currency: Json<Currency>,
+    claims: Claims,
+) -> Result<Json<&'static str>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let currency = currency.into_inner();
+    validate_currency(&currency)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO currencies (
+            code, symbol, rate, rounding_step, rounding_mode, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+        "#,
+    )
+    .bind(currency.code.to_uppercase())
+    .bind(&currency.symbol)
+    .bind(currency.rate)
+    .bind(currency.rounding_step)
+    .bind(&currency.rounding_mode)
+    .execute(&**db_pool)
+    .await?;
+
+    Ok(Json("Currency successfully created"))
+}
+
+#[get("/currencies")]
+pub async fn get_currencies(db_pool: &State<PgPool>) -> Result<Json<Vec<Currency>>, ApiError> {
+    let currencies = sqlx::query_as::<_, Currency>(
+        r#"
+        SELECT code, symbol, rate, rounding_step, rounding_mode
+        FROM currencies
+        ORDER BY code
+        "#,
+    )
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(currencies))
+}
+
+#[put("/currency/<code>", data = "<currency>")]
+pub async fn update_currency(
+    db_pool: &State<PgPool>,
+    code: &str,
+    currency: Json<Currency>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let currency = currency.into_inner();
+    validate_currency(&currency)?;
+    if code.eq_ignore_ascii_case(BASE_CURRENCY) && currency.rate != 1.0 {
+        return Err(ApiError::BadRequest);
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE currencies
+        SET symbol = $2, rate = $3, rounding_step = $4, rounding_mode = $5, updated_at = NOW()
+        WHERE code = $1
+        "#,
+    )
+    .bind(code.to_uppercase())
+    .bind(&currency.symbol)
+    .bind(currency.rate)
+    .bind(currency.rounding_step)
+    .bind(&currency.rounding_mode)
+    .execute(&**db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::UnknownCurrency);
+    }
+
+    Ok("Currency successfully updated".to_string())
+}
+
+#[delete("/currency/<code>")]
+pub async fn delete_currency(
+    db_pool: &State<PgPool>,
+    code: &str,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    if code.eq_ignore_ascii_case(BASE_CURRENCY) {
+        return Err(ApiError::BadRequest);
+    }
+
+    sqlx::query(
+        r#"
+        DELETE FROM currencies
+        WHERE code = $1
+        "#,
+    )
+    .bind(code.to_uppercase())
+    .execute(&**db_pool)
+    .await?;
+
+    Ok("Currency successfully deleted".to_string())
+}
+
+#[get("/product/<product_id>/prices")]
+pub async fn get_product_prices(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+) -> Result<Json<Vec<ProductPrice>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT product_id, currency_code, price
+        FROM product_prices
+        WHERE product_id = $1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|row| ProductPrice {
+                product_id: row.get("product_id"),
+                currency_code: row.get("currency_code"),
+                price: row.get("price"),
+            })
+            .collect(),
+    ))
+}
+
+#[put("/product/price", data = "<product_price>")]
+pub async fn set_product_price(
+    db_pool: &State<PgPool>,
+    product_price: Json<ProductPrice>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let product_price = product_price.into_inner();
+    if product_price.price < 0.0 {
+        return Err(ApiError::BadRequest);
+    }
+    let currency = find_currency(db_pool, Some(&product_price.currency_code)).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO product_prices (
+            product_id, currency_code, price, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, NOW(), NOW())
+        ON CONFLICT (product_id, currency_code)
+        DO UPDATE SET price = EXCLUDED.price, updated_at = NOW()
+        "#,
+    )
+    .bind(product_price.product_id)
+    .bind(&currency.code)
+    .bind(product_price.price)
+    .execute(&**db_pool)
+    .await?;
+
+    Ok("Product price successfully set".to_string())
+}
+
+#[delete("/product/<product_id>/price/<code>")]
+pub async fn delete_product_price(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    code: &str,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    sqlx::query(
+        r#"
+        DELETE FROM product_prices
+        WHERE product_id = $1 AND currency_code = $2
+        "#,
+    )
+    .bind(product_id)
+    .bind(code.to_uppercase())
+    .execute(&**db_pool)
+    .await?;
+
+    Ok("Product price successfully deleted".to_string())
+}
+
+pub async fn find_currency(db_pool: &PgPool, code: Option<&str>) -> Result<Currency, ApiError> {
+    let code = code.unwrap_or(BASE_CURRENCY).to_uppercase();
+
+    sqlx::query_as::<_, Currency>(
+        r#"
+        SELECT code, symbol, rate, rounding_step, rounding_mode
+        FROM currencies
+        WHERE code = $1
+        "#,
+    )
+    .bind(code)
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or(ApiError::UnknownCurrency)
+}
+
+pub async fn get_price_overrides(
+    db_pool: &PgPool,
+    currency: &Currency,
+) -> Result<HashMap<i32, f32>, ApiError> {
+    if currency.code == BASE_CURRENCY {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT product_id, price
+        FROM product_prices
+        WHERE currency_code = $1
+        "#,
+    )
+    .bind(&currency.code)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("product_id"), row.get("price")))
+        .collect())
+}
+
+fn validate_currency(currency: &Currency) -> Result<(), ApiError> {
+    let known_mode =
+        [ROUNDING_NEAREST, ROUNDING_UP, ROUNDING_DOWN].contains(&currency.rounding_mode.as_str());
+    if currency.code.len() != 3
+        || currency.rate <= 0.0
+        || currency.rounding_step < 0.0
+        || !known_mode
+    {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(())
+}
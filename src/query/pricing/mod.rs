@@ -0,0 +1,3 @@
+pub mod currency_query;
+pub mod price_history_query;
+pub mod sale_query;
@@ -0,0 +1,226 @@
+use crate::data::pricing::price_history::PriceChange;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::utils::constants::pricing::{
+    LOWEST_PRICE_WINDOW_DAYS, PRICE_SOURCE_SALE_ENDED, PRICE_SOURCE_SALE_STARTED,
+};
+use chrono::NaiveDateTime;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgConnection, PgExecutor, PgPool, Row};
+use std::collections::HashMap;
+
+#[get("/product/<id>/price_history")]
+pub async fn get_price_history(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<Json<Vec<PriceChange>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    sqlx::query("SELECT id FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&**db_pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let history = sqlx::query_as::<_, PriceChange>(
+        r#"
+        SELECT id, product_id, size, old_price, new_price, source, changed_at
+        FROM price_history
+        WHERE product_id = $1
+        ORDER BY changed_at DESC, id DESC
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(history))
+}
+
+pub async fn record_price_change<'e>(
+    executor: impl PgExecutor<'e>,
+    product_id: i32,
+    size: Option<&str>,
+    old_price: Option<f32>,
+    new_price: f32,
+    source: &str,
+) -> Result<(), ApiError> {
+    record_price_change_at(
+        executor, product_id, size, old_price, new_price, source, None,
+    )
+    .await
+}
+
+/// Like [`record_price_change`], for changes that took effect at `changed_at` rather
+/// than now.
+async fn record_price_change_at<'e>(
+    executor: impl PgExecutor<'e>,
+    product_id: i32,
+    size: Option<&str>,
+    old_price: Option<f32>,
+    new_price: f32,
+    source: &str,
+    changed_at: Option<NaiveDateTime>,
+) -> Result<(), ApiError> {
+    if old_price == Some(new_price) {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO price_history (
+            product_id, size, old_price, new_price, source, changed_at
+        )
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, NOW()))
+        "#,
+    )
+    .bind(product_id)
+    .bind(size)
+    .bind(old_price)
+    .bind(new_price)
+    .bind(source)
+    .bind(changed_at)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Records the start and end of every sale (or only of `sale_id`) whose time has
+/// come since the last run. Changes are dated when they took effect, but never
+/// before the sale was created, so a backdated sale does not rewrite history.
+pub async fn record_sale_price_changes(
+    conn: &mut PgConnection,
+    sale_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let started = sqlx::query(
+        r#"
+        UPDATE product_sales s
+        SET start_recorded = TRUE
+        FROM products p
+        WHERE p.id = s.product_id
+            AND NOT s.start_recorded AND s.starts_at <= NOW()
+            AND ($1::INT IS NULL OR s.id = $1)
+        RETURNING s.product_id, s.size, s.sale_price, p.price,
+            GREATEST(s.starts_at, s.created_at) AS changed_at
+        "#,
+    )
+    .bind(sale_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in started {
+        record_price_change_at(
+            &mut *conn,
+            row.get("product_id"),
+            row.get::<Option<String>, &str>("size").as_deref(),
+            Some(row.get("price")),
+            row.get("sale_price"),
+            PRICE_SOURCE_SALE_STARTED,
+            row.get("changed_at"),
+        )
+        .await?;
+    }
+
+    let ended = sqlx::query(
+        r#"
+        UPDATE product_sales s
+        SET end_recorded = TRUE
+        FROM products p
+        WHERE p.id = s.product_id
+            AND s.start_recorded AND NOT s.end_recorded AND s.ends_at <= NOW()
+            AND ($1::INT IS NULL OR s.id = $1)
+        RETURNING s.product_id, s.size, s.sale_price, p.price,
+            GREATEST(s.ends_at, s.created_at) AS changed_at
+        "#,
+    )
+    .bind(sale_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in ended {
+        record_price_change_at(
+            &mut *conn,
+            row.get("product_id"),
+            row.get::<Option<String>, &str>("size").as_deref(),
+            Some(row.get("sale_price")),
+            row.get("price"),
+            PRICE_SOURCE_SALE_ENDED,
+            row.get("changed_at"),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Lowest product-wide base price in effect during the `LOWEST_PRICE_WINDOW_DAYS`
+/// before each reference time, or before now when the reference is `None`. The
+/// window counts the price it opened with, so a product whose price has not changed
+/// in the window reports that price.
+pub async fn get_lowest_prices(
+    db_pool: &PgPool,
+    references: &[(i32, Option<NaiveDateTime>)],
+) -> Result<HashMap<i32, f32>, ApiError> {
+    let (ids, reference_times): (Vec<i32>, Vec<Option<NaiveDateTime>>) =
+        references.iter().copied().unzip();
+
+    let rows = sqlx::query(
+        r#"
+        SELECT r.product_id,
+            LEAST(
+                COALESCE(
+                    before_window.new_price,
+                    CASE WHEN first_in_window.changed_at IS NULL
+                        THEN p.price ELSE first_in_window.old_price END
+                ),
+                in_window.lowest
+            ) AS lowest_price
+        FROM UNNEST($1::INT[], $2::TIMESTAMP[]) AS u(product_id, reference_at)
+        CROSS JOIN LATERAL (
+            SELECT u.product_id,
+                COALESCE(u.reference_at, NOW()::TIMESTAMP) AS window_end,
+                COALESCE(u.reference_at, NOW()::TIMESTAMP)
+                    - make_interval(days => $3) AS window_start
+        ) r
+        JOIN products p ON p.id = r.product_id
+        LEFT JOIN LATERAL (
+            SELECT h.new_price
+            FROM price_history h
+            WHERE h.product_id = r.product_id AND h.size IS NULL
+                AND h.changed_at <= r.window_start
+            ORDER BY h.changed_at DESC, h.id DESC
+            LIMIT 1
+        ) before_window ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT h.old_price, h.changed_at
+            FROM price_history h
+            WHERE h.product_id = r.product_id AND h.size IS NULL
+                AND h.changed_at > r.window_start
+            ORDER BY h.changed_at, h.id
+            LIMIT 1
+        ) first_in_window ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT MIN(h.new_price) AS lowest
+            FROM price_history h
+            WHERE h.product_id = r.product_id AND h.size IS NULL
+                AND h.changed_at > r.window_start AND h.changed_at < r.window_end
+        ) in_window ON TRUE
+        "#,
+    )
+    .bind(ids)
+    .bind(reference_times)
+    .bind(LOWEST_PRICE_WINDOW_DAYS)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            row.get::<Option<f32>, &str>("lowest_price")
+                .map(|lowest| (row.get("product_id"), lowest))
+        })
+        .collect())
+}
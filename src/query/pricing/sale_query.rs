@@ -0,0 +1,249 @@
+use crate::data::pricing::currency::Currency;
+use crate::data::pricing::sale::Sale;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::pricing::price_history_query::{record_price_change, record_sale_price_changes};
+use crate::utils::constants::pricing::PRICE_SOURCE_SALE_DELETED;
+use crate::utils::constants::products::PRODUCT_SIZES;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgConnection, PgExecutor, PgPool, Row};
+use std::collections::HashMap;
+
+#[post("/sale", data = "<sale>")]
+pub async fn create_sale(
+    db_pool: &State<PgPool>,
+    sale: Json<Sale>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let mut sale = sale.into_inner();
+    // Single-size items are ordered without a size, so their sale is product-wide.
+    sale.size = sale
+        .size
+        .map(|size| size.to_lowercase())
+        .filter(|size| size != PRODUCT_SIZES[0]);
+    validate_sale(&sale)?;
+
+    let mut tx = db_pool.begin().await?;
+
+    sqlx::query("SELECT id FROM products WHERE id = $1")
+        .bind(sale.product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let id: i32 = sqlx::query(
+        r#"
+        INSERT INTO product_sales (
+            product_id, size, sale_price, starts_at, ends_at, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(sale.product_id)
+    .bind(&sale.size)
+    .bind(sale.sale_price)
+    .bind(sale.starts_at)
+    .bind(sale.ends_at)
+    .fetch_one(&mut *tx)
+    .await?
+    .get("id");
+
+    record_sale_price_changes(&mut tx, Some(id)).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(id))
+}
+
+/// Customers only see running sales; scheduled and past ones are listed for admins
+/// unless `active` is set.
+#[get("/sales?<product_id>&<active>")]
+pub async fn get_sales(
+    db_pool: &State<PgPool>,
+    product_id: Option<i32>,
+    active: Option<bool>,
+    claims: Option<Claims>,
+) -> Result<Json<Vec<Sale>>, ApiError> {
+    let is_admin = match claims {
+        Some(claims) => Claims::check_admin(db_pool, claims).await.is_ok(),
+        None => false,
+    };
+
+    let sales = if active.unwrap_or(false) || !is_admin {
+        get_active_sales(&**db_pool, product_id)
+            .await?
+            .into_values()
+            .flatten()
+            .collect()
+    } else {
+        sqlx::query_as::<_, Sale>(
+            r#"
+            SELECT id, product_id, size, sale_price, starts_at, ends_at
+            FROM product_sales
+            WHERE $1::INT IS NULL OR product_id = $1
+            ORDER BY starts_at
+            "#,
+        )
+        .bind(product_id)
+        .fetch_all(&**db_pool)
+        .await?
+    };
+
+    Ok(Json(sales))
+}
+
+#[delete("/sale/<id>")]
+pub async fn delete_sale(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let mut tx = db_pool.begin().await?;
+    record_sale_price_changes(&mut tx, Some(id)).await?;
+
+    let row = sqlx::query(
+        r#"
+        DELETE FROM product_sales s
+        USING products p
+        WHERE s.id = $1 AND p.id = s.product_id
+        RETURNING s.product_id, s.size, s.sale_price, p.price,
+            s.start_recorded AND NOT s.end_recorded AS running
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    if row.get("running") {
+        record_price_change(
+            &mut *tx,
+            row.get("product_id"),
+            row.get::<Option<String>, &str>("size").as_deref(),
+            Some(row.get("sale_price")),
+            row.get("price"),
+            PRICE_SOURCE_SALE_DELETED,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok("Sale successfully deleted".to_string())
+}
+
+pub async fn get_active_sales<'e>(
+    executor: impl PgExecutor<'e>,
+    product_id: Option<i32>,
+) -> Result<HashMap<i32, Vec<Sale>>, ApiError> {
+    let sales = sqlx::query_as::<_, Sale>(
+        r#"
+        SELECT id, product_id, size, sale_price, starts_at, ends_at
+        FROM product_sales
+        WHERE starts_at <= NOW()
+            AND (ends_at IS NULL OR ends_at > NOW())
+            AND ($1::INT IS NULL OR product_id = $1)
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(executor)
+    .await?;
+
+    let mut by_product: HashMap<i32, Vec<Sale>> = HashMap::new();
+    for sale in sales {
+        by_product.entry(sale.product_id).or_default().push(sale);
+    }
+    Ok(by_product)
+}
+
+/// A sale for the exact variant wins over a product-wide one; overlapping sales resolve to
+/// the lowest price.
+pub fn find_active_sale<'a>(sales: &'a [Sale], size: Option<&str>) -> Option<&'a Sale> {
+    let lowest = |sales: Vec<&'a Sale>| {
+        sales
+            .into_iter()
+            .min_by(|a, b| a.sale_price.total_cmp(&b.sale_price))
+    };
+
+    lowest(
+        sales
+            .iter()
+            .filter(|sale| sale.size.is_some() && sale.applies_to(size))
+            .collect(),
+    )
+    .or_else(|| lowest(sales.iter().filter(|sale| sale.size.is_none()).collect()))
+}
+
+/// Price of one unit in `currency`, with sales taking precedence over manual currency
+/// overrides.
+pub fn resolve_price(
+    base_price: f32,
+    price_override: Option<f32>,
+    sales: &[Sale],
+    size: Option<&str>,
+    currency: &Currency,
+) -> f32 {
+    match find_active_sale(sales, size) {
+        Some(sale) => currency.convert(sale.sale_price),
+        None => price_override.unwrap_or_else(|| currency.convert(base_price)),
+    }
+}
+
+/// The unit price charged at checkout. Drafts are not for sale and count as not found.
+/// The product row is share-locked, so the price cannot change before the caller's
+/// transaction commits.
+pub async fn get_checkout_price(
+    conn: &mut PgConnection,
+    product_id: i32,
+    size: Option<&str>,
+    currency: &Currency,
+) -> Result<f32, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT p.price, pp.price AS override_price
+        FROM products p
+        LEFT JOIN product_prices pp ON pp.product_id = p.id AND pp.currency_code = $2
+        WHERE p.id = $1 AND NOT p.draft
+        FOR SHARE OF p
+        "#,
+    )
+    .bind(product_id)
+    .bind(&currency.code)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    let sales = get_active_sales(&mut *conn, Some(product_id))
+        .await?
+        .remove(&product_id)
+        .unwrap_or_default();
+
+    Ok(resolve_price(
+        row.get("price"),
+        row.get("override_price"),
+        &sales,
+        size,
+        currency,
+    ))
+}
+
+fn validate_sale(sale: &Sale) -> Result<(), ApiError> {
+    if sale.sale_price < 0.0 {
+        return Err(ApiError::BadRequest);
+    }
+    if let Some(ends_at) = sale.ends_at {
+        if ends_at <= sale.starts_at {
+            return Err(ApiError::BadRequest);
+        }
+    }
+    if let Some(size) = &sale.size {
+        if !PRODUCT_SIZES.contains(&size.as_str()) {
+            return Err(ApiError::BadRequest);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,470 @@
+use crate::data::products_components::attribute::{
+    Attribute, Facet, FacetValue, ProductAttributeValue,
+};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::category_query::get_category_subtree_ids;
+use crate::utils::constants::products::{ATTRIBUTE_ENUM, ATTRIBUTE_NUMBER, ATTRIBUTE_TEXT};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+enum AttributeFilter {
+    Values(Vec<String>),
+    Range(Option<f32>, Option<f32>),
+}
+
+struct AttributeValueRow {
+    product_id: i32,
+    code: String,
+    name: String,
+    data_type: String,
+    value: Option<String>,
+    number_value: Option<f32>,
+}
+
+#[post("/attribute", data = "<attribute>")]
+pub async fn create_attribute(
+    db_pool: &State<PgPool>,
+    attribute: Json<Attribute>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let attribute = attribute.into_inner();
+    if ![ATTRIBUTE_TEXT, ATTRIBUTE_ENUM, ATTRIBUTE_NUMBER].contains(&attribute.data_type.as_str()) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    let id: i32 = sqlx::query(
+        r#"
+        INSERT INTO attributes (code, name, data_type, created_at, updated_at)
+        VALUES ($1, $2, $3, NOW(), NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(attribute.code.to_lowercase())
+    .bind(&attribute.name)
+    .bind(&attribute.data_type)
+    .fetch_one(&mut *tx)
+    .await?
+    .get("id");
+
+    if attribute.data_type == ATTRIBUTE_ENUM {
+        for (position, option) in attribute.options.unwrap_or_default().iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO attribute_options (attribute_id, value, sort_order)
+                VALUES ($1, $2, $3)
+                "#,
+            )
+            .bind(id)
+            .bind(option)
+            .bind(position as i32)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(id))
+}
+
+#[get("/attributes?<category_id>")]
+pub async fn get_attributes(
+    db_pool: &State<PgPool>,
+    category_id: Option<i32>,
+) -> Result<Json<Vec<Attribute>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id, c.parent_id FROM categories c
+            JOIN ancestors a ON c.id = a.parent_id
+        )
+        SELECT a.id, a.code, a.name, a.data_type
+        FROM attributes a
+        WHERE $1::INT IS NULL OR a.id IN (
+            SELECT attribute_id FROM category_attributes
+            WHERE category_id IN (SELECT id FROM ancestors)
+        )
+        ORDER BY a.name
+        "#,
+    )
+    .bind(category_id)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    let mut options = get_attribute_options(db_pool).await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|row| {
+                let id: i32 = row.get("id");
+                Attribute {
+                    id: Some(id),
+                    code: row.get("code"),
+                    name: row.get("name"),
+                    data_type: row.get("data_type"),
+                    options: options.remove(&id),
+                }
+            })
+            .collect(),
+    ))
+}
+
+#[delete("/attribute/<id>")]
+pub async fn delete_attribute(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    sqlx::query("DELETE FROM attributes WHERE id = $1")
+        .bind(id)
+        .execute(&**db_pool)
+        .await?;
+
+    Ok("Attribute successfully deleted".to_string())
+}
+
+#[put("/category/<category_id>/attributes", data = "<attribute_ids>")]
+pub async fn set_category_attributes(
+    db_pool: &State<PgPool>,
+    category_id: i32,
+    attribute_ids: Json<Vec<i32>>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let mut tx = db_pool.begin().await?;
+
+    sqlx::query("DELETE FROM category_attributes WHERE category_id = $1")
+        .bind(category_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO category_attributes (category_id, attribute_id)
+        SELECT $1, UNNEST($2::INT[])
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(category_id)
+    .bind(attribute_ids.into_inner())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok("Category attributes successfully updated".to_string())
+}
+
+#[get("/product/<product_id>/attributes")]
+pub async fn get_product_attributes(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+) -> Result<Json<Vec<ProductAttributeValue>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT pav.attribute_id, a.code, pav.value, pav.number_value
+        FROM product_attribute_values pav
+        JOIN attributes a ON a.id = pav.attribute_id
+        WHERE pav.product_id = $1
+        ORDER BY a.name
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|row| ProductAttributeValue {
+                attribute_id: row.get("attribute_id"),
+                code: row.get("code"),
+                value: row.get("value"),
+                number_value: row.get("number_value"),
+            })
+            .collect(),
+    ))
+}
+
+#[put("/product/<product_id>/attributes", data = "<values>")]
+pub async fn set_product_attributes(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    values: Json<Vec<ProductAttributeValue>>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let values = values.into_inner();
+
+    let category_id: Option<i32> = sqlx::query("SELECT category_id FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(&**db_pool)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .get("category_id");
+    let allowed = get_attributes(db_pool, Some(category_id.ok_or(ApiError::BadRequest)?))
+        .await?
+        .into_inner();
+
+    for value in &values {
+        let attribute = allowed
+            .iter()
+            .find(|attribute| attribute.id == Some(value.attribute_id))
+            .ok_or(ApiError::BadRequest)?;
+        let valid = match attribute.data_type.as_str() {
+            ATTRIBUTE_NUMBER => value.number_value.is_some(),
+            ATTRIBUTE_ENUM => value.value.as_ref().is_some_and(|value| {
+                attribute
+                    .options
+                    .as_ref()
+                    .is_some_and(|options| options.contains(value))
+            }),
+            _ => value.value.is_some(),
+        };
+        if !valid {
+            return Err(ApiError::BadRequest);
+        }
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    sqlx::query("DELETE FROM product_attribute_values WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for value in values {
+        sqlx::query(
+            r#"
+            INSERT INTO product_attribute_values (product_id, attribute_id, value, number_value)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(product_id)
+        .bind(value.attribute_id)
+        .bind(value.value)
+        .bind(value.number_value)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok("Product attributes successfully updated".to_string())
+}
+
+/// Counts are computed per attribute against every filter except the attribute's own, so
+/// selecting one colour still shows how many products the other colours would add.
+#[get("/facets?<category_id>&<filter>")]
+pub async fn get_facets(
+    db_pool: &State<PgPool>,
+    category_id: Option<i32>,
+    filter: Vec<String>,
+) -> Result<Json<Vec<Facet>>, ApiError> {
+    let filters = parse_filters(&filter)?;
+    let category_ids = match category_id {
+        Some(id) => Some(get_category_subtree_ids(db_pool, id).await?),
+        None => None,
+    };
+
+    let product_ids: Vec<i32> = sqlx::query(
+        r#"
+        SELECT id FROM products
+        WHERE NOT draft AND ($1::INT[] IS NULL OR category_id = ANY($1))
+        "#,
+    )
+    .bind(&category_ids)
+    .fetch_all(&**db_pool)
+    .await?
+    .into_iter()
+    .map(|row| row.get("id"))
+    .collect();
+
+    let values_by_product = get_values_by_product(db_pool, &product_ids).await?;
+
+    let mut attributes: BTreeMap<&str, (&str, &str)> = BTreeMap::new();
+    for value in values_by_product.values().flatten() {
+        attributes.insert(&value.code, (&value.name, &value.data_type));
+    }
+
+    let facets = attributes
+        .into_iter()
+        .map(|(code, (name, data_type))| {
+            let values = product_ids
+                .iter()
+                .filter(|id| matches_filters(&values_by_product, **id, &filters, Some(code)))
+                .filter_map(|id| {
+                    values_by_product
+                        .get(id)?
+                        .iter()
+                        .find(|value| value.code == code)
+                })
+                .collect::<Vec<&AttributeValueRow>>();
+            build_facet(code, name, data_type, &values, filters.get(code))
+        })
+        .collect();
+
+    Ok(Json(facets))
+}
+
+pub async fn filter_products_by_attributes(
+    db_pool: &PgPool,
+    product_ids: &[i32],
+    filter: &[String],
+) -> Result<HashSet<i32>, ApiError> {
+    let filters = parse_filters(filter)?;
+    let values_by_product = get_values_by_product(db_pool, product_ids).await?;
+
+    Ok(product_ids
+        .iter()
+        .copied()
+        .filter(|id| matches_filters(&values_by_product, *id, &filters, None))
+        .collect())
+}
+
+async fn get_attribute_options(db_pool: &PgPool) -> Result<HashMap<i32, Vec<String>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT attribute_id, value
+        FROM attribute_options
+        ORDER BY sort_order, value
+        "#,
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut options: HashMap<i32, Vec<String>> = HashMap::new();
+    for row in rows {
+        options
+            .entry(row.get("attribute_id"))
+            .or_default()
+            .push(row.get("value"));
+    }
+    Ok(options)
+}
+
+async fn get_values_by_product(
+    db_pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<HashMap<i32, Vec<AttributeValueRow>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT pav.product_id, a.code, a.name, a.data_type, pav.value, pav.number_value
+        FROM product_attribute_values pav
+        JOIN attributes a ON a.id = pav.attribute_id
+        WHERE pav.product_id = ANY($1)
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut values: HashMap<i32, Vec<AttributeValueRow>> = HashMap::new();
+    for row in rows {
+        let value = AttributeValueRow {
+            product_id: row.get("product_id"),
+            code: row.get("code"),
+            name: row.get("name"),
+            data_type: row.get("data_type"),
+            value: row.get("value"),
+            number_value: row.get("number_value"),
+        };
+        values.entry(value.product_id).or_default().push(value);
+    }
+    Ok(values)
+}
+
+fn parse_filters(filter: &[String]) -> Result<HashMap<String, AttributeFilter>, ApiError> {
+    let mut filters: HashMap<String, AttributeFilter> = HashMap::new();
+    for entry in filter {
+        let (code, value) = entry.split_once(':').ok_or(ApiError::BadRequest)?;
+        let code = code.to_lowercase();
+
+        if let Some((min, max)) = value.split_once("..") {
+            let parse = |bound: &str| -> Result<Option<f32>, ApiError> {
+                match bound {
+                    "" => Ok(None),
+                    bound => bound.parse().map(Some).map_err(|_| ApiError::BadRequest),
+                }
+            };
+            filters.insert(code, AttributeFilter::Range(parse(min)?, parse(max)?));
+        } else if let AttributeFilter::Values(values) = filters
+            .entry(code)
+            .or_insert_with(|| AttributeFilter::Values(Vec::new()))
+        {
+            values.push(value.to_string());
+        }
+    }
+    Ok(filters)
+}
+
+fn matches_filters(
+    values_by_product: &HashMap<i32, Vec<AttributeValueRow>>,
+    product_id: i32,
+    filters: &HashMap<String, AttributeFilter>,
+    skip_code: Option<&str>,
+) -> bool {
+    let product_values = values_by_product.get(&product_id);
+
+    filters
+        .iter()
+        .filter(|(code, _)| Some(code.as_str()) != skip_code)
+        .all(|(code, filter)| {
+            let value = product_values.and_then(|values| values.iter().find(|v| &v.code == code));
+            match (filter, value) {
+                (_, None) => false,
+                (AttributeFilter::Values(accepted), Some(value)) => value
+                    .value
+                    .as_ref()
+                    .is_some_and(|value| accepted.contains(value)),
+                (AttributeFilter::Range(min, max), Some(value)) => {
+                    value.number_value.is_some_and(|number| {
+                        min.is_none_or(|min| number >= min) && max.is_none_or(|max| number <= max)
+                    })
+                }
+            }
+        })
+}
+
+fn build_facet(
+    code: &str,
+    name: &str,
+    data_type: &str,
+    values: &[&AttributeValueRow],
+    filter: Option<&AttributeFilter>,
+) -> Facet {
+    let numbers = values.iter().filter_map(|value| value.number_value);
+    let min = numbers.clone().reduce(f32::min);
+    let max = numbers.reduce(f32::max);
+
+    let mut counts: BTreeMap<&str, i64> = BTreeMap::new();
+    if data_type != ATTRIBUTE_NUMBER {
+        for value in values.iter().filter_map(|value| value.value.as_deref()) {
+            *counts.entry(value).or_default() += 1;
+        }
+    }
+
+    Facet {
+        code: code.to_string(),
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        values: counts
+            .into_iter()
+            .map(|(value, count)| FacetValue {
+                value: value.to_string(),
+                count,
+                selected: matches!(filter, Some(AttributeFilter::Values(selected)) if selected.iter().any(|s| s == value)),
+            })
+            .collect(),
+        min,
+        max,
+    }
+}
@@ -0,0 +1,274 @@
+use crate::data::orders::order_item::ComponentSelection;
+use crate::data::products_components::bundle::{Bundle, BundleComponent, BundleComponentStock};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgConnection, PgExecutor, PgPool, Row};
+use std::collections::HashMap;
+
+#[get("/product/<id>/bundle")]
+pub async fn get_bundle(db_pool: &State<PgPool>, id: i32) -> Result<Json<Bundle>, ApiError> {
+    load_bundles(&**db_pool, &[id])
+        .await?
+        .remove(&id)
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+/// Replaces the components of a bundle; an empty list turns it back into a plain
+/// product. Bundles cannot contain other bundles.
+#[put("/product/<id>/bundle", data = "<components>")]
+pub async fn set_bundle(
+    db_pool: &State<PgPool>,
+    id: i32,
+    components: Json<Vec<BundleComponent>>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let components = components.into_inner();
+
+    let mut component_ids = components
+        .iter()
+        .map(|component| component.product_id)
+        .collect::<Vec<i32>>();
+    component_ids.sort_unstable();
+    component_ids.dedup();
+    if component_ids.len() != components.len()
+        || components
+            .iter()
+            .any(|component| component.quantity <= 0 || component.product_id == id)
+    {
+        return Err(ApiError::BadRequest);
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !components.is_empty() {
+        let found: i64 = sqlx::query("SELECT COUNT(*) AS found FROM products WHERE id = ANY($1)")
+            .bind(&component_ids)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("found");
+        if found != component_ids.len() as i64 {
+            return Err(ApiError::NotFound);
+        }
+
+        let nested = sqlx::query(
+            "SELECT 1 FROM bundle_components WHERE component_id = $1 OR bundle_id = ANY($2) LIMIT 1",
+        )
+        .bind(id)
+        .bind(&component_ids)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if nested.is_some() {
+            return Err(ApiError::BadRequest);
+        }
+    }
+
+    sqlx::query("DELETE FROM bundle_components WHERE bundle_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO bundle_components (bundle_id, component_id, quantity)
+        SELECT $1, component_id, quantity
+        FROM UNNEST($2::INT[], $3::INT[]) AS c(component_id, quantity)
+        "#,
+    )
+    .bind(id)
+    .bind(
+        components
+            .iter()
+            .map(|component| component.product_id)
+            .collect::<Vec<i32>>(),
+    )
+    .bind(
+        components
+            .iter()
+            .map(|component| component.quantity)
+            .collect::<Vec<i32>>(),
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE products SET updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok("Bundle successfully updated".to_string())
+}
+
+/// SQL condition that holds when `bundle_id` is a bundle and every component has a
+/// size with stock for at least one bundle, matching [`Bundle::available`] > 0.
+pub fn bundle_in_stock(bundle_id: &str) -> String {
+    format!(
+        r#"
+        (
+            EXISTS (SELECT 1 FROM bundle_components bc WHERE bc.bundle_id = {bundle_id})
+            AND NOT EXISTS (
+                SELECT 1
+                FROM bundle_components bc
+                LEFT JOIN product_sizes ps ON ps.product_id = bc.component_id
+                WHERE bc.bundle_id = {bundle_id}
+                GROUP BY bc.component_id, bc.quantity
+                HAVING COALESCE(MAX(GREATEST(
+                    ps.single_size, ps.s, ps.m, ps.l, ps.xl, ps.xxl
+                )), 0) < bc.quantity
+            )
+        )
+        "#,
+        bundle_id = bundle_id
+    )
+}
+
+/// Bundles among `ids` with their component stock. Products that are not bundles
+/// are left out.
+pub async fn load_bundles<'e>(
+    executor: impl PgExecutor<'e>,
+    ids: &[i32],
+) -> Result<HashMap<i32, Bundle>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT bc.bundle_id, bc.component_id, bc.quantity, p.name,
+            v.size, COALESCE(v.stock, 0) AS stock
+        FROM bundle_components bc
+        JOIN products p ON p.id = bc.component_id
+        LEFT JOIN product_sizes ps ON ps.product_id = bc.component_id
+        CROSS JOIN LATERAL (
+            VALUES ('single_size', ps.single_size), ('s', ps.s), ('m', ps.m),
+                   ('l', ps.l), ('xl', ps.xl), ('xxl', ps.xxl)
+        ) AS v(size, stock)
+        WHERE bc.bundle_id = ANY($1)
+        ORDER BY bc.bundle_id, bc.component_id
+        "#,
+    )
+    .bind(ids)
+    .fetch_all(executor)
+    .await?;
+
+    let mut bundles: HashMap<i32, Bundle> = HashMap::new();
+    for row in rows {
+        let bundle_id: i32 = row.get("bundle_id");
+        let component_id: i32 = row.get("component_id");
+        let quantity: i32 = row.get("quantity");
+        let stock: i32 = row.get("stock");
+
+        let bundle = bundles.entry(bundle_id).or_insert_with(|| Bundle {
+            product_id: bundle_id,
+            available: 0,
+            components: Vec::new(),
+        });
+        if bundle
+            .components
+            .last()
+            .map(|component| component.product_id)
+            != Some(component_id)
+        {
+            bundle.components.push(BundleComponentStock {
+                product_id: component_id,
+                name: row.get("name"),
+                quantity,
+                sizes: HashMap::new(),
+            });
+        }
+        if stock >= quantity {
+            if let Some(component) = bundle.components.last_mut() {
+                component.sizes.insert(row.get("size"), stock);
+            }
+        }
+    }
+
+    for bundle in bundles.values_mut() {
+        bundle.available = bundle
+            .components
+            .iter()
+            .map(|component| {
+                component
+                    .sizes
+                    .values()
+                    .map(|stock| stock / component.quantity)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0);
+    }
+
+    Ok(bundles)
+}
+
+/// The component sizes one unit of a bundle takes out of stock, as
+/// `(product_id, size, quantity)`, or `None` when `product_id` is not a bundle.
+/// Every component needs exactly one selection.
+pub async fn bundle_stock_lines(
+    conn: &mut PgConnection,
+    product_id: i32,
+    selection: &[ComponentSelection],
+) -> Result<Option<Vec<(i32, Option<String>, i32)>>, ApiError> {
+    let components = sqlx::query(
+        r#"
+        SELECT component_id, quantity
+        FROM bundle_components
+        WHERE bundle_id = $1
+        ORDER BY component_id
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if components.is_empty() {
+        return match selection.is_empty() {
+            true => Ok(None),
+            false => Err(ApiError::BadRequest),
+        };
+    }
+    if selection.len() != components.len() {
+        return Err(ApiError::BundleSelection);
+    }
+
+    components
+        .iter()
+        .map(|row| {
+            let component_id: i32 = row.get("component_id");
+            let chosen = selection
+                .iter()
+                .find(|chosen| chosen.product_id == component_id)
+                .ok_or(ApiError::BundleSelection)?;
+            Ok((component_id, chosen.size.clone(), row.get("quantity")))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()
+        .map(Some)
+}
+
+pub async fn copy_bundle(
+    conn: &mut PgConnection,
+    source_id: i32,
+    target_id: i32,
+) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        INSERT INTO bundle_components (bundle_id, component_id, quantity)
+        SELECT $2, component_id, quantity
+        FROM bundle_components
+        WHERE bundle_id = $1
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
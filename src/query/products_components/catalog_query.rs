@@ -0,0 +1,413 @@
+use crate::data::products_components::catalog::{CatalogRow, ImportReport, ImportRowError};
+use crate::data::products_components::size::Size;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::pricing::price_history_query::record_price_change;
+use crate::query::products_components::stock_query::set_stock_levels;
+use crate::query::products_components::stock_subscription_query::spawn_back_in_stock_notifications;
+use crate::utils::constants::pricing::PRICE_SOURCE_IMPORT;
+use crate::utils::constants::products::{
+    CATALOG_COLUMNS, CATALOG_IMPORT_LIMIT_MIB, PRODUCT_SIZES, STOCK_STOCKTAKE,
+};
+use csv::StringRecord;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{Acquire, PgConnection, PgPool, Row};
+
+struct RowOutcome {
+    created: bool,
+    categories_created: i32,
+}
+
+/// Every row runs inside its own savepoint of one transaction, so a dry run reports the
+/// same database-level problems a real import would hit and then rolls everything back.
+#[post("/catalog/import?<dry_run>", data = "<data>")]
+pub async fn import_catalog(
+    db_pool: &State<PgPool>,
+    data: Data<'_>,
+    dry_run: Option<bool>,
+    claims: Claims,
+) -> Result<(Status, Json<ImportReport>), ApiError> {
+    let actor_id = claims.sub;
+    Claims::check_admin(db_pool, claims).await?;
+    let csv_text = data
+        .open(CATALOG_IMPORT_LIMIT_MIB.mebibytes())
+        .into_string()
+        .await
+        .map_err(|_| ApiError::BadRequest)?;
+    if !csv_text.is_complete() {
+        return Err(ApiError::BadRequest);
+    }
+
+    let report = import_csv(db_pool, &csv_text, dry_run.unwrap_or(false), actor_id).await?;
+    let status = match report.errors.is_empty() {
+        true => Status::Ok,
+        false => Status::UnprocessableEntity,
+    };
+    Ok((status, Json(report)))
+}
+
+/// Imports `csv_text` on behalf of `actor_id`. Nothing is written unless every row
+/// imports cleanly and this is not a dry run.
+pub async fn import_csv(
+    db_pool: &PgPool,
+    csv_text: &str,
+    dry_run: bool,
+    actor_id: i32,
+) -> Result<ImportReport, ApiError> {
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    let rows = parse_rows(csv_text, &mut report.errors);
+    if !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    for (line, row) in rows {
+        let mut savepoint = tx.begin().await?;
+        match import_row(&mut savepoint, &row, actor_id).await {
+            Ok(outcome) => {
+                savepoint.commit().await?;
+                if outcome.created {
+                    report.created += 1;
+                } else {
+                    report.updated += 1;
+                }
+                report.categories_created += outcome.categories_created;
+            }
+            Err(message) => {
+                savepoint.rollback().await?;
+                report.errors.push(ImportRowError { line, message });
+            }
+        }
+    }
+
+    if report.dry_run || !report.errors.is_empty() {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        spawn_back_in_stock_notifications(db_pool, None);
+    }
+
+    Ok(report)
+}
+
+#[get("/catalog/export")]
+pub async fn export_catalog(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<(ContentType, String), ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let rows = sqlx::query(
+        r#"
+        WITH RECURSIVE category_paths AS (
+            SELECT id, name::TEXT AS path FROM categories WHERE parent_id IS NULL
+            UNION ALL
+            SELECT c.id, cp.path || '/' || c.name
+            FROM categories c
+            JOIN category_paths cp ON c.parent_id = cp.id
+        )
+        SELECT p.id, p.name, p.description, p.price, p.compare_at_price, cp.path AS category,
+            ps.single_size, ps.s, ps.m, ps.l, ps.xl, ps.xxl
+        FROM products p
+        LEFT JOIN category_paths cp ON cp.id = p.category_id
+        LEFT JOIN LATERAL (
+            SELECT * FROM product_sizes
+            WHERE product_id = p.id
+            ORDER BY id DESC
+            LIMIT 1
+        ) ps ON TRUE
+        ORDER BY p.id
+        "#,
+    )
+    .fetch_all(&**db_pool)
+    .await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer
+            .serialize(CatalogRow {
+                id: row.get("id"),
+                name: row.get("name"),
+                description: row.get("description"),
+                price: row.get("price"),
+                compare_at_price: row.get("compare_at_price"),
+                category: row.get("category"),
+                single_size: row.get("single_size"),
+                s: row.get("s"),
+                m: row.get("m"),
+                l: row.get("l"),
+                xl: row.get("xl"),
+                xxl: row.get("xxl"),
+            })
+            .map_err(|_| ApiError::InternalServerError)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok((
+        ContentType::CSV,
+        String::from_utf8(bytes).map_err(|_| ApiError::InternalServerError)?,
+    ))
+}
+
+fn parse_rows(csv_text: &str, errors: &mut Vec<ImportRowError>) -> Vec<(usize, CatalogRow)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv_text.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            errors.push(ImportRowError {
+                line: 1,
+                message: e.to_string(),
+            });
+            return Vec::new();
+        }
+    };
+
+    if let Some(unknown) = headers
+        .iter()
+        .find(|column| !CATALOG_COLUMNS.contains(column) && !PRODUCT_SIZES.contains(column))
+    {
+        errors.push(ImportRowError {
+            line: 1,
+            message: format!(
+                "unknown column {}, sizes are {}",
+                unknown,
+                PRODUCT_SIZES.join(", ")
+            ),
+        });
+        return Vec::new();
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    line: e.position().map_or(0, |position| position.line() as usize),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+        match record.deserialize::<CatalogRow>(Some(&headers)) {
+            Ok(row) => match validate_row(&row) {
+                Ok(()) => rows.push((line, row)),
+                Err(message) => errors.push(ImportRowError { line, message }),
+            },
+            Err(e) => errors.push(ImportRowError {
+                line,
+                message: cell_error(&e, &headers, &record),
+            }),
+        }
+    }
+    rows
+}
+
+/// Names the column and value a row failed on, e.g. a stock cell that is not a number.
+fn cell_error(error: &csv::Error, headers: &StringRecord, record: &StringRecord) -> String {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+            Some(field) => format!(
+                "invalid value \"{}\" in column {}: {}",
+                record.get(field as usize).unwrap_or_default(),
+                headers.get(field as usize).unwrap_or_default(),
+                err.kind()
+            ),
+            None => err.to_string(),
+        },
+        _ => error.to_string(),
+    }
+}
+
+fn validate_row(row: &CatalogRow) -> Result<(), String> {
+    if row.name.is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if row.price < 0.0 || row.compare_at_price.is_some_and(|price| price < 0.0) {
+        return Err("price must not be negative".to_string());
+    }
+    let stock = [row.single_size, row.s, row.m, row.l, row.xl, row.xxl];
+    if stock.iter().flatten().any(|quantity| *quantity < 0) {
+        return Err("stock must not be negative".to_string());
+    }
+    Ok(())
+}
+
+async fn import_row(
+    conn: &mut PgConnection,
+    row: &CatalogRow,
+    actor_id: i32,
+) -> Result<RowOutcome, String> {
+    let (category_id, categories_created) = match &row.category {
+        Some(path) if !path.is_empty() => {
+            let (id, created) = resolve_category_path(conn, path)
+                .await
+                .map_err(|e| e.to_string())?;
+            (Some(id), created)
+        }
+        _ => (None, 0),
+    };
+
+    let (product_id, old_price) = match row.id {
+        Some(id) => {
+            let old_price: f32 = sqlx::query("SELECT price FROM products WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or(format!("product {} does not exist", id))?
+                .get("price");
+
+            sqlx::query(
+                r#"
+                UPDATE products
+                SET name = $2, description = $3, price = $4, compare_at_price = $5,
+                    category_id = $6, updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .bind(&row.name)
+            .bind(&row.description)
+            .bind(row.price)
+            .bind(row.compare_at_price)
+            .bind(category_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+            (id, Some(old_price))
+        }
+        None => {
+            let id: i32 = sqlx::query(
+                r#"
+                INSERT INTO products (
+                    name, description, price, compare_at_price, category_id, created_at, updated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+                RETURNING id
+                "#,
+            )
+            .bind(&row.name)
+            .bind(&row.description)
+            .bind(row.price)
+            .bind(row.compare_at_price)
+            .bind(category_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .get("id");
+
+            (id, None)
+        }
+    };
+
+    record_price_change(
+        &mut *conn,
+        product_id,
+        None,
+        old_price,
+        row.price,
+        PRICE_SOURCE_IMPORT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    import_sizes(conn, product_id, row, actor_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(RowOutcome {
+        created: old_price.is_none(),
+        categories_created,
+    })
+}
+
+async fn import_sizes(
+    conn: &mut PgConnection,
+    product_id: i32,
+    row: &CatalogRow,
+    actor_id: i32,
+) -> Result<(), ApiError> {
+    // Blank cells leave the current stock of that size alone.
+    let size = Size {
+        product_id,
+        single_size: row.single_size,
+        s: row.s,
+        m: row.m,
+        l: row.l,
+        xl: row.xl,
+        xxl: row.xxl,
+    };
+    let size_id = set_stock_levels(conn, &size, STOCK_STOCKTAKE, Some(actor_id)).await?;
+
+    sqlx::query("UPDATE products SET size_id = $1 WHERE id = $2")
+        .bind(size_id)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn resolve_category_path(
+    conn: &mut PgConnection,
+    path: &str,
+) -> Result<(i32, i32), sqlx::Error> {
+    let mut parent_id: Option<i32> = None;
+    let mut created = 0;
+
+    for name in path
+        .split('/')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let existing = sqlx::query(
+            r#"
+            SELECT id FROM categories
+            WHERE name = $1 AND parent_id IS NOT DISTINCT FROM $2
+            "#,
+        )
+        .bind(name)
+        .bind(parent_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let id: i32 = match existing {
+            Some(row) => row.get("id"),
+            None => {
+                created += 1;
+                sqlx::query(
+                    r#"
+                    INSERT INTO categories (name, parent_id, created_at, updated_at)
+                    VALUES ($1, $2, NOW(), NOW())
+                    RETURNING id
+                    "#,
+                )
+                .bind(name)
+                .bind(parent_id)
+                .fetch_one(&mut *conn)
+                .await?
+                .get("id")
+            }
+        };
+        parent_id = Some(id);
+    }
+
+    parent_id
+        .map(|id| (id, created))
+        .ok_or(sqlx::Error::RowNotFound)
+}
@@ -1,9 +1,29 @@
-use crate::data::products_components::category::Category;
+use crate::data::caching::cached::Cached;
+use crate::data::localization::locale::Locale;
+use crate::data::products_components::category::{Category, CategoryNode};
 use crate::data::user_components::claims::Claims;
 use crate::error::api_error::ApiError;
+use crate::query::caching::cache_query::last_modified;
+use crate::utils::constants::caching::{CATALOG_CACHE_CONTROL, CATEGORY_SOURCES};
 use rocket::serde::json::Json;
 use rocket::State;
+use sqlx::postgres::PgRow;
 use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+/// Categories with name and description in the locale bound to `$1`, falling back
+/// to the default-locale columns.
+const LOCALIZED_CATEGORIES: &str = r#"
+    SELECT
+        c.id,
+        COALESCE(NULLIF(t.name, ''), c.name) AS name,
+        c.parent_id,
+        c.sort_order,
+        COALESCE(NULLIF(t.description, ''), c.description) AS description,
+        c.image_url
+    FROM categories c
+    LEFT JOIN category_translations t ON t.category_id = c.id AND t.locale = $1
+"#;
 
 #[post("/category", data = "<category_data>")]
 pub async fn create_category(
@@ -17,54 +37,91 @@ pub async fn create_category(
     sqlx::query(
         r#"
         INSERT INTO categories (
-            name, created_at, updated_at
+            name, parent_id, sort_order, description, image_url, created_at, updated_at
         )
-        VALUES ($1, NOW(), NOW())
+        VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
     "#,
     )
     .bind(&category.name)
+    .bind(category.parent_id)
+    .bind(category.sort_order.unwrap_or_default())
+    .bind(&category.description)
+    .bind(&category.image_url)
     .execute(&**db_pool)
     .await?;
 
     Ok(Json("Category successfully created"))
 }
 #[get("/categories")]
-pub async fn get_categories(db_pool: &State<PgPool>) -> Result<Json<Vec<Category>>, ApiError> {
-    let category_rows = sqlx::query(
-        r#"
-        SELECT * FROM categories;
-    "#,
-    )
+pub async fn get_categories(
+    db_pool: &State<PgPool>,
+    locale: Locale,
+) -> Result<Cached<Vec<Category>>, ApiError> {
+    let category_rows = sqlx::query(&format!(
+        "{} ORDER BY sort_order, name",
+        LOCALIZED_CATEGORIES
+    ))
+    .bind(locale.0)
     .fetch_all(&**db_pool)
     .await?;
 
     let categories = category_rows
-        .into_iter()
-        .map(|row| Category {
-            id: Some(row.get("id")),
-            name: Some(row.get("name")),
-        })
+        .iter()
+        .map(category_from_row)
         .collect::<Vec<Category>>();
 
-    Ok(Json(categories))
+    Ok(Cached {
+        value: categories,
+        last_modified: last_modified(db_pool, CATEGORY_SOURCES).await?,
+        cache_control: CATALOG_CACHE_CONTROL,
+    })
 }
 
-#[get("/category/<id>")]
-pub async fn get_category(db_pool: &State<PgPool>, id: i32) -> Result<Json<Category>, ApiError> {
-    let category_rows = sqlx::query(
-        r#"
-        SELECT * FROM categories
-        WHERE id = $1
-        "#,
-    )
-    .bind(id)
-    .fetch_one(&**db_pool)
+#[get("/categories/tree")]
+pub async fn get_category_tree(
+    db_pool: &State<PgPool>,
+    locale: Locale,
+) -> Result<Cached<Vec<CategoryNode>>, ApiError> {
+    let category_rows = sqlx::query(&format!(
+        "{} ORDER BY sort_order, name",
+        LOCALIZED_CATEGORIES
+    ))
+    .bind(locale.0)
+    .fetch_all(&**db_pool)
     .await?;
 
-    Ok(Json(Category {
-        id: category_rows.get("id"),
-        name: category_rows.get("name"),
-    }))
+    let mut children: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+    for category in category_rows.iter().map(category_from_row) {
+        children
+            .entry(category.parent_id)
+            .or_default()
+            .push(category);
+    }
+
+    Ok(Cached {
+        value: build_tree(&mut children, None),
+        last_modified: last_modified(db_pool, CATEGORY_SOURCES).await?,
+        cache_control: CATALOG_CACHE_CONTROL,
+    })
+}
+
+#[get("/category/<id>")]
+pub async fn get_category(
+    db_pool: &State<PgPool>,
+    id: i32,
+    locale: Locale,
+) -> Result<Cached<Category>, ApiError> {
+    let category_rows = sqlx::query(&format!("{} WHERE c.id = $2", LOCALIZED_CATEGORIES))
+        .bind(locale.0)
+        .bind(id)
+        .fetch_one(&**db_pool)
+        .await?;
+
+    Ok(Cached {
+        value: category_from_row(&category_rows),
+        last_modified: last_modified(db_pool, CATEGORY_SOURCES).await?,
+        cache_control: CATALOG_CACHE_CONTROL,
+    })
 }
 #[put("/category/<id>", data = "<name>")]
 pub async fn update_category_name(
@@ -93,13 +150,103 @@ pub async fn update_category_name(
 
     Ok("Category successfully updated".to_string())
 }
-#[delete("/category/<id>")]
+#[put("/category/update", data = "<category>")]
+pub async fn update_category(
+    db_pool: &State<PgPool>,
+    category: Json<Category>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let category = category.into_inner();
+    let id = category.id.ok_or(ApiError::BadRequest)?;
+
+    if let Some(parent_id) = category.parent_id {
+        if get_category_subtree_ids(db_pool, id)
+            .await?
+            .contains(&parent_id)
+        {
+            return Err(ApiError::BadRequest);
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE categories
+        SET name = $2, parent_id = $3, sort_order = $4, description = $5, image_url = $6,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(&category.name)
+    .bind(category.parent_id)
+    .bind(category.sort_order.unwrap_or_default())
+    .bind(&category.description)
+    .bind(&category.image_url)
+    .execute(&**db_pool)
+    .await?;
+
+    Ok("Category successfully updated".to_string())
+}
+#[delete("/category/<id>?<reparent>")]
 pub async fn delete_category_by_id(
     db_pool: &State<PgPool>,
     id: i32,
+    reparent: Option<bool>,
     claims: Claims,
 ) -> Result<String, ApiError> {
     Claims::check_admin(db_pool, claims).await?;
+    let mut tx = db_pool.begin().await?;
+
+    let category = sqlx::query(
+        r#"
+        SELECT parent_id,
+            (SELECT COUNT(*) FROM categories WHERE parent_id = $1) AS children_count,
+            (SELECT COUNT(*) FROM products WHERE category_id = $1) AS products_count
+        FROM categories
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    let parent_id: Option<i32> = category.get("parent_id");
+    let children_count: i64 = category.get("children_count");
+    let products_count: i64 = category.get("products_count");
+
+    if children_count > 0 || products_count > 0 {
+        if !reparent.unwrap_or(false) || (products_count > 0 && parent_id.is_none()) {
+            return Err(ApiError::CategoryNotEmpty);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE categories
+            SET parent_id = $2, updated_at = NOW()
+            WHERE parent_id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(parent_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE products
+            SET category_id = $2, updated_at = NOW()
+            WHERE category_id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(parent_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
     sqlx::query(
         r#"
         DELETE FROM categories
@@ -107,8 +254,58 @@ pub async fn delete_category_by_id(
         "#,
     )
     .bind(id)
-    .execute(&**db_pool)
+    .execute(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     Ok("Category successfully deleted".to_string())
 }
+
+pub async fn get_category_subtree_ids(db_pool: &PgPool, id: i32) -> Result<Vec<i32>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id FROM categories c
+            JOIN subtree s ON c.parent_id = s.id
+        )
+        SELECT id FROM subtree
+        "#,
+    )
+    .bind(id)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.get("id")).collect())
+}
+
+fn category_from_row(row: &PgRow) -> Category {
+    Category {
+        id: row.get("id"),
+        name: row.get("name"),
+        parent_id: row.get("parent_id"),
+        sort_order: row.get("sort_order"),
+        description: row.get("description"),
+        image_url: row.get("image_url"),
+    }
+}
+
+fn build_tree(
+    children: &mut HashMap<Option<i32>, Vec<Category>>,
+    parent_id: Option<i32>,
+) -> Vec<CategoryNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|category| {
+            let nested = build_tree(children, category.id);
+            CategoryNode {
+                category,
+                children: nested,
+            }
+        })
+        .collect()
+}
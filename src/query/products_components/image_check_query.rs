@@ -0,0 +1,186 @@
+use crate::data::products_components::image_check::{ImageCheckReport, MissingImageFile};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::product_image_query::{stored_filenames, sync_gallery};
+use crate::storage::storage;
+use crate::utils::constants::images::{
+    IMAGE_CHECK_QUARANTINE, IMAGE_CHECK_REMOVE, IMAGE_CHECK_REPORT, ORPHAN_IMAGE_GRACE_MINUTES,
+    QUARANTINE_PREFIX,
+};
+use crate::utils::image_processing::variant_filenames;
+use chrono::{Duration, Utc};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeSet, HashSet};
+
+#[get("/images/check")]
+pub async fn get_image_check(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<Json<ImageCheckReport>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    Ok(Json(check_images(db_pool, IMAGE_CHECK_REPORT).await?))
+}
+
+#[post("/images/check?<action>")]
+pub async fn run_image_check(
+    db_pool: &State<PgPool>,
+    action: &str,
+    claims: Claims,
+) -> Result<Json<ImageCheckReport>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    Ok(Json(check_images(db_pool, action).await?))
+}
+
+/// Compares image rows with the stored files and finds files nothing refers to, rows
+/// whose files are gone and images of deleted products. With `quarantine` the broken
+/// rows are marked as quarantined and the affected files are moved under
+/// `QUARANTINE_PREFIX`; with `remove` both are deleted. Files younger than the grace
+/// period are never treated as orphans, because uploads store files before their row
+/// is committed.
+pub async fn check_images(db_pool: &PgPool, action: &str) -> Result<ImageCheckReport, ApiError> {
+    if ![
+        IMAGE_CHECK_REPORT,
+        IMAGE_CHECK_QUARANTINE,
+        IMAGE_CHECK_REMOVE,
+    ]
+    .contains(&action)
+    {
+        return Err(ApiError::BadRequest);
+    }
+    let mut report = ImageCheckReport {
+        action: action.to_string(),
+        ..Default::default()
+    };
+
+    let stored = storage().list().await?;
+    let stored_keys: HashSet<&str> = stored.iter().map(|blob| blob.key.as_str()).collect();
+
+    let images = sqlx::query(
+        r#"
+        SELECT pi.id, pi.product_id, pi.image_url, pi.image_key,
+            pi.product_id IS NOT NULL AND p.id IS NULL AS detached
+        FROM product_images pi
+        LEFT JOIN products p ON p.id = pi.product_id
+        WHERE pi.quarantined_at IS NULL
+        ORDER BY pi.id
+        "#,
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut referenced: HashSet<String> = HashSet::new();
+    for row in images.iter() {
+        let filenames = stored_filenames(row);
+        if row.get("detached") {
+            report.detached_images.push(row.get("id"));
+        } else {
+            for filename in filenames.iter() {
+                if !stored_keys.contains(filename.as_str()) {
+                    report.missing_files.push(MissingImageFile {
+                        image_id: row.get("id"),
+                        product_id: row.get("product_id"),
+                        filename: filename.clone(),
+                    });
+                }
+            }
+        }
+        referenced.extend(filenames);
+    }
+
+    let review_keys = sqlx::query("SELECT image_key FROM review_photos")
+        .fetch_all(db_pool)
+        .await?;
+    for row in review_keys {
+        referenced.extend(variant_filenames(row.get("image_key")));
+    }
+
+    let cutoff = Utc::now() - Duration::minutes(ORPHAN_IMAGE_GRACE_MINUTES);
+    report.orphaned_files = stored
+        .iter()
+        .filter(|blob| !referenced.contains(&blob.key))
+        .filter(|blob| blob.modified.is_none_or(|modified| modified < cutoff))
+        .map(|blob| blob.key.clone())
+        .collect();
+    report.orphaned_files.sort();
+
+    if action == IMAGE_CHECK_REPORT {
+        return Ok(report);
+    }
+
+    let broken_ids: Vec<i32> = report
+        .missing_files
+        .iter()
+        .map(|missing| missing.image_id)
+        .chain(report.detached_images.iter().copied())
+        .collect::<BTreeSet<i32>>()
+        .into_iter()
+        .collect();
+    let product_ids: BTreeSet<i32> = report
+        .missing_files
+        .iter()
+        .filter_map(|missing| missing.product_id)
+        .collect();
+
+    let mut tx = db_pool.begin().await?;
+    let broken = match action {
+        IMAGE_CHECK_QUARANTINE => {
+            sqlx::query(
+                r#"
+                UPDATE product_images
+                SET quarantined_at = NOW(), position = NULL, updated_at = NOW()
+                WHERE id = ANY($1)
+                RETURNING image_url, image_key
+                "#,
+            )
+            .bind(&broken_ids)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        _ => {
+            sqlx::query(
+                "DELETE FROM product_images WHERE id = ANY($1) RETURNING image_url, image_key",
+            )
+            .bind(&broken_ids)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+    };
+    for product_id in product_ids {
+        sync_gallery(&mut tx, product_id).await?;
+    }
+    tx.commit().await?;
+    match action {
+        IMAGE_CHECK_QUARANTINE => report.rows_quarantined = broken.len(),
+        _ => report.rows_removed = broken.len(),
+    }
+
+    let leftover_files = broken
+        .iter()
+        .flat_map(stored_filenames)
+        .filter(|filename| stored_keys.contains(filename.as_str()));
+    for filename in report
+        .orphaned_files
+        .clone()
+        .into_iter()
+        .chain(leftover_files)
+    {
+        let disposed = match action {
+            IMAGE_CHECK_QUARANTINE => {
+                let target = format!("{}{}", QUARANTINE_PREFIX, filename);
+                storage().rename(&filename, &target).await
+            }
+            _ => storage().delete(&filename).await,
+        };
+        match (disposed, action) {
+            (Ok(()), IMAGE_CHECK_QUARANTINE) => report.files_quarantined += 1,
+            (Ok(()), _) => report.files_removed += 1,
+            (Err(error), _) => log::error!("Failed to clean up image {}: {}", filename, error),
+        }
+    }
+
+    Ok(report)
+}
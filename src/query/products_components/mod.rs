@@ -1,4 +1,12 @@
+pub mod attribute_query;
+pub mod bundle_query;
+pub mod catalog_query;
 pub mod category_query;
+pub mod image_check_query;
 pub mod product_image_query;
 pub mod product_query;
+pub mod recommendation_query;
 pub mod size_query;
+pub mod stock_alert_query;
+pub mod stock_query;
+pub mod stock_subscription_query;
@@ -1,15 +1,30 @@
-use crate::data::products_components::product_image::{NewProductImage, ProductImage};
+use crate::data::caching::cached::Cached;
+use crate::data::products_components::product_image::{
+    ImageOrder, ImageVariant, NewProductImage, ProductImage,
+};
 use crate::data::user_components::claims::Claims;
 use crate::error::api_error::ApiError;
-use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
-use crate::utils::env_configuration::CONFIG;
+use crate::query::caching::cache_query::last_modified;
+use crate::storage::storage;
+use crate::utils::constants::caching::{CATALOG_CACHE_CONTROL, PRODUCT_IMAGE_SOURCES};
+use crate::utils::constants::images::{
+    IMAGE_BATCH_LIMIT_MIB, IMAGE_BATCH_MAX_FILES, IMAGE_FILE_LIMIT_MIB, IMAGE_FORMATS,
+    IMAGE_VARIANTS,
+};
+use crate::utils::image_processing::{
+    primary_filename, process_image, variant_filename, variant_filenames, ProcessedImage,
+};
+use multer::{Constraints, Multipart, SizeLimit};
+use rocket::data::{Data, ToByteUnit};
 use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::http::ContentType;
 use rocket::serde::json::Json;
 use rocket::State;
 use sqlx::postgres::PgRow;
-use sqlx::{PgPool, Row};
-use std::{env, fs};
-use tokio::fs::File;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::HashMap;
+use tokio::io::AsyncReadExt;
 use uuid::Uuid;
 
 #[post("/product_image?<position>", data = "<image_form>")]
@@ -22,59 +37,89 @@ pub async fn create_product_image(
     Claims::check_admin(db_pool, claims).await?;
     let product_image = image_form.into_inner();
 
-    let image_filename = format!("{}.png", Uuid::new_v4());
-    let image_path = format!("{}/{}", PATH_PRODUCT_IMAGES, image_filename);
+    if let Some(product_id) = product_image.product_id {
+        ensure_product_exists(db_pool, product_id).await?;
+    }
 
-    fs::create_dir_all(PATH_PRODUCT_IMAGES).map_err(|_| ApiError::InternalServerError)?;
+    let image_key = Uuid::new_v4().to_string();
+    let processed = process_upload(&image_key, &product_image.image).await?;
+    store_upload(&image_key, processed).await?;
 
-    let mut file = File::create(&image_path)
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
-    let mut image_file = product_image
-        .image
-        .open()
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
-    tokio::io::copy(&mut image_file, &mut file)
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
+    let saved = save_image(db_pool, &image_key, product_image.product_id, position).await;
+    if saved.is_err() {
+        discard_files(&variant_filenames(&image_key)).await;
+    }
+    saved?;
 
-    let mut tx = db_pool.begin().await.map_err(ApiError::DatabaseError)?;
+    Ok("Product successfully created")
+}
 
-    let id = sqlx::query(
-        r#"
-            INSERT INTO product_images (
-             image_url, product_id, position, created_at, updated_at
-            )
-            VALUES($1, $2, $3, NOW(), NOW())
-            RETURNING id
-        "#,
-    )
-    .bind(&image_filename)
-    .bind(product_image.product_id)
-    .bind(position)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(ApiError::DatabaseError)?;
+/// Uploads several images at once and appends them to the product gallery in the
+/// order they were sent. Every file is processed before any is stored, so one
+/// unreadable image rejects the whole batch.
+///
+/// The multipart body is read here rather than through `Form`, so the batch size
+/// limit applies to this route only.
+#[post("/product/<product_id>/images", data = "<images>")]
+pub async fn create_product_images(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    content_type: &ContentType,
+    images: Data<'_>,
+    claims: Claims,
+) -> Result<Json<Vec<ProductImage>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    ensure_product_exists(db_pool, product_id).await?;
+    let images = read_image_batch(content_type, images).await?;
 
-    if position == Some(1) {
-        sqlx::query(
-            r#"
-            UPDATE products
-            SET primary_image_id = $1, updated_at = NOW()
-            WHERE id = $2
-            "#,
-        )
-        .bind(id.get::<i32, &str>("id"))
-        .bind(product_image.product_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(ApiError::DatabaseError)?;
+    let mut uploads = Vec::with_capacity(images.len());
+    for image_data in images {
+        let image_key = Uuid::new_v4().to_string();
+        let processed = process_image_data(&image_key, image_data).await?;
+        uploads.push((image_key, processed));
+    }
+    let mut image_keys = Vec::with_capacity(uploads.len());
+    for (image_key, processed) in uploads {
+        if let Err(error) = store_upload(&image_key, processed).await {
+            discard_uploads(&image_keys).await;
+            return Err(error);
+        }
+        image_keys.push(image_key);
     }
 
-    tx.commit().await.map_err(ApiError::DatabaseError)?;
+    let saved = save_gallery_images(db_pool, product_id, &image_keys).await;
+    if saved.is_err() {
+        discard_uploads(&image_keys).await;
+    }
+    saved?;
 
-    Ok("Product successfully created")
+    Ok(Json(get_gallery(db_pool, product_id).await?))
+}
+
+/// Replaces the gallery order of a product. `image_ids` has to list every image of
+/// the product exactly once; the first one becomes the primary image.
+#[put("/product/<product_id>/images/order", data = "<image_order>")]
+pub async fn reorder_product_images(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    image_order: Json<ImageOrder>,
+    claims: Claims,
+) -> Result<Json<Vec<ProductImage>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let image_ids = image_order.into_inner().image_ids;
+
+    let mut tx = db_pool.begin().await?;
+    let mut current = gallery_order(&mut tx, product_id).await?;
+    let mut requested = image_ids.clone();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return Err(ApiError::BadRequest);
+    }
+    write_gallery_order(&mut tx, product_id, &image_ids).await?;
+    tx.commit().await?;
+
+    Ok(Json(get_gallery(db_pool, product_id).await?))
 }
 
 #[get("/product_image/<id>")]
@@ -93,31 +138,95 @@ pub async fn get_one_product_image(
     .fetch_one(&**db_pool)
     .await
     .map_err(ApiError::DatabaseError)?;
-    let image_url: String = get_image_path(&row);
 
-    Ok(Json(ProductImage {
+    Ok(Json(product_image_from_row(&row)))
+}
+
+/// The primary image of each product, falling back to its first image by position.
+pub async fn get_primary_images(
+    db_pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<HashMap<i32, ProductImage>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+            SELECT DISTINCT ON (pi.product_id) pi.*
+            FROM product_images pi
+            JOIN products p ON p.id = pi.product_id
+            WHERE pi.product_id = ANY($1) AND pi.quarantined_at IS NULL
+            ORDER BY pi.product_id, pi.id = p.primary_image_id DESC,
+                pi.position NULLS LAST, pi.id
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("product_id"), product_image_from_row(row)))
+        .collect())
+}
+
+/// All images of each product in gallery order.
+pub async fn get_product_galleries(
+    db_pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<HashMap<i32, Vec<ProductImage>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+            SELECT *
+            FROM product_images
+            WHERE product_id = ANY($1) AND quarantined_at IS NULL
+            ORDER BY product_id, position NULLS LAST, id
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut galleries: HashMap<i32, Vec<ProductImage>> = HashMap::new();
+    for row in rows.iter() {
+        galleries
+            .entry(row.get("product_id"))
+            .or_default()
+            .push(product_image_from_row(row));
+    }
+    Ok(galleries)
+}
+
+fn product_image_from_row(row: &PgRow) -> ProductImage {
+    let image_key: Option<String> = row.get("image_key");
+
+    ProductImage {
         id: row.get("id"),
-        image_url,
+        image_url: get_image_path(&row.get::<String, &str>("image_url")),
         product_id: row.get("product_id"),
         position: row.get("position"),
-    }))
+        variants: image_key.map(|image_key| {
+            IMAGE_VARIANTS
+                .iter()
+                .flat_map(|(variant, _)| {
+                    IMAGE_FORMATS.iter().map(|format| ImageVariant {
+                        name: variant.to_string(),
+                        format: format.to_string(),
+                        url: get_image_path(&variant_filename(&image_key, variant, format)),
+                    })
+                })
+                .collect()
+        }),
+    }
 }
 
-fn get_image_path(row: &PgRow) -> String {
-    if CONFIG.get().unwrap().local {
-        format!(
-            "http://{}:{}/{}/{}",
-            CONFIG.get().unwrap().server_address,
-            CONFIG.get().unwrap().server_port,
-            PATH_PRODUCT_IMAGES,
-            row.get::<String, &str>("image_url")
-        )
-    } else {
-        format!(
-            "/{}/{}",
-            PATH_PRODUCT_IMAGES,
-            row.get::<String, &str>("image_url")
-        )
+fn get_image_path(filename: &str) -> String {
+    storage().url(filename)
+}
+
+/// Every file written for an image row: all variants, or the single file of rows
+/// uploaded before variants existed.
+pub fn stored_filenames(row: &PgRow) -> Vec<String> {
+    match row.get::<Option<String>, &str>("image_key") {
+        Some(image_key) => variant_filenames(&image_key),
+        None => vec![row.get("image_url")],
     }
 }
 
@@ -128,21 +237,23 @@ pub async fn delete_product_image_by_id(
     claims: Claims,
 ) -> Result<Json<String>, ApiError> {
     Claims::check_admin(db_pool, claims).await?;
-    let path = sqlx::query("SELECT image_url FROM product_images WHERE id = $1")
-        .bind(id)
-        .fetch_one(&**db_pool)
-        .await
-        .map_err(ApiError::DatabaseError)?;
-    let path: String = path.get("image_url");
-
-    let absolute_path = env::current_dir()
-        .expect("Failed to get current directory")
-        .join(PATH_PRODUCT_IMAGES)
-        .join(path);
-
-    let absolute_path_str = absolute_path
-        .to_str()
-        .expect("Failed to convert path to string");
+    let mut tx = db_pool.begin().await?;
+    // An image whose product is gone has no gallery left to renumber.
+    let row = sqlx::query(
+        r#"
+        SELECT pi.image_url, pi.image_key, p.id AS product_id
+        FROM product_images pi
+        LEFT JOIN products p ON p.id = pi.product_id
+        WHERE pi.id = $1
+        FOR UPDATE OF pi
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::DatabaseError)?;
+    let filenames = stored_filenames(&row);
+    let product_id: Option<i32> = row.get("product_id");
 
     sqlx::query(
         r#"
@@ -152,14 +263,15 @@ pub async fn delete_product_image_by_id(
         "#,
     )
     .bind(id)
-    .execute(&**db_pool)
+    .execute(&mut *tx)
     .await
     .map_err(ApiError::DatabaseError)?;
 
-    if let Err(e) = tokio::fs::remove_file(absolute_path_str).await {
-        eprintln!("Failed to delete file: {}", e);
-        return Err(ApiError::InternalServerError);
+    if let Some(product_id) = product_id {
+        sync_gallery(&mut tx, product_id).await?;
     }
+    tx.commit().await?;
+    discard_files(&filenames).await;
 
     Ok(Json("Successfully deleted image".to_string()))
 }
@@ -168,9 +280,9 @@ pub async fn delete_product_image_by_id(
 pub async fn get_all_product_images(
     db_pool: &State<PgPool>,
     product_id: Option<i32>,
-) -> Result<Json<Vec<ProductImage>>, ApiError> {
+) -> Result<Cached<Vec<ProductImage>>, ApiError> {
     let rows = match product_id {
-        None => sqlx::query(r#"SELECT * FROM product_images"#)
+        None => sqlx::query(r#"SELECT * FROM product_images WHERE quarantined_at IS NULL"#)
             .fetch_all(&**db_pool)
             .await
             .map_err(ApiError::DatabaseError)?,
@@ -178,7 +290,8 @@ pub async fn get_all_product_images(
             r#"
             SELECT *
             FROM product_images
-            WHERE product_id = $1
+            WHERE product_id = $1 AND quarantined_at IS NULL
+            ORDER BY position NULLS LAST, id
         "#,
         )
         .bind(id)
@@ -186,17 +299,13 @@ pub async fn get_all_product_images(
         .await
         .map_err(ApiError::DatabaseError)?,
     };
-    let images: Vec<ProductImage> = rows
-        .iter()
-        .map(|row| ProductImage {
-            id: row.get("id"),
-            image_url: get_image_path(row),
-            product_id,
-            position: row.get("position"),
-        })
-        .collect();
+    let images: Vec<ProductImage> = rows.iter().map(product_image_from_row).collect();
 
-    Ok(Json(images))
+    Ok(Cached {
+        value: images,
+        last_modified: last_modified(db_pool, PRODUCT_IMAGE_SOURCES).await?,
+        cache_control: CATALOG_CACHE_CONTROL,
+    })
 }
 #[put("/product_image/update", data = "<product_image>")]
 pub async fn update_product_image(
@@ -209,6 +318,14 @@ pub async fn update_product_image(
 
     let mut tx = db_pool.begin().await?;
 
+    let old_product_id: Option<i32> =
+        sqlx::query("SELECT product_id FROM product_images WHERE id = $1 FOR UPDATE")
+            .bind(product_image.id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(ApiError::NotFound)?
+            .get("product_id");
+
     sqlx::query(
         r#"
         UPDATE product_images
@@ -222,33 +339,367 @@ pub async fn update_product_image(
     .execute(&mut *tx)
     .await?;
 
-    if product_image.position == Some(1) {
-        sqlx::query(
-            r#"
-            UPDATE products
-            SET primary_image_id = $1, updated_at = NOW()
-            WHERE id = $2
-            "#,
-        )
-        .bind(product_image.id)
-        .bind(product_image.product_id)
-        .execute(&mut *tx)
+    if let Some(old_product_id) = old_product_id.filter(|id| Some(*id) != product_image.product_id)
+    {
+        sync_gallery(&mut tx, old_product_id).await?;
+    }
+    if let Some(product_id) = product_image.product_id {
+        let mut order = gallery_order(&mut tx, product_id).await?;
+        order.retain(|image_id| *image_id != product_image.id);
+        order.insert(
+            gallery_index(product_image.position, order.len()),
+            product_image.id,
+        );
+        write_gallery_order(&mut tx, product_id, &order).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok("Product image updated successfully".to_string())
+}
+
+async fn process_upload(
+    image_key: &str,
+    image: &TempFile<'_>,
+) -> Result<Vec<ProcessedImage>, ApiError> {
+    let mut image_data = Vec::new();
+    image
+        .open()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .read_to_end(&mut image_data)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    process_image_data(image_key, image_data).await
+}
+
+async fn process_image_data(
+    image_key: &str,
+    image_data: Vec<u8>,
+) -> Result<Vec<ProcessedImage>, ApiError> {
+    let image_key = image_key.to_string();
+    tokio::task::spawn_blocking(move || process_image(&image_key, &image_data))
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+}
+
+/// Reads every `images` field of a multipart batch upload, refusing bodies over
+/// [`IMAGE_BATCH_LIMIT_MIB`], files over [`IMAGE_FILE_LIMIT_MIB`] and batches over
+/// [`IMAGE_BATCH_MAX_FILES`].
+async fn read_image_batch(
+    content_type: &ContentType,
+    data: Data<'_>,
+) -> Result<Vec<Vec<u8>>, ApiError> {
+    let boundary = content_type
+        .params()
+        .find(|(name, _)| *name == "boundary")
+        .filter(|_| content_type.is_form_data())
+        .map(|(_, boundary)| boundary.to_string())
+        .ok_or(ApiError::BadRequest)?;
+    let constraints = Constraints::new()
+        .allowed_fields(vec!["images"])
+        .size_limit(SizeLimit::new().per_field(IMAGE_FILE_LIMIT_MIB.mebibytes().as_u64()));
+    let mut multipart = Multipart::with_reader_with_constraints(
+        data.open(IMAGE_BATCH_LIMIT_MIB.mebibytes()),
+        boundary,
+        constraints,
+    );
+
+    let mut images = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::BadRequest)?
+    {
+        if images.len() == IMAGE_BATCH_MAX_FILES {
+            return Err(ApiError::BadRequest);
+        }
+        let bytes = field.bytes().await.map_err(|_| ApiError::BadRequest)?;
+        images.push(bytes.to_vec());
+    }
+    if images.is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+
+    Ok(images)
+}
+
+async fn ensure_product_exists(db_pool: &PgPool, product_id: i32) -> Result<(), ApiError> {
+    sqlx::query("SELECT 1 FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(db_pool)
+        .await?
+        .map(|_| ())
+        .ok_or(ApiError::NotFound)
+}
+
+/// Stores every variant of an upload. When one write fails the variants already
+/// written are removed again, so a failed upload leaves no files behind.
+async fn store_upload(image_key: &str, processed: Vec<ProcessedImage>) -> Result<(), ApiError> {
+    for variant in processed {
+        if let Err(error) = storage().put(&variant.filename, variant.bytes).await {
+            discard_files(&variant_filenames(image_key)).await;
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+async fn save_image(
+    db_pool: &PgPool,
+    image_key: &str,
+    product_id: Option<i32>,
+    position: Option<i32>,
+) -> Result<(), ApiError> {
+    let mut tx = db_pool.begin().await?;
+
+    let id = insert_image(&mut tx, image_key, product_id, position).await?;
+
+    if let Some(product_id) = product_id {
+        let mut order = gallery_order(&mut tx, product_id).await?;
+        order.retain(|image_id| *image_id != id);
+        order.insert(gallery_index(position, order.len()), id);
+        write_gallery_order(&mut tx, product_id, &order).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn save_gallery_images(
+    db_pool: &PgPool,
+    product_id: i32,
+    image_keys: &[String],
+) -> Result<(), ApiError> {
+    let mut tx = db_pool.begin().await?;
+
+    let mut order = gallery_order(&mut tx, product_id).await?;
+    for image_key in image_keys {
+        let id = insert_image(&mut tx, image_key, Some(product_id), None).await?;
+        order.push(id);
+    }
+    write_gallery_order(&mut tx, product_id, &order).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn discard_uploads(image_keys: &[String]) {
+    for image_key in image_keys {
+        discard_files(&variant_filenames(image_key)).await;
+    }
+}
+
+/// Best-effort removal of files whose rows are already gone. Failures are only
+/// logged; the image consistency check picks up whatever is left behind.
+pub async fn discard_files(filenames: &[String]) {
+    for filename in filenames {
+        if let Err(error) = storage().delete(filename).await {
+            log::warn!("Leaving orphaned image file {}: {}", filename, error);
+        }
+    }
+}
+
+async fn insert_image(
+    conn: &mut PgConnection,
+    image_key: &str,
+    product_id: Option<i32>,
+    position: Option<i32>,
+) -> Result<i32, ApiError> {
+    let id = sqlx::query(
+        r#"
+            INSERT INTO product_images (
+             image_url, image_key, product_id, position, created_at, updated_at
+            )
+            VALUES($1, $2, $3, $4, NOW(), NOW())
+            RETURNING id
+        "#,
+    )
+    .bind(primary_filename(image_key))
+    .bind(image_key)
+    .bind(product_id)
+    .bind(position)
+    .fetch_one(&mut *conn)
+    .await?
+    .get("id");
+
+    Ok(id)
+}
+
+async fn get_gallery(db_pool: &PgPool, product_id: i32) -> Result<Vec<ProductImage>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+            SELECT *
+            FROM product_images
+            WHERE product_id = $1 AND quarantined_at IS NULL
+            ORDER BY position NULLS LAST, id
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows.iter().map(product_image_from_row).collect())
+}
+
+/// Zero-based gallery slot for a one-based `position`; no position appends.
+fn gallery_index(position: Option<i32>, len: usize) -> usize {
+    position.map_or(len, |position| {
+        usize::try_from(position - 1).unwrap_or(0).min(len)
+    })
+}
+
+/// Locks the product and returns its image ids in gallery order, primary image
+/// first, leaving out quarantined images. A primary image that was uploaded before
+/// the product existed is adopted into the gallery here.
+async fn gallery_order(conn: &mut PgConnection, product_id: i32) -> Result<Vec<i32>, ApiError> {
+    let primary_image_id: Option<i32> =
+        sqlx::query("SELECT primary_image_id FROM products WHERE id = $1 FOR UPDATE")
+            .bind(product_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(ApiError::NotFound)?
+            .get("primary_image_id");
+
+    sqlx::query("UPDATE product_images SET product_id = $1 WHERE id = $2 AND product_id IS NULL")
+        .bind(product_id)
+        .bind(primary_image_id)
+        .execute(&mut *conn)
         .await?;
 
-        sqlx::query(
+    let order = sqlx::query(
+        r#"
+        SELECT id FROM product_images
+        WHERE product_id = $1 AND quarantined_at IS NULL
+        ORDER BY id IS NOT DISTINCT FROM $2 DESC, position NULLS LAST, id
+        "#,
+    )
+    .bind(product_id)
+    .bind(primary_image_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .iter()
+    .map(|row| row.get("id"))
+    .collect();
+
+    Ok(order)
+}
+
+/// Copies the gallery of `source_id` onto `target_id` in the same order, giving every
+/// copy files of its own. Returns the written files so the caller can remove them
+/// again if its transaction does not commit.
+pub async fn copy_product_images(
+    conn: &mut PgConnection,
+    source_id: i32,
+    target_id: i32,
+) -> Result<Vec<String>, ApiError> {
+    let source_order = gallery_order(conn, source_id).await?;
+    let mut sources: HashMap<i32, PgRow> =
+        sqlx::query("SELECT id, image_url, image_key FROM product_images WHERE id = ANY($1)")
+            .bind(&source_order)
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .map(|row| (row.get("id"), row))
+            .collect();
+
+    let mut written = Vec::new();
+    let mut order = Vec::with_capacity(source_order.len());
+    for source_id in source_order {
+        let Some(source) = sources.remove(&source_id) else {
+            continue;
+        };
+        let (image_url, image_key) = match source.get::<Option<String>, &str>("image_key") {
+            Some(source_key) => {
+                let image_key = Uuid::new_v4().to_string();
+                (primary_filename(&image_key), Some((source_key, image_key)))
+            }
+            None => (
+                format!(
+                    "{}-{}",
+                    Uuid::new_v4(),
+                    source.get::<String, &str>("image_url")
+                ),
+                None,
+            ),
+        };
+        let copies: Vec<(String, String)> = match &image_key {
+            Some((source_key, image_key)) => variant_filenames(source_key)
+                .into_iter()
+                .zip(variant_filenames(image_key))
+                .collect(),
+            None => vec![(source.get("image_url"), image_url.clone())],
+        };
+        for (from, to) in copies {
+            if let Err(error) = storage().copy(&from, &to).await {
+                discard_files(&written).await;
+                return Err(error);
+            }
+            written.push(to);
+        }
+
+        let inserted = sqlx::query(
             r#"
-            UPDATE product_images
-            SET position = NULL
-            WHERE product_id = $1 AND id != $2 AND position = 1
+            INSERT INTO product_images (
+             image_url, image_key, product_id, created_at, updated_at
+            )
+            VALUES($1, $2, $3, NOW(), NOW())
+            RETURNING id
             "#,
         )
-        .bind(product_image.product_id)
-        .bind(product_image.id)
-        .execute(&mut *tx)
-        .await?;
+        .bind(&image_url)
+        .bind(image_key.map(|(_, image_key)| image_key))
+        .bind(target_id)
+        .fetch_one(&mut *conn)
+        .await;
+        match inserted {
+            Ok(row) => order.push(row.get("id")),
+            Err(error) => {
+                discard_files(&written).await;
+                return Err(error.into());
+            }
+        }
+    }
+    if let Err(error) = write_gallery_order(conn, target_id, &order).await {
+        discard_files(&written).await;
+        return Err(error);
     }
 
-    tx.commit().await?;
+    Ok(written)
+}
 
-    Ok("Product image updated successfully".to_string())
+/// Renumbers the gallery of a product after images were added or removed elsewhere.
+pub async fn sync_gallery(conn: &mut PgConnection, product_id: i32) -> Result<(), ApiError> {
+    let order = gallery_order(conn, product_id).await?;
+    write_gallery_order(conn, product_id, &order).await
+}
+
+/// Numbers the gallery 1..n in the given order and points the product's primary
+/// image at position 1, so the two always agree.
+async fn write_gallery_order(
+    conn: &mut PgConnection,
+    product_id: i32,
+    image_ids: &[i32],
+) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        UPDATE product_images pi
+        SET position = o.position::INT, updated_at = NOW()
+        FROM UNNEST($2::INT[]) WITH ORDINALITY AS o(id, position)
+        WHERE pi.id = o.id AND pi.product_id = $1
+        "#,
+    )
+    .bind(product_id)
+    .bind(image_ids)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE products SET primary_image_id = $2, updated_at = NOW() WHERE id = $1")
+        .bind(product_id)
+        .bind(image_ids.first())
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
 }
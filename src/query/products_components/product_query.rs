@@ -1,9 +1,33 @@
-use crate::data::products_components::product::Product;
+use crate::data::caching::cached::Cached;
+use crate::data::localization::locale::Locale;
+use crate::data::products_components::product::{Product, ProductDuplicate};
+use crate::data::products_components::size::Size;
 use crate::data::user_components::claims::Claims;
 use crate::error::api_error::ApiError;
+use crate::query::caching::cache_query::last_modified;
+use crate::query::localization::translation_query::get_product_text;
+use crate::query::pricing::currency_query::{find_currency, get_price_overrides};
+use crate::query::pricing::price_history_query::{get_lowest_prices, record_price_change};
+use crate::query::pricing::sale_query::{find_active_sale, get_active_sales, resolve_price};
+use crate::query::products_components::attribute_query::filter_products_by_attributes;
+use crate::query::products_components::bundle_query::copy_bundle;
+use crate::query::products_components::category_query::get_category_subtree_ids;
+use crate::query::products_components::product_image_query::{
+    copy_product_images, discard_files, stored_filenames, sync_gallery,
+};
+use crate::query::products_components::stock_query::set_stock_levels;
+use crate::query::reviews::review_query::get_rating_summaries;
+use crate::utils::constants::caching::{
+    CATALOG_CACHE_CONTROL, PRIVATE_CACHE_CONTROL, PRODUCT_SOURCES,
+};
+use crate::utils::constants::pricing::PRICE_SOURCE_MANUAL;
+use crate::utils::constants::products::STOCK_ADJUSTMENT;
+use chrono::NaiveDateTime;
 use rocket::serde::json::Json;
 use rocket::State;
+use sqlx::postgres::PgRow;
 use sqlx::{query, PgPool, Row};
+use std::collections::HashMap;
 
 #[post("/product", data = "<product>")]
 pub async fn create_product(
@@ -17,9 +41,9 @@ pub async fn create_product(
     let product_id = sqlx::query(
         r#"
                 INSERT INTO products(
-                    name, description, primary_image_id, price, category_id, size_id, created_at, updated_at
+                    name, description, primary_image_id, price, category_id, size_id, compare_at_price, draft, created_at, updated_at
                 )
-                VALUES($1, $2, $3, $4, $5, $6, NOW(), NOW())
+                VALUES($1, $2, $3, $4, $5, $6, $7, $8, NOW(), NOW())
                 RETURNING id
         "#
     ).bind(product.name)
@@ -28,11 +52,23 @@ pub async fn create_product(
         .bind(product.price)
         .bind(product.category_id)
         .bind(product.size_id)
+        .bind(product.compare_at_price)
+        .bind(product.draft.unwrap_or(false))
         .fetch_one(&**db_pool)
         .await?;
 
     let product_id: i32 = product_id.get("id");
 
+    record_price_change(
+        &**db_pool,
+        product_id,
+        None,
+        None,
+        product.price,
+        PRICE_SOURCE_MANUAL,
+    )
+    .await?;
+
     sqlx::query(
         r#"
                 UPDATE product_images
@@ -49,13 +85,27 @@ pub async fn create_product(
     Ok(Json(product_id))
 }
 
-#[get("/product?<category_id>&<selected_id>&<product_id>")]
+#[get("/product?<category_id>&<selected_id>&<product_id>&<currency>&<filter>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_products(
     db_pool: &State<PgPool>,
     category_id: Option<i32>,
     selected_id: Option<i32>,
     product_id: Option<i32>,
-) -> Result<Json<Vec<Product>>, ApiError> {
+    currency: Option<&str>,
+    filter: Vec<String>,
+    locale: Locale,
+    claims: Option<Claims>,
+) -> Result<Cached<Vec<Product>>, ApiError> {
+    let include_drafts = match claims {
+        Some(claims) => Claims::check_admin(db_pool, claims).await.is_ok(),
+        None => false,
+    };
+    let category_ids = match category_id {
+        Some(id) => get_category_subtree_ids(db_pool, id).await?,
+        None => Vec::new(),
+    };
+
     let query = match (category_id, selected_id, product_id) {
         (None, None, Some(id)) => sqlx::query(
             r#"
@@ -64,20 +114,20 @@ pub async fn get_products(
             "#,
         )
         .bind(id),
-        (Some(id), None, None) => sqlx::query(
+        (Some(_), None, None) => sqlx::query(
             r#"
             SELECT * FROM products
-            WHERE category_id = $1
+            WHERE category_id = ANY($1)
             "#,
         )
-        .bind(id),
-        (Some(id), Some(selected_id), None) => sqlx::query(
+        .bind(&category_ids),
+        (Some(_), Some(selected_id), None) => sqlx::query(
             r#"
             SELECT * FROM products
-            WHERE category_id = $1 AND id != $2
+            WHERE category_id = ANY($1) AND id != $2
             "#,
         )
-        .bind(id)
+        .bind(&category_ids)
         .bind(selected_id),
         _ => sqlx::query(
             r#"
@@ -86,22 +136,120 @@ pub async fn get_products(
         ),
     };
 
-    let products = query.fetch_all(&**db_pool).await?;
+    let mut products = query.fetch_all(&**db_pool).await?;
+    if !include_drafts {
+        products.retain(|product| !product.get::<bool, &str>("draft"));
+    }
+
+    if !filter.is_empty() {
+        let ids = products
+            .iter()
+            .map(|product| product.get("id"))
+            .collect::<Vec<i32>>();
+        let matching = filter_products_by_attributes(db_pool, &ids, &filter).await?;
+        products.retain(|product| matching.contains(&product.get::<i32, &str>("id")));
+    }
 
-    Ok(Json(
-        products
-            .into_iter()
-            .map(|product| Product {
-                id: product.get("id"),
-                name: product.get("name"),
-                description: product.get("description"),
+    Ok(Cached {
+        value: products_from_rows(db_pool, products, currency, product_id, locale).await?,
+        last_modified: last_modified(db_pool, PRODUCT_SOURCES).await?,
+        cache_control: match include_drafts {
+            true => PRIVATE_CACHE_CONTROL,
+            false => CATALOG_CACHE_CONTROL,
+        },
+    })
+}
+
+/// Builds product responses priced in the requested currency and translated into
+/// the requested locale, with active sales and rating aggregates applied.
+pub async fn products_from_rows(
+    db_pool: &PgPool,
+    rows: Vec<PgRow>,
+    currency: Option<&str>,
+    product_id: Option<i32>,
+    locale: Locale,
+) -> Result<Vec<Product>, ApiError> {
+    let currency = find_currency(db_pool, currency).await?;
+    let price_overrides = get_price_overrides(db_pool, &currency).await?;
+    let active_sales = get_active_sales(db_pool, product_id).await?;
+    let ratings = get_rating_summaries(db_pool, product_id).await?;
+    let ids = rows
+        .iter()
+        .map(|product| product.get("id"))
+        .collect::<Vec<i32>>();
+    let mut translations = get_product_text(db_pool, &ids, locale).await?;
+    let lowest_price_references = ids
+        .iter()
+        .map(|id| {
+            let sales = active_sales.get(id).map(Vec::as_slice).unwrap_or_default();
+            (
+                *id,
+                find_active_sale(sales, None).map(|sale| sale.starts_at),
+            )
+        })
+        .collect::<Vec<(i32, Option<NaiveDateTime>)>>();
+    let lowest_prices = get_lowest_prices(db_pool, &lowest_price_references).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|product| {
+            let id: i32 = product.get("id");
+            let base_price: f32 = product.get("price");
+            let price_override = price_overrides.get(&id).copied();
+            let sales = active_sales.get(&id).map(Vec::as_slice).unwrap_or_default();
+            let price = price_override.unwrap_or_else(|| currency.convert(base_price));
+            let compare_at_price = product
+                .get::<Option<f32>, &str>("compare_at_price")
+                .map(|compare_at| currency.convert(compare_at))
+                .or_else(|| find_active_sale(sales, None).map(|_| price));
+            let variant_prices = sales
+                .iter()
+                .filter_map(|sale| sale.size.clone())
+                .map(|size| {
+                    let size_price =
+                        resolve_price(base_price, price_override, sales, Some(&size), &currency);
+                    (size, size_price)
+                })
+                .collect::<HashMap<String, f32>>();
+            let translation = translations.remove(&id);
+
+            Product {
+                id: Some(id),
+                name: translation
+                    .as_ref()
+                    .and_then(|translation| translation.name.clone())
+                    .unwrap_or_else(|| product.get("name")),
+                description: translation
+                    .and_then(|translation| translation.description)
+                    .or_else(|| product.get("description")),
                 primary_image_id: product.get("primary_image_id"),
-                price: product.get("price"),
+                price,
                 size_id: product.get("size_id"),
                 category_id: product.get("category_id"),
-            })
-            .collect::<Vec<Product>>(),
-    ))
+                currency: Some(currency.code.clone()),
+                compare_at_price,
+                active_price: Some(resolve_price(
+                    base_price,
+                    price_override,
+                    sales,
+                    None,
+                    &currency,
+                )),
+                variant_prices: (!variant_prices.is_empty()).then_some(variant_prices),
+                rating_average: ratings.get(&id).map(|(average, _)| *average),
+                rating_count: Some(ratings.get(&id).map_or(0, |(_, count)| *count)),
+                draft: Some(product.get("draft")),
+                // History is kept in the base currency only, so it says nothing about
+                // a manual price set for this currency.
+                lowest_price_30d: match price_override {
+                    Some(_) => None,
+                    None => lowest_prices
+                        .get(&id)
+                        .map(|lowest| currency.convert(*lowest)),
+                },
+            }
+        })
+        .collect())
 }
 #[put("/product/update", data = "<product>")]
 pub async fn product_update(
@@ -111,11 +259,22 @@ pub async fn product_update(
 ) -> Result<String, ApiError> {
     Claims::check_admin(db_pool, claims).await?;
     let product = product.into_inner();
+    let product_id = product.id.ok_or(ApiError::BadRequest)?;
+
+    let mut tx = db_pool.begin().await?;
+
+    let old_price: f32 = query("SELECT price FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .get("price");
 
     let _ = query(
         r#"
         UPDATE products
-        SET name = $1, description = $2, primary_image_id = $3, price = $4, category_id = $5, updated_at = NOW()
+        SET name = $1, description = $2, primary_image_id = $3, price = $4, category_id = $5,
+            compare_at_price = $7, draft = COALESCE($8, draft), updated_at = NOW()
         WHERE id = $6
     "#,
     )
@@ -124,12 +283,133 @@ pub async fn product_update(
     .bind(product.primary_image_id)
     .bind(product.price)
     .bind(product.category_id)
-    .bind(product.id)
-    .execute(&**db_pool)
+    .bind(product_id)
+    .bind(product.compare_at_price)
+    .bind(product.draft)
+    .execute(&mut *tx)
     .await?;
+    // The requested primary image moves to position 1 of the gallery; an image that is
+    // not in this product's gallery leaves the current first image as primary.
+    sync_gallery(&mut tx, product_id).await?;
+
+    record_price_change(
+        &mut *tx,
+        product_id,
+        None,
+        Some(old_price),
+        product.price,
+        PRICE_SOURCE_MANUAL,
+    )
+    .await?;
+
+    tx.commit().await?;
 
     Ok("Product succeed update!".to_string())
 }
+/// Clones a product as a draft under a new name, copying only the requested parts.
+#[post("/product/<id>/duplicate", data = "<duplicate>")]
+pub async fn duplicate_product(
+    db_pool: &State<PgPool>,
+    id: i32,
+    duplicate: Json<ProductDuplicate>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    let actor_id = claims.sub;
+    Claims::check_admin(db_pool, claims).await?;
+    let duplicate = duplicate.into_inner();
+    let name = duplicate.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+
+    let mut tx = db_pool.begin().await?;
+
+    let source = query("SELECT * FROM products WHERE id = $1 FOR SHARE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let product_id: i32 = query(
+        r#"
+        INSERT INTO products (
+            name, description, price, compare_at_price, category_id, draft, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, TRUE, NOW(), NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(name)
+    .bind(
+        duplicate
+            .description
+            .then(|| source.get::<Option<String>, &str>("description"))
+            .flatten(),
+    )
+    .bind(source.get::<f32, &str>("price"))
+    .bind(source.get::<Option<f32>, &str>("compare_at_price"))
+    .bind(
+        duplicate
+            .category
+            .then(|| source.get::<Option<i32>, &str>("category_id"))
+            .flatten(),
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .get("id");
+
+    record_price_change(
+        &mut *tx,
+        product_id,
+        None,
+        None,
+        source.get("price"),
+        PRICE_SOURCE_MANUAL,
+    )
+    .await?;
+
+    if duplicate.sizes {
+        let sizes = query("SELECT * FROM product_sizes WHERE product_id = $1")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if let Some(sizes) = sizes {
+            let zeroed = |column: &str| sizes.get::<Option<i32>, &str>(column).map(|_| 0);
+            let size = Size {
+                product_id,
+                single_size: zeroed("single_size"),
+                s: zeroed("s"),
+                m: zeroed("m"),
+                l: zeroed("l"),
+                xl: zeroed("xl"),
+                xxl: zeroed("xxl"),
+            };
+            let size_id =
+                set_stock_levels(&mut tx, &size, STOCK_ADJUSTMENT, Some(actor_id)).await?;
+            query("UPDATE products SET size_id = $1 WHERE id = $2")
+                .bind(size_id)
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    if duplicate.bundle {
+        copy_bundle(&mut tx, id, product_id).await?;
+    }
+
+    let copied_files = match duplicate.images {
+        true => copy_product_images(&mut tx, id, product_id).await?,
+        false => Vec::new(),
+    };
+    if let Err(error) = tx.commit().await {
+        discard_files(&copied_files).await;
+        return Err(error.into());
+    }
+
+    Ok(Json(product_id))
+}
+
 #[delete("/product/<id>")]
 pub async fn delete_product(
     db_pool: &State<PgPool>,
@@ -137,6 +417,22 @@ pub async fn delete_product(
     claims: Claims,
 ) -> Result<String, ApiError> {
     Claims::check_admin(db_pool, claims).await?;
+    let mut tx = db_pool.begin().await?;
+
+    let in_bundle = query("SELECT 1 FROM bundle_components WHERE component_id = $1 LIMIT 1")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if in_bundle.is_some() {
+        return Err(ApiError::ProductInBundle);
+    }
+
+    let images =
+        query("DELETE FROM product_images WHERE product_id = $1 RETURNING image_url, image_key")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+
     let _ = query(
         r#"
         DELETE FROM products
@@ -144,7 +440,58 @@ pub async fn delete_product(
     "#,
     )
     .bind(id)
-    .execute(&**db_pool)
+    .execute(&mut *tx)
     .await?;
+    tx.commit().await?;
+
+    discard_files(
+        &images
+            .iter()
+            .flat_map(stored_filenames)
+            .collect::<Vec<String>>(),
+    )
+    .await;
     Ok("Product was successfully deleted!".to_string())
 }
+
+#[get("/product/<id>/tags")]
+pub async fn get_product_tags(
+    db_pool: &State<PgPool>,
+    id: i32,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let tags = query("SELECT tags FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&**db_pool)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .get("tags");
+
+    Ok(Json(tags))
+}
+
+#[put("/product/<id>/tags", data = "<tags>")]
+pub async fn set_product_tags(
+    db_pool: &State<PgPool>,
+    id: i32,
+    tags: Json<Vec<String>>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let mut tags = tags
+        .into_inner()
+        .into_iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<String>>();
+    tags.sort();
+    tags.dedup();
+
+    query("UPDATE products SET tags = $2, updated_at = NOW() WHERE id = $1 RETURNING id")
+        .bind(id)
+        .bind(&tags)
+        .fetch_optional(&**db_pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok("Product tags successfully updated".to_string())
+}
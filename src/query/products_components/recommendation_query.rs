@@ -0,0 +1,104 @@
+use crate::data::localization::locale::Locale;
+use crate::data::products_components::product::Product;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::bundle_query::bundle_in_stock;
+use crate::query::products_components::product_query::products_from_rows;
+use crate::utils::constants::orders::ORDER_STATUS_CANCELLED;
+use crate::utils::constants::products::{RECOMMENDATIONS_LIMIT, RECOMMENDATIONS_MAX_LIMIT};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::PgPool;
+
+#[get("/product/<product_id>/recommendations?<limit>&<currency>")]
+pub async fn get_recommendations(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    limit: Option<i64>,
+    currency: Option<&str>,
+    locale: Locale,
+) -> Result<Json<Vec<Product>>, ApiError> {
+    let limit = limit
+        .unwrap_or(RECOMMENDATIONS_LIMIT)
+        .clamp(1, RECOMMENDATIONS_MAX_LIMIT);
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT p.*
+        FROM (
+            SELECT id, MIN(tier) AS tier, MAX(score) AS score
+            FROM (
+                SELECT recommended_id AS id, 0 AS tier, score
+                FROM product_recommendations
+                WHERE product_id = $1
+                UNION ALL
+                SELECT p.id, 1 AS tier, 0 AS score
+                FROM products p
+                JOIN products source ON source.id = $1
+                WHERE p.category_id = source.category_id AND p.id != $1
+            ) candidates
+            GROUP BY id
+        ) ranked
+        JOIN products p ON p.id = ranked.id
+        WHERE NOT p.draft AND (
+            EXISTS (
+                SELECT 1 FROM product_sizes ps
+                WHERE ps.product_id = p.id
+                    AND COALESCE(ps.single_size, 0) + COALESCE(ps.s, 0) + COALESCE(ps.m, 0)
+                        + COALESCE(ps.l, 0) + COALESCE(ps.xl, 0) + COALESCE(ps.xxl, 0) > 0
+            )
+            OR {}
+        )
+        ORDER BY ranked.tier, ranked.score DESC, p.id
+        LIMIT $2
+        "#,
+        bundle_in_stock("p.id")
+    ))
+    .bind(product_id)
+    .bind(limit)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    products_from_rows(db_pool, rows, currency, None, locale)
+        .await
+        .map(Json)
+}
+
+#[post("/recommendations/refresh")]
+pub async fn refresh_recommendations_now(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    refresh_recommendations(db_pool).await?;
+
+    Ok("Recommendations successfully refreshed".to_string())
+}
+
+/// Recomputes co-purchase scores: the number of orders in which two products
+/// were bought together.
+pub async fn refresh_recommendations(db_pool: &PgPool) -> Result<(), ApiError> {
+    let mut tx = db_pool.begin().await?;
+
+    sqlx::query("DELETE FROM product_recommendations")
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO product_recommendations (product_id, recommended_id, score, updated_at)
+        SELECT a.product_id, b.product_id, COUNT(DISTINCT a.order_id), NOW()
+        FROM order_items a
+        JOIN order_items b ON b.order_id = a.order_id AND b.product_id != a.product_id
+        JOIN orders o ON o.id = a.order_id
+        WHERE o.status IS DISTINCT FROM $1
+        GROUP BY a.product_id, b.product_id
+        "#,
+    )
+    .bind(ORDER_STATUS_CANCELLED)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use crate::data::products_components::size::Size;
 use crate::data::user_components::claims::Claims;
 use crate::error::api_error::ApiError;
+use crate::query::products_components::stock_query::{set_stock_levels, MANUAL_STOCK_REASONS};
+use crate::query::products_components::stock_subscription_query::spawn_back_in_stock_notifications;
+use crate::utils::constants::products::{STOCK_ADJUSTMENT, STOCK_RESTOCK};
 use rocket::serde::json::Json;
 use rocket::State;
-use sqlx::{query, PgPool, Row};
+use sqlx::{PgPool, Row};
 
 #[post("/size", data = "<size>")]
 pub async fn create_size(
@@ -11,30 +14,12 @@ pub async fn create_size(
     size: Json<Size>,
     claims: Claims,
 ) -> Result<&'static str, ApiError> {
+    let actor_id = claims.sub;
     Claims::check_admin(db_pool, claims).await?;
     let size = size.into_inner();
 
-    let row = sqlx::query(
-        r#"
-        INSERT INTO product_sizes (
-            product_id, s, m, l, xl, xxl, single_size
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id
-        "#,
-    )
-    .bind(size.product_id)
-    .bind(size.s)
-    .bind(size.m)
-    .bind(size.l)
-    .bind(size.xl)
-    .bind(size.xxl)
-    .bind(size.single_size)
-    .fetch_one(&**db_pool)
-    .await
-    .expect("Error creating size in the database");
-
-    let size_id: i32 = row.get("id");
+    let mut tx = db_pool.begin().await?;
+    let size_id = set_stock_levels(&mut tx, &size, STOCK_RESTOCK, Some(actor_id)).await?;
 
     sqlx::query(
         r#"
@@ -45,9 +30,10 @@ pub async fn create_size(
     )
     .bind(size_id)
     .bind(size.product_id)
-    .execute(&**db_pool)
-    .await
-    .expect("Error updating products_components with size_id in the database");
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    spawn_back_in_stock_notifications(db_pool, Some(size.product_id));
 
     Ok("Size successfully created and linked to products_components")
 }
@@ -74,31 +60,25 @@ pub async fn get_size(db_pool: &State<PgPool>, product_id: i32) -> Result<Json<S
         xxl: row.get("xxl"),
     }))
 }
-#[put("/size/update", data = "<size>")]
+#[put("/size/update?<reason>", data = "<size>")]
 pub async fn update_size(
     db_pool: &State<PgPool>,
     size: Json<Size>,
+    reason: Option<&str>,
     claims: Claims,
 ) -> Result<String, ApiError> {
+    let actor_id = claims.sub;
     Claims::check_admin(db_pool, claims).await?;
     let size = size.into_inner();
+    let reason = reason.unwrap_or(STOCK_ADJUSTMENT);
+    if !MANUAL_STOCK_REASONS.contains(&reason) {
+        return Err(ApiError::BadRequest);
+    }
 
-    let _ = query(
-        r#"
-        UPDATE product_sizes
-        SET s = $1, m = $2, xl = $3, xxl = $4, l = $5, single_size = $6, product_id = $7
-        WHERE product_id = $7
-    "#,
-    )
-    .bind(size.s)
-    .bind(size.m)
-    .bind(size.xl)
-    .bind(size.xxl)
-    .bind(size.l)
-    .bind(size.single_size)
-    .bind(size.product_id)
-    .execute(&**db_pool)
-    .await?;
+    let mut tx = db_pool.begin().await?;
+    set_stock_levels(&mut tx, &size, reason, Some(actor_id)).await?;
+    tx.commit().await?;
+    spawn_back_in_stock_notifications(db_pool, Some(size.product_id));
 
     Ok("Size succeed update".to_string())
 }
@@ -0,0 +1,203 @@
+use crate::data::products_components::stock_alert::{
+    StockAlert, StockAlertSettings, StockThreshold,
+};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::mail::sender::send_mail_low_stock;
+use crate::utils::env_configuration::CONFIG;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgExecutor, PgPool, Row};
+
+#[get("/stock/alerts?<open>")]
+pub async fn get_stock_alerts(
+    db_pool: &State<PgPool>,
+    open: Option<bool>,
+    claims: Claims,
+) -> Result<Json<Vec<StockAlert>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    let alerts = sqlx::query_as::<_, StockAlert>(
+        r#"
+        SELECT a.*, p.name AS product_name
+        FROM stock_alerts a
+        JOIN products p ON p.id = a.product_id
+        WHERE ($1::BOOLEAN IS NULL OR (a.resolved_at IS NULL) = $1)
+        ORDER BY a.created_at DESC, a.id DESC
+        "#,
+    )
+    .bind(open)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(alerts))
+}
+
+#[get("/stock/alerts/settings")]
+pub async fn get_stock_alert_settings(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<Json<StockAlertSettings>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    let default_threshold = sqlx::query("SELECT default_threshold FROM stock_alert_settings")
+        .fetch_one(&**db_pool)
+        .await?
+        .get("default_threshold");
+
+    Ok(Json(StockAlertSettings { default_threshold }))
+}
+
+#[put("/stock/alerts/settings", data = "<settings>")]
+pub async fn update_stock_alert_settings(
+    db_pool: &State<PgPool>,
+    settings: Json<StockAlertSettings>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    if settings.default_threshold < 0 {
+        return Err(ApiError::BadRequest);
+    }
+
+    sqlx::query("UPDATE stock_alert_settings SET default_threshold = $1")
+        .bind(settings.default_threshold)
+        .execute(&**db_pool)
+        .await?;
+    check_low_stock(&**db_pool, None).await?;
+
+    Ok("Low stock threshold successfully updated".to_string())
+}
+
+/// Sets a per-product threshold; `null` falls back to the global default.
+#[put("/product/<product_id>/stock/threshold", data = "<threshold>")]
+pub async fn set_product_stock_threshold(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    threshold: Json<StockThreshold>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    if threshold.threshold.is_some_and(|threshold| threshold < 0) {
+        return Err(ApiError::BadRequest);
+    }
+
+    sqlx::query("UPDATE products SET low_stock_threshold = $2 WHERE id = $1")
+        .bind(product_id)
+        .bind(threshold.threshold)
+        .execute(&**db_pool)
+        .await?;
+    check_low_stock(&**db_pool, Some(product_id)).await?;
+
+    Ok("Low stock threshold successfully updated".to_string())
+}
+
+/// Opens an alert for every tracked size at or below its threshold and resolves
+/// open alerts whose stock has recovered. A size is tracked once it has stock or
+/// any ledger history, so sizes a product was never sold in stay quiet.
+pub async fn check_low_stock<'e>(
+    executor: impl PgExecutor<'e>,
+    product_id: Option<i32>,
+) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        WITH levels AS (
+            SELECT
+                ps.product_id,
+                v.size,
+                COALESCE(v.quantity, 0) AS stock,
+                COALESCE(p.low_stock_threshold, s.default_threshold) AS threshold
+            FROM product_sizes ps
+            JOIN products p ON p.id = ps.product_id
+            CROSS JOIN stock_alert_settings s
+            CROSS JOIN LATERAL (
+                VALUES ('single_size', ps.single_size), ('s', ps.s), ('m', ps.m),
+                       ('l', ps.l), ('xl', ps.xl), ('xxl', ps.xxl)
+            ) AS v(size, quantity)
+            WHERE ($1::INT IS NULL OR ps.product_id = $1)
+                AND (
+                    COALESCE(v.quantity, 0) > 0
+                    OR EXISTS (
+                        SELECT 1 FROM stock_movements m
+                        WHERE m.product_id = ps.product_id AND m.size = v.size
+                    )
+                )
+        ), resolved AS (
+            UPDATE stock_alerts a
+            SET resolved_at = NOW(), stock = l.stock
+            FROM levels l
+            WHERE a.product_id = l.product_id AND a.size = l.size
+                AND a.resolved_at IS NULL AND l.stock > l.threshold
+        )
+        INSERT INTO stock_alerts (product_id, size, stock, threshold, created_at)
+        SELECT product_id, size, stock, threshold, NOW()
+        FROM levels
+        WHERE stock <= threshold
+        ON CONFLICT (product_id, size) WHERE resolved_at IS NULL
+        DO UPDATE SET stock = EXCLUDED.stock, threshold = EXCLUDED.threshold
+        "#,
+    )
+    .bind(product_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Emails administrators one digest of the open alerts they have not been told
+/// about yet. Alerts are claimed and committed before the mail goes out, so no row
+/// stays locked during the SMTP round-trip; a failed send releases them for the
+/// next digest.
+pub async fn send_low_stock_digest(db_pool: &PgPool) -> Result<(), ApiError> {
+    let admin_emails = sqlx::query("SELECT email FROM users WHERE role = $1")
+        .bind(&CONFIG.get().unwrap().admin_role)
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get("email"))
+        .collect::<Vec<String>>();
+
+    if admin_emails.is_empty() {
+        return Ok(());
+    }
+
+    let alerts = sqlx::query_as::<_, StockAlert>(
+        r#"
+        WITH claimed AS (
+            UPDATE stock_alerts
+            SET notified_at = NOW()
+            WHERE id IN (
+                SELECT id FROM stock_alerts
+                WHERE resolved_at IS NULL AND notified_at IS NULL
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        )
+        SELECT a.*, p.name AS product_name
+        FROM claimed a
+        JOIN products p ON p.id = a.product_id
+        ORDER BY p.name, a.size
+        "#,
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    if alerts.is_empty() {
+        return Ok(());
+    }
+
+    let alert_ids = alerts.iter().map(|alert| alert.id).collect::<Vec<i32>>();
+    let sent = tokio::task::spawn_blocking(move || send_mail_low_stock(&admin_emails, &alerts))
+        .await
+        .map_err(|_| ApiError::InternalServerError)
+        .and_then(|sent| sent);
+
+    if let Err(error) = sent {
+        sqlx::query("UPDATE stock_alerts SET notified_at = NULL WHERE id = ANY($1)")
+            .bind(&alert_ids)
+            .execute(db_pool)
+            .await?;
+        return Err(error);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,424 @@
+use crate::data::products_components::size::Size;
+use crate::data::products_components::stock_movement::{StockDiscrepancy, StockMovement};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::query::products_components::stock_alert_query::check_low_stock;
+use crate::query::products_components::stock_subscription_query::spawn_back_in_stock_notifications;
+use crate::utils::constants::products::{
+    PRODUCT_SIZES, STOCK_ADJUSTMENT, STOCK_RESTOCK, STOCK_RETURN, STOCK_SALE, STOCK_STOCKTAKE,
+};
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{query, PgConnection, PgExecutor, PgPool, Row};
+
+/// Reasons an admin may record by hand; sales and returns only come from orders.
+pub const MANUAL_STOCK_REASONS: [&str; 3] = [STOCK_ADJUSTMENT, STOCK_RESTOCK, STOCK_STOCKTAKE];
+
+const DISCREPANCIES_QUERY: &str = r#"
+    WITH stock AS (
+        SELECT ps.product_id, v.size, COALESCE(v.quantity, 0) AS stock
+        FROM product_sizes ps
+        CROSS JOIN LATERAL (
+            VALUES ('single_size', ps.single_size), ('s', ps.s), ('m', ps.m),
+                   ('l', ps.l), ('xl', ps.xl), ('xxl', ps.xxl)
+        ) AS v(size, quantity)
+        WHERE ps.product_id IS NOT NULL
+    ), ledger AS (
+        SELECT product_id, size, SUM(delta)::INT AS ledger
+        FROM stock_movements
+        GROUP BY product_id, size
+    )
+    SELECT
+        COALESCE(s.product_id, l.product_id) AS product_id,
+        COALESCE(s.size, l.size) AS size,
+        COALESCE(s.stock, 0) AS stock,
+        COALESCE(l.ledger, 0) AS ledger
+    FROM stock s
+    FULL JOIN ledger l ON l.product_id = s.product_id AND l.size = s.size
+    WHERE COALESCE(s.stock, 0) != COALESCE(l.ledger, 0)
+    ORDER BY 1, 2
+"#;
+
+#[get("/product/<product_id>/stock/movements?<size>")]
+pub async fn get_stock_movements(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    size: Option<&str>,
+    claims: Claims,
+) -> Result<Json<Vec<StockMovement>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let size = size.map(|size| size_column(Some(size))).transpose()?;
+
+    let movements = sqlx::query_as::<_, StockMovement>(
+        r#"
+        SELECT * FROM stock_movements
+        WHERE product_id = $1 AND ($2::TEXT IS NULL OR size = $2)
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )
+    .bind(product_id)
+    .bind(size)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(movements))
+}
+
+#[post("/stock/movement", data = "<movement>")]
+pub async fn create_stock_movement(
+    db_pool: &State<PgPool>,
+    movement: Json<StockMovement>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    let actor_id = claims.sub;
+    Claims::check_admin(db_pool, claims).await?;
+    let movement = movement.into_inner();
+    if movement.delta == 0 || !MANUAL_STOCK_REASONS.contains(&movement.reason.as_str()) {
+        return Err(ApiError::BadRequest);
+    }
+    let column = size_column(Some(&movement.size))?;
+
+    let mut tx = db_pool.begin().await?;
+
+    let stock: Option<i32> = query(&format!(
+        "SELECT {} AS stock FROM product_sizes WHERE product_id = $1 FOR UPDATE",
+        column
+    ))
+    .bind(movement.product_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?
+    .get("stock");
+
+    if stock.unwrap_or_default() + movement.delta < 0 {
+        return Err(ApiError::BadRequest);
+    }
+
+    query(&format!(
+        "UPDATE product_sizes SET {0} = COALESCE({0}, 0) + $2, updated_at = NOW() WHERE product_id = $1",
+        column
+    ))
+    .bind(movement.product_id)
+    .bind(movement.delta)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = record_stock_movement(
+        &mut *tx,
+        movement.product_id,
+        column,
+        movement.delta,
+        &movement.reason,
+        Some(actor_id),
+        None,
+        movement.note.as_deref(),
+    )
+    .await?;
+    check_low_stock(&mut *tx, Some(movement.product_id)).await?;
+    tx.commit().await?;
+    spawn_back_in_stock_notifications(db_pool, Some(movement.product_id));
+
+    Ok(Json(id))
+}
+
+#[get("/stock/reconcile")]
+pub async fn get_stock_discrepancies(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<Json<Vec<StockDiscrepancy>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    let discrepancies = sqlx::query_as::<_, StockDiscrepancy>(DISCREPANCIES_QUERY)
+        .fetch_all(&**db_pool)
+        .await?;
+
+    Ok(Json(discrepancies))
+}
+
+/// Treats the counts in `product_sizes` as the physical truth and writes a
+/// stocktake correction into the ledger for every size that disagrees.
+#[post("/stock/reconcile")]
+pub async fn reconcile_stock(
+    db_pool: &State<PgPool>,
+    claims: Claims,
+) -> Result<Json<Vec<StockDiscrepancy>>, ApiError> {
+    let actor_id = claims.sub;
+    Claims::check_admin(db_pool, claims).await?;
+
+    let mut tx = db_pool.begin().await?;
+    sqlx::query("LOCK TABLE product_sizes IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+
+    let discrepancies = sqlx::query_as::<_, StockDiscrepancy>(DISCREPANCIES_QUERY)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for discrepancy in discrepancies.iter() {
+        record_stock_movement(
+            &mut *tx,
+            discrepancy.product_id,
+            &discrepancy.size,
+            discrepancy.stock - discrepancy.ledger,
+            STOCK_STOCKTAKE,
+            Some(actor_id),
+            None,
+            Some("reconciliation"),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(discrepancies))
+}
+
+/// Locks the stock row of a product and takes `quantity` of `size` out of it,
+/// failing with the product name when there is not enough left.
+pub async fn reserve_stock(
+    conn: &mut PgConnection,
+    product_id: i32,
+    size: Option<&str>,
+    quantity: i32,
+    order_id: i32,
+    actor_id: Option<i32>,
+) -> Result<(), ApiError> {
+    if quantity <= 0 {
+        return Err(ApiError::BadRequest);
+    }
+    let column = size_column(size)?;
+
+    let stock: Option<i32> = query(&format!(
+        "SELECT {} AS stock FROM product_sizes WHERE product_id = $1 FOR UPDATE",
+        column
+    ))
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .and_then(|row| row.get("stock"));
+
+    if stock.unwrap_or_default() < quantity {
+        let name: String = query("SELECT name FROM products WHERE id = $1")
+            .bind(product_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(ApiError::NotFound)?
+            .get("name");
+
+        return Err(ApiError::OutOfStock(match size {
+            Some(size) if column != PRODUCT_SIZES[0] => {
+                format!("{} ({})", name, size.to_uppercase())
+            }
+            _ => name,
+        }));
+    }
+
+    query(&format!(
+        "UPDATE product_sizes SET {0} = {0} - $2, updated_at = NOW() WHERE product_id = $1",
+        column
+    ))
+    .bind(product_id)
+    .bind(quantity)
+    .execute(&mut *conn)
+    .await?;
+
+    record_stock_movement(
+        &mut *conn,
+        product_id,
+        column,
+        -quantity,
+        STOCK_SALE,
+        actor_id,
+        Some(order_id),
+        None,
+    )
+    .await?;
+    check_low_stock(&mut *conn, Some(product_id)).await?;
+
+    Ok(())
+}
+
+/// Puts `quantity` of `size` back into the stock of a product.
+pub async fn release_stock(
+    conn: &mut PgConnection,
+    product_id: i32,
+    size: Option<&str>,
+    quantity: i32,
+    order_id: i32,
+    actor_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let column = size_column(size)?;
+
+    query(&format!(
+        "UPDATE product_sizes SET {0} = COALESCE({0}, 0) + $2, updated_at = NOW() WHERE product_id = $1",
+        column
+    ))
+    .bind(product_id)
+    .bind(quantity)
+    .execute(&mut *conn)
+    .await?;
+
+    record_stock_movement(
+        &mut *conn,
+        product_id,
+        column,
+        quantity,
+        STOCK_RETURN,
+        actor_id,
+        Some(order_id),
+        None,
+    )
+    .await?;
+    check_low_stock(&mut *conn, Some(product_id)).await?;
+
+    Ok(())
+}
+
+/// Overwrites the stock levels of a product, creating its `product_sizes` row
+/// if needed, and records the difference for every size in the ledger. Sizes
+/// left as `None` keep their current level. Returns the `product_sizes` id.
+pub async fn set_stock_levels(
+    conn: &mut PgConnection,
+    size: &Size,
+    reason: &str,
+    actor_id: Option<i32>,
+) -> Result<i32, ApiError> {
+    let requested = [size.single_size, size.s, size.m, size.l, size.xl, size.xxl];
+    if requested.iter().flatten().any(|quantity| *quantity < 0) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let current = query(
+        r#"
+        SELECT id, single_size, s, m, l, xl, xxl
+        FROM product_sizes
+        WHERE product_id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(size.product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let old_levels = PRODUCT_SIZES.map(|column| {
+        current
+            .as_ref()
+            .and_then(|row| row.get::<Option<i32>, &str>(column))
+            .unwrap_or_default()
+    });
+    let mut new_levels = old_levels;
+    for (level, requested) in new_levels.iter_mut().zip(requested) {
+        if let Some(requested) = requested {
+            *level = requested;
+        }
+    }
+
+    let size_id: i32 = match current {
+        Some(row) => {
+            query(
+                r#"
+                UPDATE product_sizes
+                SET single_size = $2, s = $3, m = $4, l = $5, xl = $6, xxl = $7, updated_at = NOW()
+                WHERE product_id = $1
+                "#,
+            )
+            .bind(size.product_id)
+            .bind(new_levels[0])
+            .bind(new_levels[1])
+            .bind(new_levels[2])
+            .bind(new_levels[3])
+            .bind(new_levels[4])
+            .bind(new_levels[5])
+            .execute(&mut *conn)
+            .await?;
+            row.get("id")
+        }
+        None => query(
+            r#"
+            INSERT INTO product_sizes (product_id, single_size, s, m, l, xl, xxl)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(size.product_id)
+        .bind(new_levels[0])
+        .bind(new_levels[1])
+        .bind(new_levels[2])
+        .bind(new_levels[3])
+        .bind(new_levels[4])
+        .bind(new_levels[5])
+        .fetch_one(&mut *conn)
+        .await?
+        .get("id"),
+    };
+
+    for ((column, old), new) in PRODUCT_SIZES.iter().zip(old_levels).zip(new_levels) {
+        if new != old {
+            record_stock_movement(
+                &mut *conn,
+                size.product_id,
+                column,
+                new - old,
+                reason,
+                actor_id,
+                None,
+                None,
+            )
+            .await?;
+        }
+    }
+    check_low_stock(&mut *conn, Some(size.product_id)).await?;
+
+    Ok(size_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn record_stock_movement<'e>(
+    executor: impl PgExecutor<'e>,
+    product_id: i32,
+    size: &str,
+    delta: i32,
+    reason: &str,
+    actor_id: Option<i32>,
+    order_id: Option<i32>,
+    note: Option<&str>,
+) -> Result<i32, ApiError> {
+    let id = query(
+        r#"
+        INSERT INTO stock_movements (
+            product_id, size, delta, reason, actor_id, order_id, note, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(product_id)
+    .bind(size)
+    .bind(delta)
+    .bind(reason)
+    .bind(actor_id)
+    .bind(order_id)
+    .bind(note)
+    .fetch_one(executor)
+    .await?
+    .get("id");
+
+    Ok(id)
+}
+
+/// Maps an order item size to its `product_sizes` column; items without a size
+/// are taken from `single_size`.
+pub fn size_column(size: Option<&str>) -> Result<&'static str, ApiError> {
+    let size = size
+        .map(str::trim)
+        .filter(|size| !size.is_empty())
+        .map(str::to_lowercase);
+
+    match size {
+        None => Ok(PRODUCT_SIZES[0]),
+        Some(size) => PRODUCT_SIZES
+            .iter()
+            .find(|column| **column == size)
+            .copied()
+            .ok_or(ApiError::BadRequest),
+    }
+}
@@ -0,0 +1,263 @@
+use crate::data::products_components::stock_subscription::NewStockSubscription;
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::mail::sender::{
+    generate_api_link, send_mail_back_in_stock, send_mail_stock_subscription_confirmation,
+};
+use crate::query::products_components::bundle_query::{bundle_in_stock, load_bundles};
+use crate::query::products_components::stock_query::size_column;
+use crate::utils::constants::products::PRODUCT_SIZES;
+use crate::utils::constants::routes::MAIN_URL;
+use lettre::Address;
+use rocket::response::Redirect;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{query, PgPool, Row};
+use uuid::Uuid;
+
+/// Subscribes to a size that is currently sold out. Subscriptions of logged-in users
+/// are active right away; guest subscriptions wait for the emailed confirmation link.
+#[post("/product/<product_id>/stock/subscribe", data = "<subscription>")]
+pub async fn subscribe_to_stock(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    subscription: Json<NewStockSubscription>,
+    claims: Option<Claims>,
+) -> Result<&'static str, ApiError> {
+    let subscription = subscription.into_inner();
+    let mut column = size_column(subscription.size.as_deref())?;
+
+    let row = query(&format!(
+        r#"
+        SELECT p.name, COALESCE(ps.{}, 0) AS stock
+        FROM products p
+        LEFT JOIN product_sizes ps ON ps.product_id = p.id
+        WHERE p.id = $1 AND NOT p.draft
+        "#,
+        column
+    ))
+    .bind(product_id)
+    .fetch_optional(&**db_pool)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    // Bundles have no sizes or stock of their own; they are in stock when their components are.
+    let in_stock = match load_bundles(&**db_pool, &[product_id])
+        .await?
+        .remove(&product_id)
+    {
+        Some(bundle) => {
+            column = PRODUCT_SIZES[0];
+            bundle.available > 0
+        }
+        None => row.get::<i32, &str>("stock") > 0,
+    };
+    if in_stock {
+        return Err(ApiError::AlreadyInStock);
+    }
+    let product_name: String = row.get("name");
+
+    let (user_id, email) = match claims {
+        Some(claims) => {
+            let email: String = sqlx::query("SELECT email FROM users WHERE id = $1")
+                .bind(claims.sub)
+                .fetch_optional(&**db_pool)
+                .await?
+                .ok_or(ApiError::Unauthorized)?
+                .get("email");
+            (Some(claims.sub), email)
+        }
+        None => {
+            let email = subscription
+                .email
+                .map(|email| email.trim().to_string())
+                .ok_or(ApiError::BadRequest)?;
+            email.parse::<Address>().map_err(|_| ApiError::BadRequest)?;
+            (None, email)
+        }
+    };
+
+    // A logged-in subscriber takes over a guest subscription to the same email that
+    // was never confirmed.
+    let token = Uuid::new_v4().simple().to_string();
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO stock_subscriptions (product_id, size, user_id, email, token, confirmed_at)
+        VALUES ($1, $2, $3, $4, $5, CASE WHEN $3::INT IS NULL THEN NULL ELSE NOW() END)
+        ON CONFLICT (product_id, size, LOWER(email)) WHERE notified_at IS NULL
+        DO UPDATE SET user_id = EXCLUDED.user_id, confirmed_at = EXCLUDED.confirmed_at
+        WHERE stock_subscriptions.confirmed_at IS NULL AND EXCLUDED.confirmed_at IS NOT NULL
+        "#,
+    )
+    .bind(product_id)
+    .bind(column)
+    .bind(user_id)
+    .bind(&email)
+    .bind(&token)
+    .execute(&**db_pool)
+    .await?
+    .rows_affected();
+
+    if inserted == 0 {
+        return Ok("Already subscribed");
+    }
+    if user_id.is_none() {
+        let sent = send_mail_stock_subscription_confirmation(
+            &email,
+            &product_name,
+            &generate_api_link(&format!("stock/subscription/confirm?token={}", token)),
+        );
+        if let Err(error) = sent {
+            sqlx::query("DELETE FROM stock_subscriptions WHERE token = $1")
+                .bind(&token)
+                .execute(&**db_pool)
+                .await?;
+            return Err(error);
+        }
+        return Ok("Confirmation email sent");
+    }
+
+    Ok("Subscribed successfully")
+}
+
+#[get("/stock/subscription/confirm?<token>")]
+pub async fn confirm_stock_subscription(
+    db_pool: &State<PgPool>,
+    token: &str,
+) -> Result<Redirect, ApiError> {
+    sqlx::query(
+        r#"
+        UPDATE stock_subscriptions
+        SET confirmed_at = COALESCE(confirmed_at, NOW())
+        WHERE token = $1
+        "#,
+    )
+    .bind(token)
+    .execute(&**db_pool)
+    .await?;
+
+    Ok(Redirect::to(MAIN_URL))
+}
+
+#[get("/stock/subscription/unsubscribe?<token>")]
+pub async fn unsubscribe_from_stock(
+    db_pool: &State<PgPool>,
+    token: &str,
+) -> Result<Redirect, ApiError> {
+    sqlx::query("DELETE FROM stock_subscriptions WHERE token = $1")
+        .bind(token)
+        .execute(&**db_pool)
+        .await?;
+
+    Ok(Redirect::to(MAIN_URL))
+}
+
+/// Emails every confirmed subscriber whose size is back in stock. Subscriptions are
+/// marked as notified before sending, so each one is sent at most once; a failed
+/// email puts the subscription back as pending for the next run.
+pub async fn notify_back_in_stock(
+    db_pool: &PgPool,
+    product_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let subscriptions = sqlx::query(&format!(
+        r#"
+        WITH claimed AS (
+            UPDATE stock_subscriptions
+            SET notified_at = NOW()
+            WHERE id IN (
+                SELECT s.id
+                FROM stock_subscriptions s
+                WHERE s.confirmed_at IS NOT NULL AND s.notified_at IS NULL
+                    AND (
+                        $1::INT IS NULL OR s.product_id = $1 OR s.product_id IN (
+                            SELECT bundle_id FROM bundle_components WHERE component_id = $1
+                        )
+                    )
+                    AND (
+                        EXISTS (
+                            SELECT 1
+                            FROM product_sizes ps
+                            CROSS JOIN LATERAL (
+                                VALUES ('single_size', ps.single_size), ('s', ps.s),
+                                       ('m', ps.m), ('l', ps.l), ('xl', ps.xl), ('xxl', ps.xxl)
+                            ) AS v(size, quantity)
+                            WHERE ps.product_id = s.product_id
+                                AND v.size = s.size AND v.quantity > 0
+                        )
+                        OR {}
+                    )
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, product_id, email, size, token
+        )
+        SELECT c.id, c.email, c.size, c.token, p.name AS product_name
+        FROM claimed c
+        JOIN products p ON p.id = c.product_id
+        ORDER BY c.id
+        "#,
+        bundle_in_stock("s.product_id")
+    ))
+    .bind(product_id)
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        let token: String = row.get("token");
+        (
+            row.get::<i32, &str>("id"),
+            row.get::<String, &str>("email"),
+            row.get::<String, &str>("product_name"),
+            row.get::<String, &str>("size"),
+            generate_api_link(&format!("stock/subscription/unsubscribe?token={}", token)),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    let claimed_ids = subscriptions
+        .iter()
+        .map(|(id, ..)| *id)
+        .collect::<Vec<i32>>();
+    let failed_ids = tokio::task::spawn_blocking(move || {
+        subscriptions
+            .into_iter()
+            .filter_map(|(id, email, product_name, size, unsubscribe_link)| {
+                match send_mail_back_in_stock(&email, &product_name, &size, &unsubscribe_link) {
+                    Ok(_) => None,
+                    Err(error) => {
+                        log::error!(
+                            "Back in stock email for subscription {} failed: {}",
+                            id,
+                            error
+                        );
+                        Some(id)
+                    }
+                }
+            })
+            .collect::<Vec<i32>>()
+    })
+    .await
+    .unwrap_or(claimed_ids);
+
+    if !failed_ids.is_empty() {
+        sqlx::query("UPDATE stock_subscriptions SET notified_at = NULL WHERE id = ANY($1)")
+            .bind(&failed_ids)
+            .execute(db_pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Runs [`notify_back_in_stock`] in the background once a stock change is committed,
+/// so sending emails never holds up the admin request.
+pub fn spawn_back_in_stock_notifications(db_pool: &PgPool, product_id: Option<i32>) {
+    let db_pool = db_pool.clone();
+    tokio::spawn(async move {
+        if let Err(error) = notify_back_in_stock(&db_pool, product_id).await {
+            log::error!("Back in stock notifications failed: {}", error);
+        }
+    });
+}
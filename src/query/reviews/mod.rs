@@ -0,0 +1 @@
+pub mod review_query;
@@ -0,0 +1,437 @@
+use crate::data::reviews::review::{NewReview, NewReviewPhoto, Review, ReviewPage, ReviewPhoto};
+use crate::data::user_components::claims::Claims;
+use crate::error::api_error::ApiError;
+use crate::storage::storage;
+use crate::utils::constants::orders::ORDER_STATUS_DELIVERED;
+use crate::utils::constants::reviews::{
+    REVIEWS_MAX_PER_PAGE, REVIEWS_PER_PAGE, REVIEW_APPROVED, REVIEW_HIDDEN, REVIEW_MAX_PHOTOS,
+    REVIEW_PENDING,
+};
+use crate::utils::image_processing::{process_image, variant_filename, variant_filenames};
+use rocket::form::Form;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde_json::Value;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+#[post("/product/<product_id>/review", data = "<review>")]
+pub async fn create_review(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    review: Json<NewReview>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    let review = review.into_inner();
+    if !(1..=5).contains(&review.rating) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let eligibility = sqlx::query(
+        r#"
+        SELECT
+            EXISTS (
+                SELECT 1 FROM orders o
+                JOIN order_items oi ON oi.order_id = o.id
+                WHERE o.user_id = $1 AND oi.product_id = $2 AND o.status = $3
+            ) AS purchased,
+            EXISTS (
+                SELECT 1 FROM reviews WHERE user_id = $1 AND product_id = $2
+            ) AS reviewed
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(product_id)
+    .bind(ORDER_STATUS_DELIVERED)
+    .fetch_one(&**db_pool)
+    .await?;
+
+    if !eligibility.get::<bool, &str>("purchased") {
+        return Err(ApiError::ReviewNotAllowed);
+    }
+    if eligibility.get::<bool, &str>("reviewed") {
+        return Err(ApiError::ReviewExists);
+    }
+
+    let id: i32 = sqlx::query(
+        r#"
+        INSERT INTO reviews (
+            product_id, user_id, rating, text, status, created_at, updated_at
+        )
+        VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(product_id)
+    .bind(claims.sub)
+    .bind(review.rating)
+    .bind(review.text)
+    .bind(REVIEW_PENDING)
+    .fetch_one(&**db_pool)
+    .await?
+    .get("id");
+
+    Ok(Json(id))
+}
+
+#[post("/review/<id>/photo", data = "<photo_form>")]
+pub async fn add_review_photo(
+    db_pool: &State<PgPool>,
+    id: i32,
+    photo_form: Form<NewReviewPhoto<'_>>,
+    claims: Claims,
+) -> Result<&'static str, ApiError> {
+    let review = sqlx::query(
+        r#"
+        SELECT user_id, (SELECT COUNT(*) FROM review_photos WHERE review_id = $1) AS photos
+        FROM reviews
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&**db_pool)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    if review.get::<Option<i32>, &str>("user_id") != Some(claims.sub) {
+        return Err(ApiError::Unauthorized);
+    }
+    if review.get::<i64, &str>("photos") >= REVIEW_MAX_PHOTOS {
+        return Err(ApiError::BadRequest);
+    }
+
+    let mut image_data = Vec::new();
+    photo_form
+        .into_inner()
+        .image
+        .open()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .read_to_end(&mut image_data)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let image_key = format!("review-{}", Uuid::new_v4());
+    let processed = {
+        let image_key = image_key.clone();
+        tokio::task::spawn_blocking(move || process_image(&image_key, &image_data))
+            .await
+            .map_err(|_| ApiError::InternalServerError)??
+    };
+    for variant in processed {
+        storage().put(&variant.filename, variant.bytes).await?;
+    }
+
+    // The count above only saves decoding when the limit is already reached; the
+    // review row lock makes the real check safe against concurrent uploads.
+    let mut tx = db_pool.begin().await?;
+    let photos: i64 = sqlx::query(
+        r#"
+        SELECT (SELECT COUNT(*) FROM review_photos WHERE review_id = r.id) AS photos
+        FROM reviews r
+        WHERE r.id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .map_or(REVIEW_MAX_PHOTOS, |row| row.get("photos"));
+
+    if photos >= REVIEW_MAX_PHOTOS {
+        tx.rollback().await?;
+        for filename in variant_filenames(&image_key) {
+            if let Err(error) = storage().delete(&filename).await {
+                log::error!(
+                    "Failed to remove rejected review photo {}: {}",
+                    filename,
+                    error
+                );
+            }
+        }
+        return Err(ApiError::BadRequest);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO review_photos (review_id, image_key, created_at)
+        VALUES ($1, $2, NOW())
+        "#,
+    )
+    .bind(id)
+    .bind(&image_key)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok("Photo successfully added")
+}
+
+#[get("/product/<product_id>/reviews?<sort>&<page>&<per_page>")]
+pub async fn get_product_reviews(
+    db_pool: &State<PgPool>,
+    product_id: i32,
+    sort: Option<&str>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<Json<ReviewPage>, ApiError> {
+    get_review_page(
+        db_pool,
+        Some(product_id),
+        Some(REVIEW_APPROVED),
+        sort,
+        page,
+        per_page,
+    )
+    .await
+    .map(Json)
+}
+
+#[get("/reviews?<status>&<product_id>&<sort>&<page>&<per_page>")]
+pub async fn get_reviews(
+    db_pool: &State<PgPool>,
+    status: Option<&str>,
+    product_id: Option<i32>,
+    sort: Option<&str>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    claims: Claims,
+) -> Result<Json<ReviewPage>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    get_review_page(db_pool, product_id, status, sort, page, per_page)
+        .await
+        .map(Json)
+}
+
+#[put("/review/<id>/status", data = "<status>")]
+pub async fn moderate_review(
+    db_pool: &State<PgPool>,
+    id: i32,
+    status: Json<Value>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let status = status
+        .get("status")
+        .and_then(Value::as_str)
+        .filter(|status| [REVIEW_PENDING, REVIEW_APPROVED, REVIEW_HIDDEN].contains(status))
+        .ok_or(ApiError::BadRequest)?;
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE reviews
+        SET status = $2, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .execute(&**db_pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok("Review status successfully updated".to_string())
+}
+
+#[put("/review/<id>/reply", data = "<reply>")]
+pub async fn reply_to_review(
+    db_pool: &State<PgPool>,
+    id: i32,
+    reply: Json<Value>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let reply = reply
+        .get("reply")
+        .and_then(Value::as_str)
+        .filter(|reply| !reply.trim().is_empty());
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE reviews
+        SET admin_reply = $2,
+            replied_at = CASE WHEN $2::TEXT IS NULL THEN NULL ELSE NOW() END,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(reply)
+    .execute(&**db_pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok("Review reply successfully saved".to_string())
+}
+
+#[delete("/review/<id>")]
+pub async fn delete_review(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+    let mut tx = db_pool.begin().await?;
+    let photos = sqlx::query("DELETE FROM review_photos WHERE review_id = $1 RETURNING image_key")
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let deleted = sqlx::query("DELETE FROM reviews WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    tx.commit().await?;
+
+    // Blobs go only once the rows are gone for good, so a failed delete never leaves
+    // a review pointing at missing photos. The review is deleted either way, so a
+    // blob that cannot be removed is only logged.
+    for photo in photos {
+        for filename in variant_filenames(photo.get("image_key")) {
+            if let Err(error) = storage().delete(&filename).await {
+                log::error!("Failed to remove review photo {}: {}", filename, error);
+            }
+        }
+    }
+
+    Ok("Review successfully deleted".to_string())
+}
+
+/// Average rating and number of approved reviews per product.
+pub async fn get_rating_summaries(
+    db_pool: &PgPool,
+    product_id: Option<i32>,
+) -> Result<HashMap<i32, (f32, i64)>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT product_id, AVG(rating)::REAL AS average, COUNT(*) AS count
+        FROM reviews
+        WHERE status = $1 AND ($2::INT IS NULL OR product_id = $2)
+        GROUP BY product_id
+        "#,
+    )
+    .bind(REVIEW_APPROVED)
+    .bind(product_id)
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("product_id"),
+                (row.get("average"), row.get("count")),
+            )
+        })
+        .collect())
+}
+
+async fn get_review_page(
+    db_pool: &PgPool,
+    product_id: Option<i32>,
+    status: Option<&str>,
+    sort: Option<&str>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<ReviewPage, ApiError> {
+    let page = page.unwrap_or(1).max(1);
+    let per_page = per_page
+        .unwrap_or(REVIEWS_PER_PAGE)
+        .clamp(1, REVIEWS_MAX_PER_PAGE);
+    let order_by = match sort.unwrap_or("newest") {
+        "oldest" => "r.created_at ASC",
+        "highest" => "r.rating DESC, r.created_at DESC",
+        "lowest" => "r.rating ASC, r.created_at DESC",
+        _ => "r.created_at DESC",
+    };
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT r.*, u.username, COUNT(*) OVER () AS total
+        FROM reviews r
+        LEFT JOIN users u ON u.id = r.user_id
+        WHERE ($1::INT IS NULL OR r.product_id = $1)
+            AND ($2::TEXT IS NULL OR r.status = $2)
+        ORDER BY {}
+        LIMIT $3 OFFSET $4
+        "#,
+        order_by
+    ))
+    .bind(product_id)
+    .bind(status)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(db_pool)
+    .await?;
+
+    let total = rows.first().map_or(0, |row| row.get::<i64, &str>("total"));
+    let review_ids = rows.iter().map(|row| row.get("id")).collect::<Vec<i32>>();
+    let mut photos = get_review_photos(db_pool, &review_ids).await?;
+
+    Ok(ReviewPage {
+        items: rows
+            .iter()
+            .map(|row| review_from_row(row, &mut photos))
+            .collect(),
+        total,
+        page,
+        per_page,
+    })
+}
+
+async fn get_review_photos(
+    db_pool: &PgPool,
+    review_ids: &[i32],
+) -> Result<HashMap<i32, Vec<ReviewPhoto>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT review_id, image_key
+        FROM review_photos
+        WHERE review_id = ANY($1)
+        ORDER BY id
+        "#,
+    )
+    .bind(review_ids)
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut photos: HashMap<i32, Vec<ReviewPhoto>> = HashMap::new();
+    for row in rows {
+        let image_key: String = row.get("image_key");
+        photos
+            .entry(row.get("review_id"))
+            .or_default()
+            .push(ReviewPhoto {
+                thumbnail_url: storage().url(&variant_filename(&image_key, "thumbnail", "jpg")),
+                url: storage().url(&variant_filename(&image_key, "full", "jpg")),
+            });
+    }
+    Ok(photos)
+}
+
+fn review_from_row(row: &PgRow, photos: &mut HashMap<i32, Vec<ReviewPhoto>>) -> Review {
+    let id: i32 = row.get("id");
+    Review {
+        id,
+        product_id: row.get("product_id"),
+        user_id: row.get("user_id"),
+        username: row.get("username"),
+        rating: row.get("rating"),
+        text: row.get("text"),
+        status: row.get("status"),
+        admin_reply: row.get("admin_reply"),
+        replied_at: row.get("replied_at"),
+        created_at: row.get("created_at"),
+        photos: photos.remove(&id).unwrap_or_default(),
+    }
+}
@@ -4,6 +4,7 @@ use crate::data::user_components::user::{JwtUser, TempUser, User, UserProfile};
 use crate::error::api_error::ApiError;
 use crate::error::api_error::ApiError::DatabaseError;
 use crate::mail::sender::{generate_registration_link, send_mail_registration};
+use crate::query::wishlists::wishlist_query::merge_wishlist;
 use crate::utils::constants::routes::{LOGIN, MAIN_URL};
 use crate::utils::env_configuration::CONFIG;
 use bcrypt::{hash, verify, DEFAULT_COST};
@@ -61,6 +62,13 @@ pub async fn login(
         return Err(ApiError::Unauthorized);
     }
 
+    if let Some(wishlist) = login_data.wishlist {
+        // A wishlist that fails to merge must not keep the customer from signing in.
+        if let Err(error) = merge_wishlist(db_pool, user.id, wishlist).await {
+            log::error!("Failed to merge wishlist for user {}: {}", user.id, error);
+        }
+    }
+
     let secret = CONFIG.get().unwrap().jwt_secret.as_str();
     let claims = Claims::new(user.id, user.role);
     let token = encode(
@@ -255,10 +263,7 @@ pub async fn try_registration(
 
 fn check_error_field(new_user: &mut TempUser, exist: Option<PgRow>) -> Option<ApiError> {
     if let Some(row) = exist {
-        let existing_conflict: Option<String> = match row.try_get("conflicted") {
-            Ok(conflicted) => Some(conflicted),
-            Err(_) => None,
-        };
+        let existing_conflict: Option<String> = row.try_get("conflicted").ok();
         if existing_conflict == Some(new_user.email.clone()) {
             return Some(ApiError::EmailError);
         }
@@ -0,0 +1 @@
+pub mod wishlist_query;
@@ -0,0 +1,257 @@
+use crate::data::localization::locale::Locale;
+use crate::data::user_components::claims::Claims;
+use crate::data::wishlists::wishlist::{NewWishlistItem, WishlistItem, WishlistStat};
+use crate::error::api_error::ApiError;
+use crate::query::products_components::bundle_query::load_bundles;
+use crate::query::products_components::product_query::products_from_rows;
+use crate::query::products_components::stock_query::size_column;
+use crate::utils::constants::products::PRODUCT_SIZES;
+use rocket::serde::json::Json;
+use rocket::State;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+#[get("/wishlist?<currency>")]
+pub async fn get_wishlist(
+    db_pool: &State<PgPool>,
+    currency: Option<&str>,
+    locale: Locale,
+    claims: Claims,
+) -> Result<Json<Vec<WishlistItem>>, ApiError> {
+    let items = sqlx::query(
+        r#"
+        SELECT id, product_id, size, created_at
+        FROM wishlist_items
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )
+    .bind(claims.sub)
+    .fetch_all(&**db_pool)
+    .await?;
+
+    let product_ids = items
+        .iter()
+        .map(|item| item.get("product_id"))
+        .collect::<Vec<i32>>();
+    let product_rows = sqlx::query("SELECT * FROM products WHERE id = ANY($1) AND NOT draft")
+        .bind(&product_ids)
+        .fetch_all(&**db_pool)
+        .await?;
+    let products = products_from_rows(db_pool, product_rows, currency, None, locale)
+        .await?
+        .into_iter()
+        .filter_map(|product| product.id.map(|id| (id, product)))
+        .collect::<HashMap<_, _>>();
+
+    let stock = sqlx::query(
+        r#"
+        SELECT product_id, single_size, s, m, l, xl, xxl
+        FROM product_sizes
+        WHERE product_id = ANY($1)
+        "#,
+    )
+    .bind(&product_ids)
+    .fetch_all(&**db_pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        let levels = PRODUCT_SIZES.map(|column| {
+            row.get::<Option<i32>, &str>(column)
+                .unwrap_or_default()
+                .max(0)
+        });
+        (row.get::<i32, &str>("product_id"), levels)
+    })
+    .collect::<HashMap<i32, [i32; 6]>>();
+    // Bundles have no stock of their own; it follows from their components.
+    let bundles = load_bundles(&**db_pool, &product_ids).await?;
+
+    Ok(Json(
+        items
+            .into_iter()
+            .filter_map(|item| {
+                let product_id: i32 = item.get("product_id");
+                let size: Option<String> = item.get("size");
+                let levels = stock.get(&product_id).copied().unwrap_or_default();
+                let stock = match (bundles.get(&product_id), size.as_deref()) {
+                    (Some(bundle), _) => bundle.available,
+                    (None, Some(size)) => PRODUCT_SIZES
+                        .iter()
+                        .position(|column| *column == size)
+                        .map_or(0, |index| levels[index]),
+                    (None, None) => levels.iter().sum(),
+                };
+                // The same product may be saved in several sizes.
+                let product = products.get(&product_id)?.clone();
+                Some(WishlistItem {
+                    id: item.get("id"),
+                    size,
+                    stock,
+                    in_stock: stock > 0,
+                    added_at: item.get("created_at"),
+                    product,
+                })
+            })
+            .collect(),
+    ))
+}
+
+#[post("/wishlist", data = "<item>")]
+pub async fn add_to_wishlist(
+    db_pool: &State<PgPool>,
+    item: Json<NewWishlistItem>,
+    claims: Claims,
+) -> Result<Json<i32>, ApiError> {
+    let item = item.into_inner();
+    let size = wishlist_size(item.size.as_deref())?;
+
+    let exists = sqlx::query("SELECT 1 FROM products WHERE id = $1 AND NOT draft")
+        .bind(item.product_id)
+        .fetch_optional(&**db_pool)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let id = insert_wishlist_item(db_pool, claims.sub, item.product_id, size).await?;
+
+    Ok(Json(id))
+}
+
+#[delete("/wishlist/<id>")]
+pub async fn remove_from_wishlist(
+    db_pool: &State<PgPool>,
+    id: i32,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    sqlx::query("DELETE FROM wishlist_items WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&**db_pool)
+        .await?;
+
+    Ok("Item successfully removed from wishlist".to_string())
+}
+
+/// Adds a guest's client-side wishlist to the account; items already saved, with an
+/// unknown size or pointing at drafts or products that no longer exist are skipped.
+#[post("/wishlist/merge", data = "<items>")]
+pub async fn merge_wishlist_items(
+    db_pool: &State<PgPool>,
+    items: Json<Vec<NewWishlistItem>>,
+    claims: Claims,
+) -> Result<String, ApiError> {
+    merge_wishlist(db_pool, claims.sub, items.into_inner()).await?;
+
+    Ok("Wishlist successfully merged".to_string())
+}
+
+#[get("/wishlists/stats?<limit>")]
+pub async fn get_wishlist_stats(
+    db_pool: &State<PgPool>,
+    limit: Option<i64>,
+    claims: Claims,
+) -> Result<Json<Vec<WishlistStat>>, ApiError> {
+    Claims::check_admin(db_pool, claims).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            p.id AS product_id,
+            p.name AS product_name,
+            COUNT(DISTINCT w.user_id) AS wishlist_count,
+            MAX(w.created_at) AS last_added_at
+        FROM wishlist_items w
+        JOIN products p ON p.id = w.product_id
+        GROUP BY p.id, p.name
+        ORDER BY wishlist_count DESC, last_added_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit.unwrap_or(20).clamp(1, 100))
+    .fetch_all(&**db_pool)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|row| WishlistStat {
+                product_id: row.get("product_id"),
+                product_name: row.get("product_name"),
+                wishlist_count: row.get("wishlist_count"),
+                last_added_at: row.get("last_added_at"),
+            })
+            .collect(),
+    ))
+}
+
+pub async fn merge_wishlist(
+    db_pool: &PgPool,
+    user_id: i32,
+    items: Vec<NewWishlistItem>,
+) -> Result<(), ApiError> {
+    // A guest wishlist is kept client-side, so it may hold sizes that were never valid.
+    let (product_ids, sizes): (Vec<i32>, Vec<Option<&str>>) = items
+        .iter()
+        .filter_map(|item| {
+            let size = wishlist_size(item.size.as_deref()).ok()?;
+            Some((item.product_id, size))
+        })
+        .unzip();
+
+    sqlx::query(
+        r#"
+        INSERT INTO wishlist_items (user_id, product_id, size, created_at)
+        SELECT $1, items.product_id, items.size, NOW()
+        FROM UNNEST($2::INT[], $3::TEXT[]) AS items(product_id, size)
+        WHERE EXISTS (SELECT 1 FROM products WHERE id = items.product_id AND NOT draft)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(&product_ids)
+    .bind(&sizes)
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_wishlist_item(
+    db_pool: &PgPool,
+    user_id: i32,
+    product_id: i32,
+    size: Option<&str>,
+) -> Result<i32, ApiError> {
+    let id = sqlx::query(
+        r#"
+        WITH inserted AS (
+            INSERT INTO wishlist_items (user_id, product_id, size, created_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT DO NOTHING
+            RETURNING id
+        )
+        SELECT id FROM inserted
+        UNION ALL
+        SELECT id FROM wishlist_items
+        WHERE user_id = $1 AND product_id = $2 AND size IS NOT DISTINCT FROM $3
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .bind(size)
+    .fetch_one(db_pool)
+    .await?
+    .get("id");
+
+    Ok(id)
+}
+
+/// Normalizes an optional wishlist size to its stock column name.
+fn wishlist_size(size: Option<&str>) -> Result<Option<&'static str>, ApiError> {
+    match size.map(str::trim).filter(|size| !size.is_empty()) {
+        Some(size) => size_column(Some(size)).map(Some),
+        None => Ok(None),
+    }
+}
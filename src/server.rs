@@ -1,28 +1,79 @@
 extern crate rocket;
 
+use crate::data::caching::immutable_images::ImmutableImages;
+use crate::data::feeds::feed::FeedCache;
+use crate::jobs::spawn_jobs;
+use crate::query::collections::collection_query::{
+    create_collection, delete_collection, get_active_collections, get_collection, get_collections,
+    update_collection,
+};
+use crate::query::feeds::feed_query::{get_google_feed, get_sitemap, get_yml_feed};
+use crate::query::localization::translation_query::{
+    delete_category_translation, delete_product_translation, get_category_translations,
+    get_missing_translations, get_product_translations, set_category_translation,
+    set_product_translation,
+};
 use crate::query::orders::orders_query::{
     delete_order, get_order_details, get_orders, place_new_order, update_order_status,
 };
 use crate::query::orders::shipping_query::get_shipping_by_id;
+use crate::query::pricing::currency_query::{
+    create_currency, delete_currency, delete_product_price, get_currencies, get_product_prices,
+    set_product_price, update_currency,
+};
+use crate::query::pricing::price_history_query::get_price_history;
+use crate::query::pricing::sale_query::{create_sale, delete_sale, get_sales};
+use crate::query::products_components::attribute_query::{
+    create_attribute, delete_attribute, get_attributes, get_facets, get_product_attributes,
+    set_category_attributes, set_product_attributes,
+};
+use crate::query::products_components::bundle_query::{get_bundle, set_bundle};
+use crate::query::products_components::catalog_query::{export_catalog, import_catalog};
 use crate::query::products_components::category_query::{
-    create_category, delete_category_by_id, get_categories, get_category, update_category_name,
+    create_category, delete_category_by_id, get_categories, get_category, get_category_tree,
+    update_category, update_category_name,
 };
+use crate::query::products_components::image_check_query::{get_image_check, run_image_check};
 use crate::query::products_components::product_image_query::{
-    create_product_image, delete_product_image_by_id, get_all_product_images,
-    get_one_product_image, update_product_image,
+    create_product_image, create_product_images, delete_product_image_by_id,
+    get_all_product_images, get_one_product_image, reorder_product_images, update_product_image,
 };
 use crate::query::products_components::product_query::{
-    create_product, delete_product, get_products, product_update,
+    create_product, delete_product, duplicate_product, get_product_tags, get_products,
+    product_update, set_product_tags,
+};
+use crate::query::products_components::recommendation_query::{
+    get_recommendations, refresh_recommendations_now,
 };
 use crate::query::products_components::size_query::{create_size, get_size, update_size};
+use crate::query::products_components::stock_alert_query::{
+    get_stock_alert_settings, get_stock_alerts, set_product_stock_threshold,
+    update_stock_alert_settings,
+};
+use crate::query::products_components::stock_query::{
+    create_stock_movement, get_stock_discrepancies, get_stock_movements, reconcile_stock,
+};
+use crate::query::products_components::stock_subscription_query::{
+    confirm_stock_subscription, subscribe_to_stock, unsubscribe_from_stock,
+};
+use crate::query::reviews::review_query::{
+    add_review_photo, create_review, delete_review, get_product_reviews, get_reviews,
+    moderate_review, reply_to_review,
+};
 use crate::query::user::user_query::{
     get_profile, get_user_role, login, registration_by_token, try_registration, update_password,
     update_profile,
 };
+use crate::query::wishlists::wishlist_query::{
+    add_to_wishlist, get_wishlist, get_wishlist_stats, merge_wishlist_items, remove_from_wishlist,
+};
+use crate::storage::{init_storage, storage};
+use crate::utils::constants::images::IMAGE_FILE_LIMIT_MIB;
 use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
 use crate::utils::env_configuration::CONFIG;
 use log::LevelFilter;
 use reqwest::Client;
+use rocket::data::{Limits, ToByteUnit};
 use rocket::figment::Figment;
 use rocket::Config;
 use rocket_cors::{AllowedHeaders, AllowedOrigins, Cors, CorsOptions};
@@ -31,6 +82,8 @@ use std::net::IpAddr;
 
 pub async fn set_up_rocket(db_pool: PgPool) {
     configure_logging();
+    init_storage();
+    spawn_jobs(&db_pool);
 
     let config = get_server_config().expect("Failed to configure Rocket server");
     let cors = configure_cors();
@@ -40,18 +93,24 @@ pub async fn set_up_rocket(db_pool: PgPool) {
 }
 
 fn configure_logging() {
-    env_logger::Builder::new()
+    let _ = env_logger::Builder::new()
         .filter_level(LevelFilter::Info)
-        .init();
+        .try_init();
 }
 
-fn get_server_config() -> Result<Config, rocket::figment::Error> {
+fn get_server_config() -> Result<Config, Box<rocket::figment::Error>> {
     let (address, port) = parse_address_port();
+    // Room for one image and its other fields; batch uploads read their body themselves.
+    let limits = Limits::default()
+        .limit("file", IMAGE_FILE_LIMIT_MIB.mebibytes())
+        .limit("data-form", (IMAGE_FILE_LIMIT_MIB + 1).mebibytes());
 
     Figment::from(Config::default())
         .merge(("address", address.to_string()))
         .merge(("port", port))
+        .merge(("limits", limits))
         .extract()
+        .map_err(Box::new)
 }
 
 fn parse_address_port() -> (IpAddr, u16) {
@@ -92,15 +151,21 @@ fn configure_cors() -> Cors {
 }
 
 async fn build_rocket(db_pool: PgPool, config: Config, cors: Cors, client: Client) {
-    rocket::custom(config)
+    let mut rocket = rocket::custom(config)
         .attach(cors)
         .attach(rocket::shield::Shield::default())
         .manage(db_pool)
         .manage(client)
-        .mount(
+        .manage(FeedCache::default());
+
+    if storage().is_local() {
+        rocket = rocket.attach(ImmutableImages).mount(
             format!("/{}", PATH_PRODUCT_IMAGES),
-            rocket::fs::FileServer::from(PATH_PRODUCT_IMAGES),
-        )
+            rocket::fs::FileServer::from(&CONFIG.get().unwrap().images_dir),
+        );
+    }
+
+    rocket
         .mount(
             "/api",
             routes![
@@ -134,6 +199,78 @@ async fn build_rocket(db_pool: PgPool, config: Config, cors: Cors, client: Clien
                 update_size,
                 update_product_image,
                 delete_product,
+                create_currency,
+                get_currencies,
+                update_currency,
+                delete_currency,
+                get_product_prices,
+                set_product_price,
+                delete_product_price,
+                create_sale,
+                get_sales,
+                delete_sale,
+                get_category_tree,
+                update_category,
+                create_attribute,
+                get_attributes,
+                delete_attribute,
+                set_category_attributes,
+                get_product_attributes,
+                set_product_attributes,
+                get_facets,
+                import_catalog,
+                export_catalog,
+                create_review,
+                add_review_photo,
+                get_product_reviews,
+                get_reviews,
+                moderate_review,
+                reply_to_review,
+                delete_review,
+                get_recommendations,
+                refresh_recommendations_now,
+                get_stock_movements,
+                create_stock_movement,
+                get_stock_discrepancies,
+                reconcile_stock,
+                get_stock_alerts,
+                get_stock_alert_settings,
+                update_stock_alert_settings,
+                set_product_stock_threshold,
+                get_product_translations,
+                set_product_translation,
+                delete_product_translation,
+                get_category_translations,
+                set_category_translation,
+                delete_category_translation,
+                get_missing_translations,
+                get_product_tags,
+                set_product_tags,
+                create_collection,
+                update_collection,
+                delete_collection,
+                get_collections,
+                get_active_collections,
+                get_collection,
+                get_wishlist,
+                add_to_wishlist,
+                remove_from_wishlist,
+                merge_wishlist_items,
+                get_wishlist_stats,
+                subscribe_to_stock,
+                confirm_stock_subscription,
+                unsubscribe_from_stock,
+                create_product_images,
+                reorder_product_images,
+                get_image_check,
+                run_image_check,
+                duplicate_product,
+                get_sitemap,
+                get_google_feed,
+                get_yml_feed,
+                get_price_history,
+                get_bundle,
+                set_bundle,
             ],
         )
         .launch()
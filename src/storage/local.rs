@@ -0,0 +1,124 @@
+use crate::error::api_error::ApiError;
+use crate::storage::{BlobStorage, StoredBlob};
+use crate::utils::constants::images::QUARANTINE_PREFIX;
+use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
+use crate::utils::env_configuration::CONFIG;
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+
+/// Files under `root` are served publicly; keys under [`QUARANTINE_PREFIX`] are kept
+/// in `quarantine_root` instead, so quarantined files are never served.
+pub struct LocalStorage {
+    root: String,
+    quarantine_root: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: &str, quarantine_root: &str) -> Self {
+        LocalStorage {
+            root: root.to_string(),
+            quarantine_root: quarantine_root.to_string(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        match key.strip_prefix(QUARANTINE_PREFIX) {
+            Some(key) => PathBuf::from(&self.quarantine_root).join(key),
+            None => PathBuf::from(&self.root).join(key),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl BlobStorage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ApiError> {
+        tokio::fs::create_dir_all(&self.root)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?;
+        tokio::fs::write(self.path(key), bytes).await.map_err(|e| {
+            log::error!("Failed to write {}: {}", key, e);
+            ApiError::InternalServerError
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ApiError> {
+        tokio::fs::remove_file(self.path(key)).await.map_err(|e| {
+            log::error!("Failed to delete {}: {}", key, e);
+            ApiError::InternalServerError
+        })
+    }
+
+    async fn list(&self) -> Result<Vec<StoredBlob>, ApiError> {
+        let mut entries = match tokio::fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                log::error!("Failed to list {}: {}", self.root, e);
+                return Err(ApiError::InternalServerError);
+            }
+        };
+
+        let mut blobs = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|_| ApiError::InternalServerError)?
+        {
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|_| ApiError::InternalServerError)?;
+            if !metadata.is_file() {
+                continue;
+            }
+            blobs.push(StoredBlob {
+                key: entry.file_name().to_string_lossy().to_string(),
+                modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            });
+        }
+        Ok(blobs)
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> Result<(), ApiError> {
+        tokio::fs::copy(self.path(from), self.path(to))
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                log::error!("Failed to copy {} to {}: {}", from, to, e);
+                ApiError::InternalServerError
+            })
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), ApiError> {
+        let target = self.path(to);
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|_| ApiError::InternalServerError)?;
+        }
+        tokio::fs::rename(self.path(from), target)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to move {} to {}: {}", from, to, e);
+                ApiError::InternalServerError
+            })
+    }
+
+    fn url(&self, key: &str) -> String {
+        if CONFIG.get().unwrap().local {
+            format!(
+                "http://{}:{}/{}/{}",
+                CONFIG.get().unwrap().server_address,
+                CONFIG.get().unwrap().server_port,
+                PATH_PRODUCT_IMAGES,
+                key
+            )
+        } else {
+            format!("/{}/{}", PATH_PRODUCT_IMAGES, key)
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
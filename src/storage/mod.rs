@@ -0,0 +1,64 @@
+pub mod local;
+pub mod s3;
+
+use crate::error::api_error::ApiError;
+use crate::storage::local::LocalStorage;
+use crate::storage::s3::S3Storage;
+use crate::utils::env_configuration::CONFIG;
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+
+pub static STORAGE: OnceCell<Box<dyn BlobStorage>> = OnceCell::new();
+
+/// An object found by [`BlobStorage::list`].
+#[derive(Debug, Clone)]
+pub struct StoredBlob {
+    pub key: String,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+#[rocket::async_trait]
+pub trait BlobStorage: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ApiError>;
+    async fn delete(&self, key: &str) -> Result<(), ApiError>;
+    /// Every stored object except those moved under
+    /// [`QUARANTINE_PREFIX`](crate::utils::constants::images::QUARANTINE_PREFIX).
+    async fn list(&self) -> Result<Vec<StoredBlob>, ApiError>;
+    async fn copy(&self, from: &str, to: &str) -> Result<(), ApiError>;
+    async fn rename(&self, from: &str, to: &str) -> Result<(), ApiError>;
+    /// URL handed to clients; backends configured for presigning return a signed, expiring URL.
+    fn url(&self, key: &str) -> String;
+    fn is_local(&self) -> bool {
+        false
+    }
+    /// Whether [`url`](BlobStorage::url) returns signed URLs that stop working after a while.
+    fn urls_expire(&self) -> bool {
+        false
+    }
+}
+
+pub fn init_storage() {
+    STORAGE.get_or_init(|| {
+        let config = CONFIG.get().unwrap();
+        match config.storage_backend.as_str() {
+            "s3" => Box::new(S3Storage::from_config(config)),
+            _ => Box::new(LocalStorage::new(
+                &config.images_dir,
+                &config.quarantine_dir,
+            )),
+        }
+    });
+}
+
+pub fn storage() -> &'static dyn BlobStorage {
+    STORAGE.get().expect("Storage is not initialized").as_ref()
+}
+
+pub fn content_type(key: &str) -> &'static str {
+    match key.rsplit('.').next() {
+        Some("webp") => "image/webp",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,342 @@
+use crate::error::api_error::ApiError;
+use crate::storage::{content_type, BlobStorage, StoredBlob};
+use crate::utils::constants::images::QUARANTINE_PREFIX;
+use crate::utils::env_configuration::EnvConfiguration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method};
+use sha2::{Digest, Sha256};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Minimal S3 client signing requests with AWS Signature V4 and path-style addressing, which
+/// MinIO and most S3-compatible services accept.
+pub struct S3Storage {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    public_url: Option<String>,
+    presigned_ttl: u64,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Self {
+        S3Storage {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            public_url: None,
+            presigned_ttl: 0,
+        }
+    }
+
+    pub fn from_config(config: &EnvConfiguration) -> Self {
+        S3Storage {
+            public_url: config.s3_public_url.clone(),
+            presigned_ttl: config.s3_presigned_ttl,
+            ..S3Storage::new(
+                &config.s3_endpoint,
+                &config.s3_bucket,
+                &config.s3_region,
+                &config.s3_access_key,
+                &config.s3_secret_key,
+            )
+        }
+    }
+
+    pub fn presigned_url(&self, key: &str, expires_in: u64) -> String {
+        let now = Utc::now();
+        let (amz_date, scope) = self.scope(&now);
+        let path = self.object_path(key);
+        let mut query = [
+            ("X-Amz-Algorithm", ALGORITHM.to_string()),
+            ("X-Amz-Credential", format!("{}/{}", self.access_key, scope)),
+            ("X-Amz-Date", amz_date.clone()),
+            ("X-Amz-Expires", expires_in.to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+        .collect::<Vec<String>>();
+        query.sort();
+        let query = query.join("&");
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\n{}",
+            path,
+            query,
+            self.host(),
+            UNSIGNED_PAYLOAD
+        );
+        let signature = self.signature(&now, &scope, &amz_date, &canonical_request);
+
+        format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.endpoint, path, query, signature
+        )
+    }
+
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>) -> Result<(), ApiError> {
+        self.request(method, &self.object_path(key), &[], &[], body, Some(key))
+            .await
+            .map(|_| ())
+    }
+
+    /// Signs and sends one request. `query` and `amz_headers` are signed along with
+    /// the request; `key` sets the Content-Type of uploaded objects.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        amz_headers: &[(&str, String)],
+        body: Vec<u8>,
+        key: Option<&str>,
+    ) -> Result<String, ApiError> {
+        let now = Utc::now();
+        let (amz_date, scope) = self.scope(&now);
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let mut query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+            .collect::<Vec<String>>();
+        query.sort();
+        let query = query.join("&");
+
+        let mut headers = vec![
+            ("host", self.host().to_string()),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        headers.extend(amz_headers.iter().cloned());
+        headers.sort();
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect::<String>();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<&str>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, payload_hash
+        );
+        let signature = self.signature(&now, &scope, &amz_date, &canonical_request);
+        let authorization = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, self.access_key, scope, signed_headers, signature
+        );
+
+        let url = match query.is_empty() {
+            true => format!("{}{}", self.endpoint, path),
+            false => format!("{}{}?{}", self.endpoint, path, query),
+        };
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("Authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, value);
+        }
+        if let Some(key) = key {
+            request = request.header("Content-Type", content_type(key));
+        }
+
+        let response = request.body(body).send().await.map_err(|e| {
+            log::error!("S3 request for {} failed: {}", path, e);
+            ApiError::HttpError
+        })?;
+
+        if !response.status().is_success() {
+            log::error!("S3 returned {} for {}", response.status(), path);
+            return Err(ApiError::HttpError);
+        }
+        response.text().await.map_err(|_| ApiError::HttpError)
+    }
+
+    fn host(&self) -> &str {
+        self.endpoint
+            .split_once("://")
+            .map(|(_, host)| host)
+            .unwrap_or(&self.endpoint)
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("/{}/{}", self.bucket, uri_encode(key, false))
+    }
+
+    fn scope(&self, now: &DateTime<Utc>) -> (String, String) {
+        (
+            now.format("%Y%m%dT%H%M%SZ").to_string(),
+            format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region),
+        )
+    }
+
+    fn signature(
+        &self,
+        now: &DateTime<Utc>,
+        scope: &str,
+        amz_date: &str,
+        canonical_request: &str,
+    ) -> String {
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let date_key = hmac(
+            format!("AWS4{}", self.secret_key).as_bytes(),
+            now.format("%Y%m%d").to_string().as_bytes(),
+        );
+        let region_key = hmac(&date_key, self.region.as_bytes());
+        let service_key = hmac(&region_key, b"s3");
+        let signing_key = hmac(&service_key, b"aws4_request");
+        hex::encode(hmac(&signing_key, string_to_sign.as_bytes()))
+    }
+}
+
+#[rocket::async_trait]
+impl BlobStorage for S3Storage {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ApiError> {
+        self.send(Method::PUT, key, bytes).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ApiError> {
+        self.send(Method::DELETE, key, Vec::new()).await
+    }
+
+    async fn list(&self) -> Result<Vec<StoredBlob>, ApiError> {
+        let path = format!("/{}", self.bucket);
+        let mut blobs = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2".to_string())];
+            if let Some(token) = continuation_token.take() {
+                query.push(("continuation-token", token));
+            }
+            let xml = self
+                .request(Method::GET, &path, &query, &[], Vec::new(), None)
+                .await?;
+
+            for contents in xml_elements(&xml, "Contents") {
+                let Some(key) = xml_elements(contents, "Key")
+                    .first()
+                    .map(|key| xml_unescape(key))
+                else {
+                    continue;
+                };
+                if key.starts_with(QUARANTINE_PREFIX) {
+                    continue;
+                }
+                let modified = xml_elements(contents, "LastModified")
+                    .first()
+                    .and_then(|modified| DateTime::parse_from_rfc3339(modified).ok())
+                    .map(|modified| modified.with_timezone(&Utc));
+                blobs.push(StoredBlob { key, modified });
+            }
+
+            match xml_elements(&xml, "NextContinuationToken").first() {
+                Some(token) => continuation_token = Some(xml_unescape(token)),
+                None => return Ok(blobs),
+            }
+        }
+    }
+
+    /// Server-side copy; the object is never downloaded.
+    async fn copy(&self, from: &str, to: &str) -> Result<(), ApiError> {
+        let copy_source = ("x-amz-copy-source", self.object_path(from));
+        self.request(
+            Method::PUT,
+            &self.object_path(to),
+            &[],
+            &[copy_source],
+            Vec::new(),
+            None,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// S3 has no rename, so the object is copied and the original deleted.
+    async fn rename(&self, from: &str, to: &str) -> Result<(), ApiError> {
+        self.copy(from, to).await?;
+        self.delete(from).await
+    }
+
+    fn url(&self, key: &str) -> String {
+        match (&self.public_url, self.presigned_ttl) {
+            (_, ttl) if ttl > 0 => self.presigned_url(key, ttl),
+            (Some(public_url), _) => format!("{}/{}", public_url.trim_end_matches('/'), key),
+            (None, _) => format!("{}{}", self.endpoint, self.object_path(key)),
+        }
+    }
+
+    fn urls_expire(&self) -> bool {
+        self.presigned_ttl > 0
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Inner text of every `<tag>` element, enough for the flat XML of S3 listings.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        elements.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    elements
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
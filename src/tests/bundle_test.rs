@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod bundles {
+    use crate::data::localization::locale::Locale;
+    use crate::data::products_components::product::ProductDuplicate;
+    use crate::data::products_components::stock_subscription::NewStockSubscription;
+    use crate::data::user_components::claims::Claims;
+    use crate::error::api_error::ApiError;
+    use crate::query::products_components::product_query::duplicate_product;
+    use crate::query::products_components::recommendation_query::get_recommendations;
+    use crate::query::products_components::stock_subscription_query::subscribe_to_stock;
+    use crate::query::wishlists::wishlist_query::get_wishlist;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use crate::utils::constants::locales::DEFAULT_LOCALE;
+    use rocket::serde::json::Json;
+    use rocket::State;
+    use sqlx::{PgPool, Row};
+
+    /// A bundle of a T-shirt (stocked in M) and a cap (out of stock), with no stock rows
+    /// of its own. Returns `(bundle_id, cap_id)`.
+    async fn create_bundle(db_pool: &PgPool) -> (i32, i32) {
+        let shirt_id = create_test_product(db_pool, "Футболка", 500.0).await;
+        let cap_id = create_test_product(db_pool, "Кепка", 300.0).await;
+        let bundle_id = create_test_product(db_pool, "Комплект", 700.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, m) VALUES ($1, 2)")
+            .bind(shirt_id)
+            .execute(db_pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 0)")
+            .bind(cap_id)
+            .execute(db_pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO bundle_components (bundle_id, component_id, quantity)
+            VALUES ($1, $2, 1), ($1, $3, 1)
+            "#,
+        )
+        .bind(bundle_id)
+        .bind(shirt_id)
+        .bind(cap_id)
+        .execute(db_pool)
+        .await
+        .unwrap();
+        (bundle_id, cap_id)
+    }
+
+    async fn restock(db_pool: &PgPool, product_id: i32) {
+        sqlx::query("UPDATE product_sizes SET single_size = 5 WHERE product_id = $1")
+            .bind(product_id)
+            .execute(db_pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wishlist_takes_bundle_stock_from_components() {
+        let db_pool = fresh_db_pool().await;
+        let user_id = create_test_admin(&db_pool).await;
+        let (bundle_id, cap_id) = create_bundle(&db_pool).await;
+        sqlx::query("INSERT INTO wishlist_items (user_id, product_id) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(bundle_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let wishlist = || {
+            get_wishlist(
+                State::from(&db_pool),
+                None,
+                Locale(DEFAULT_LOCALE),
+                Claims::new(user_id, None),
+            )
+        };
+        let before = wishlist().await.unwrap().into_inner();
+        assert_eq!((before[0].stock, before[0].in_stock), (0, false));
+
+        restock(&db_pool, cap_id).await;
+        let after = wishlist().await.unwrap().into_inner();
+        assert_eq!((after[0].stock, after[0].in_stock), (2, true));
+    }
+
+    #[tokio::test]
+    async fn subscribing_to_bundle_follows_component_stock() {
+        let db_pool = fresh_db_pool().await;
+        let user_id = create_test_admin(&db_pool).await;
+        let (bundle_id, cap_id) = create_bundle(&db_pool).await;
+
+        let subscribe = || {
+            subscribe_to_stock(
+                State::from(&db_pool),
+                bundle_id,
+                Json(NewStockSubscription {
+                    size: None,
+                    email: None,
+                }),
+                Some(Claims::new(user_id, None)),
+            )
+        };
+        assert_eq!(subscribe().await.unwrap(), "Subscribed successfully");
+
+        restock(&db_pool, cap_id).await;
+        assert!(matches!(subscribe().await, Err(ApiError::AlreadyInStock)));
+    }
+
+    #[tokio::test]
+    async fn recommendations_include_bundles_whose_components_are_in_stock() {
+        let db_pool = fresh_db_pool().await;
+        let (bundle_id, cap_id) = create_bundle(&db_pool).await;
+        let source_id = create_test_product(&db_pool, "Шарф", 200.0).await;
+        let category_id: i32 =
+            sqlx::query("INSERT INTO categories (name) VALUES ('Аксесуари') RETURNING id")
+                .fetch_one(&db_pool)
+                .await
+                .unwrap()
+                .get("id");
+        sqlx::query("UPDATE products SET category_id = $1 WHERE id = ANY($2)")
+            .bind(category_id)
+            .bind(vec![bundle_id, source_id])
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let recommended = || async {
+            get_recommendations(
+                State::from(&db_pool),
+                source_id,
+                None,
+                None,
+                Locale(DEFAULT_LOCALE),
+            )
+            .await
+            .unwrap()
+            .into_inner()
+            .into_iter()
+            .map(|product| product.id)
+            .collect::<Vec<_>>()
+        };
+        assert!(recommended().await.is_empty());
+
+        restock(&db_pool, cap_id).await;
+        assert_eq!(recommended().await, vec![Some(bundle_id)]);
+    }
+
+    #[tokio::test]
+    async fn duplicate_copies_bundle_components_only_on_request() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let (bundle_id, _) = create_bundle(&db_pool).await;
+
+        let duplicate = |name: &str, bundle: bool| {
+            duplicate_product(
+                State::from(&db_pool),
+                bundle_id,
+                Json(ProductDuplicate {
+                    name: name.to_string(),
+                    description: false,
+                    category: false,
+                    sizes: false,
+                    images: false,
+                    bundle,
+                }),
+                Claims::new(admin_id, None),
+            )
+        };
+        let components = |product_id: i32| {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM bundle_components WHERE bundle_id = $1",
+            )
+            .bind(product_id)
+            .fetch_one(&db_pool)
+        };
+
+        let plain = duplicate("Копія", false).await.unwrap().into_inner();
+        assert_eq!(components(plain).await.unwrap(), 0);
+
+        let copied = duplicate("Копія комплекту", true)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(components(copied).await.unwrap(), 2);
+    }
+}
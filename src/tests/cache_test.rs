@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod caching {
+    use crate::query::caching::cache_query::last_modified;
+    use crate::tests::database::test_db::{create_test_product, fresh_db_pool};
+    use crate::utils::constants::caching::{CATEGORY_SOURCES, PRODUCT_SOURCES};
+
+    #[tokio::test]
+    async fn deleting_a_row_moves_last_modified() {
+        let db_pool = fresh_db_pool().await;
+        let first_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        create_test_product(&db_pool, "Hoodie", 1500.0).await;
+        let before = last_modified(&db_pool, PRODUCT_SOURCES).await.unwrap();
+        let categories_before = last_modified(&db_pool, CATEGORY_SOURCES).await.unwrap();
+
+        sqlx::query("DELETE FROM products WHERE id = $1")
+            .bind(first_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM products WHERE id = -1")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let after = last_modified(&db_pool, PRODUCT_SOURCES).await.unwrap();
+        assert!(
+            after > before,
+            "{:?} should be later than {:?}",
+            after,
+            before
+        );
+        assert_eq!(
+            last_modified(&db_pool, CATEGORY_SOURCES).await.unwrap(),
+            categories_before
+        );
+    }
+}
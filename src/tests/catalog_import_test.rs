@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod catalog_import {
+    use crate::query::products_components::catalog_query::import_csv;
+    use crate::tests::database::test_db::{create_test_admin, fresh_db_pool};
+    use sqlx::{PgPool, Row};
+
+    async fn stock(db_pool: &PgPool, name: &str) -> Vec<Option<i32>> {
+        let row = sqlx::query(
+            r#"
+            SELECT ps.single_size, ps.s, ps.m, ps.l, ps.xl, ps.xxl
+            FROM products p
+            JOIN product_sizes ps ON ps.product_id = p.id
+            WHERE p.name = $1
+            "#,
+        )
+        .bind(name)
+        .fetch_one(db_pool)
+        .await
+        .unwrap();
+        ["single_size", "s", "m", "l", "xl", "xxl"]
+            .iter()
+            .map(|column| row.get(*column))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn blank_stock_cells_leave_stock_unchanged() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+
+        let created = import_csv(
+            &db_pool,
+            "name,price,s,m,l\nHoodie,1500,3,5,7\n",
+            false,
+            admin_id,
+        )
+        .await
+        .unwrap();
+        assert!(created.errors.is_empty(), "{:?}", created.errors);
+        assert_eq!(created.created, 1);
+        assert_eq!(
+            stock(&db_pool, "Hoodie").await,
+            vec![Some(0), Some(3), Some(5), Some(7), Some(0), Some(0)]
+        );
+
+        let id: i32 = sqlx::query("SELECT id FROM products WHERE name = 'Hoodie'")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap()
+            .get("id");
+        let updated = import_csv(
+            &db_pool,
+            &format!("id,name,price,s,m,l\n{},Hoodie,1500,,2,\n", id),
+            false,
+            admin_id,
+        )
+        .await
+        .unwrap();
+        assert!(updated.errors.is_empty(), "{:?}", updated.errors);
+        assert_eq!(updated.updated, 1);
+        assert_eq!(
+            stock(&db_pool, "Hoodie").await,
+            vec![Some(0), Some(3), Some(2), Some(7), Some(0), Some(0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn non_numeric_stock_is_a_row_error() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+
+        let report = import_csv(
+            &db_pool,
+            "name,price,s,m\nCap,700,4,5\nShirt,900,two,1\n",
+            false,
+            admin_id,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 3);
+        assert!(
+            report.errors[0].message.contains("\"two\" in column s"),
+            "{}",
+            report.errors[0].message
+        );
+        assert_eq!(report.created, 0);
+        let products: i64 = sqlx::query("SELECT COUNT(*) AS count FROM products")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap()
+            .get("count");
+        assert_eq!(products, 0);
+    }
+
+    #[tokio::test]
+    async fn unknown_size_column_is_rejected() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+
+        let report = import_csv(&db_pool, "name,price,xxxl\nCap,700,4\n", false, admin_id)
+            .await
+            .unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 1);
+        assert!(
+            report.errors[0].message.contains("unknown column xxxl"),
+            "{}",
+            report.errors[0].message
+        );
+        let products: i64 = sqlx::query("SELECT COUNT(*) AS count FROM products")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap()
+            .get("count");
+        assert_eq!(products, 0);
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod collections {
+    use crate::data::collections::collection::Collection;
+    use crate::data::user_components::claims::Claims;
+    use crate::error::api_error::ApiError;
+    use crate::query::collections::collection_query::{create_collection, update_collection};
+    use crate::query::products_components::product_query::set_product_tags;
+    use crate::tests::database::test_db::{create_test_admin, fresh_db_pool};
+    use crate::utils::constants::collections::COLLECTION_MANUAL;
+    use rocket::serde::json::Json;
+    use rocket::State;
+
+    fn collection(id: Option<i32>, slug: &str) -> Json<Collection> {
+        Json(Collection {
+            id,
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            kind: COLLECTION_MANUAL.to_string(),
+            product_ids: Some(Vec::new()),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn duplicate_slug_is_rejected() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let admin = || Claims::new(admin_id, None);
+        let state = State::from(&db_pool);
+
+        create_collection(state, collection(None, "summer"), admin())
+            .await
+            .unwrap();
+        let winter = create_collection(state, collection(None, "winter"), admin())
+            .await
+            .unwrap()
+            .0;
+
+        let created = create_collection(state, collection(None, "summer"), admin()).await;
+        assert!(matches!(created, Err(ApiError::SlugTaken)));
+        let renamed = update_collection(state, collection(Some(winter), "summer"), admin()).await;
+        assert!(matches!(renamed, Err(ApiError::SlugTaken)));
+        update_collection(state, collection(Some(winter), "winter"), admin())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn tagging_unknown_product_is_not_found() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+
+        let tagged = set_product_tags(
+            State::from(&db_pool),
+            404,
+            Json(vec!["new".to_string()]),
+            Claims::new(admin_id, None),
+        )
+        .await;
+        assert!(matches!(tagged, Err(ApiError::NotFound)));
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod currency_conversion {
+    use crate::data::pricing::currency::Currency;
+    use crate::utils::constants::pricing::{ROUNDING_DOWN, ROUNDING_NEAREST, ROUNDING_UP};
+
+    fn usd(rounding_step: f32, rounding_mode: &str) -> Currency {
+        Currency {
+            code: "USD".to_string(),
+            symbol: "$".to_string(),
+            rate: 41.5,
+            rounding_step,
+            rounding_mode: rounding_mode.to_string(),
+        }
+    }
+
+    #[test]
+    fn converts_from_base_currency_by_rate() {
+        assert!((usd(0.01, ROUNDING_NEAREST).convert(830.0) - 20.0).abs() < 1e-4);
+        assert!((usd(0.01, ROUNDING_NEAREST).convert(700.0) - 16.87).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rounds_to_step_in_configured_direction() {
+        // 100 UAH is 2.4096... USD.
+        assert_eq!(usd(0.5, ROUNDING_NEAREST).convert(100.0), 2.5);
+        assert_eq!(usd(0.5, ROUNDING_UP).convert(100.0), 2.5);
+        assert_eq!(usd(0.5, ROUNDING_DOWN).convert(100.0), 2.0);
+        assert_eq!(usd(1.0, ROUNDING_NEAREST).convert(100.0), 2.0);
+        assert_eq!(usd(1.0, ROUNDING_UP).convert(100.0), 3.0);
+    }
+
+    #[test]
+    fn zero_step_leaves_amount_unrounded() {
+        let converted = usd(0.0, ROUNDING_NEAREST).convert(100.0);
+        assert!((converted - 100.0 / 41.5).abs() < 1e-6);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod pricing;
 pub mod products;
 pub mod request_test_db;
+pub mod test_db;
 pub mod user_test_db;
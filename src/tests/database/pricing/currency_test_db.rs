@@ -0,0 +1,46 @@
+use crate::data::pricing::currency::Currency;
+use crate::data::products_components::product::Product;
+use crate::error::api_error::ApiError;
+use crate::tests::database::request_test_db::send_request;
+use crate::tests::database::user_test_db::UserTest;
+use crate::utils::constants::pricing::ROUNDING_NEAREST;
+use rocket::serde::json::json;
+
+#[allow(dead_code)]
+pub async fn create_currency(
+    user_test: &UserTest<'_>,
+    code: &str,
+    symbol: &str,
+    rate: f32,
+) -> Result<(), ApiError> {
+    let request = user_test
+        .client
+        .post(format!("{}/api/currency", user_test.base_url))
+        .header("Authorization", user_test.auth_header.as_str())
+        .json(&json!(Currency {
+            code: code.to_string(),
+            symbol: symbol.to_string(),
+            rate,
+            rounding_step: 0.01,
+            rounding_mode: ROUNDING_NEAREST.to_string(),
+        }));
+    send_request(request).await?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub async fn get_product_price(
+    user_test: &UserTest<'_>,
+    product_id: i32,
+    currency: &str,
+) -> Result<f32, ApiError> {
+    let request = user_test.client.get(format!(
+        "{}/api/product?product_id={}&currency={}",
+        user_test.base_url, product_id, currency
+    ));
+    let products: Vec<Product> =
+        serde_json::from_str(&send_request(request).await?).map_err(|_| ApiError::BadRequest)?;
+    let product = products.first().ok_or(ApiError::NotFound)?;
+    assert_eq!(product.currency.as_deref(), Some(currency));
+    Ok(product.price)
+}
@@ -22,6 +22,7 @@ pub async fn create_cap_red(user_test: &UserTest<'_>) -> Result<(), ApiError> {
             price: 700f32,
             size_id: None,
             category_id: Some(1),
+            ..Default::default()
         },
     )
     .await?;
@@ -59,6 +60,7 @@ pub async fn create_cap_beige(user_test: &UserTest<'_>) -> Result<(), ApiError>
             price: 700f32,
             size_id: None,
             category_id: Some(1),
+            ..Default::default()
         },
     )
     .await?;
@@ -96,6 +98,7 @@ pub async fn create_cap_black(user_test: &UserTest<'_>) -> Result<(), ApiError>
             price: 700_f32,
             size_id: None,
             category_id: Some(1),
+            ..Default::default()
         },
     )
     .await?;
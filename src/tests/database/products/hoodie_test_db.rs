@@ -20,6 +20,7 @@ pub async fn create_hoodie_black(user_test: &UserTest<'_>) -> Result<(), ApiErro
             price: 1700_f32,
             size_id: None,
             category_id: Some(3),
+            ..Default::default()
         },
     )
     .await?;
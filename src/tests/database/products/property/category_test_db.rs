@@ -13,6 +13,7 @@ pub async fn create_category(user_test: &UserTest<'_>, name: &str) -> Result<(),
         .json(&json!(Category {
             id: None,
             name: Some(name.to_string()),
+            ..Default::default()
         }));
     send_request(request).await?;
     Ok(())
@@ -20,6 +20,7 @@ pub async fn create_t_shirt_black(user_test: &UserTest<'_>) -> Result<(), ApiErr
             price: 900_f32,
             size_id: None,
             category_id: Some(2),
+            ..Default::default()
         },
     )
     .await?;
@@ -55,6 +56,7 @@ pub async fn create_t_shirt_white(user_test: &UserTest<'_>) -> Result<(), ApiErr
             price: 900_f32,
             size_id: None,
             category_id: Some(2),
+            ..Default::default()
         },
     )
     .await?;
@@ -0,0 +1,73 @@
+use crate::database::open_db_pool;
+use crate::utils::env_configuration::{EnvConfiguration, CONFIG};
+use sqlx::{PgPool, Row};
+use std::env;
+use std::sync::Once;
+
+/// Loads the configuration with uploads going to a temporary directory instead of
+/// the working tree, unless `IMAGES_DIR` is set explicitly, and a flat shipping price
+/// unless `SHIPPING_PRICE` is.
+#[allow(dead_code)]
+pub fn init_test_config() {
+    static IMAGES_DIR: Once = Once::new();
+    IMAGES_DIR.call_once(|| {
+        if env::var_os("IMAGES_DIR").is_none() {
+            let images_dir =
+                env::temp_dir().join(format!("product_images_{}", uuid::Uuid::new_v4().simple()));
+            env::set_var("IMAGES_DIR", images_dir);
+        }
+        // A non-zero flat rate, so order totals show whether shipping was added.
+        if env::var_os("SHIPPING_PRICE").is_none() {
+            env::set_var("SHIPPING_PRICE", "50");
+        }
+    });
+    EnvConfiguration::init_config();
+}
+
+/// A pool on a freshly created database of its own, so tests that call queries
+/// directly can run in parallel with each other and with the bootstrap test.
+#[allow(dead_code)]
+pub async fn fresh_db_pool() -> PgPool {
+    init_test_config();
+    let database_name = format!(
+        "{}_{}",
+        CONFIG.get().unwrap().database_name,
+        uuid::Uuid::new_v4().simple()
+    );
+    open_db_pool(&database_name)
+        .await
+        .expect("Failed to open test database")
+}
+
+#[allow(dead_code)]
+pub async fn create_test_admin(db_pool: &PgPool) -> i32 {
+    sqlx::query(
+        r#"
+        INSERT INTO users (username, email, password_hash, role)
+        VALUES ('admin', 'admin', '', $1)
+        RETURNING id
+        "#,
+    )
+    .bind(&CONFIG.get().unwrap().admin_role)
+    .fetch_one(db_pool)
+    .await
+    .expect("Failed to create test admin")
+    .get("id")
+}
+
+#[allow(dead_code)]
+pub async fn create_test_product(db_pool: &PgPool, name: &str, price: f32) -> i32 {
+    sqlx::query(
+        r#"
+        INSERT INTO products (name, price, created_at, updated_at)
+        VALUES ($1, $2, NOW(), NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(name)
+    .bind(price)
+    .fetch_one(db_pool)
+    .await
+    .expect("Failed to create test product")
+    .get("id")
+}
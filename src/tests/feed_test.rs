@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod feeds {
+    use crate::data::feeds::feed::FeedCache;
+    use crate::query::feeds::feed_query::get_sitemap;
+    use crate::storage::init_storage;
+    use crate::tests::database::test_db::{create_test_product, fresh_db_pool};
+    use crate::utils::constants::routes::MAIN_URL;
+    use rocket::State;
+
+    #[tokio::test]
+    async fn sitemap_lists_path_urls() {
+        let db_pool = fresh_db_pool().await;
+        init_storage();
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let cache = FeedCache::default();
+
+        let (_, sitemap) = get_sitemap(State::from(&db_pool), State::from(&cache))
+            .await
+            .unwrap();
+
+        assert!(sitemap.contains(&format!("<loc>{}/product/{}</loc>", MAIN_URL, product_id)));
+        assert!(!sitemap.contains("#/"));
+    }
+}
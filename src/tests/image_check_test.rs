@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod image_check {
+    use crate::query::products_components::image_check_query::check_images;
+    use crate::storage::init_storage;
+    use crate::storage::local::LocalStorage;
+    use crate::storage::BlobStorage;
+    use crate::tests::database::test_db::{create_test_product, fresh_db_pool};
+    use crate::utils::constants::images::{IMAGE_CHECK_QUARANTINE, QUARANTINE_PREFIX};
+    use sqlx::Row;
+    use std::env;
+
+    #[tokio::test]
+    async fn quarantined_files_leave_the_served_directory() {
+        let base = env::temp_dir().join(format!("image_check_{}", uuid::Uuid::new_v4().simple()));
+        let root = base.join("images");
+        let quarantine_root = base.join("quarantine");
+        let storage = LocalStorage::new(root.to_str().unwrap(), quarantine_root.to_str().unwrap());
+
+        storage.put("a_full.jpg", vec![1, 2, 3]).await.unwrap();
+        storage
+            .rename("a_full.jpg", &format!("{}a_full.jpg", QUARANTINE_PREFIX))
+            .await
+            .unwrap();
+
+        assert!(!root.join("a_full.jpg").exists());
+        assert!(!root.join(QUARANTINE_PREFIX).exists());
+        assert!(quarantine_root.join("a_full.jpg").exists());
+        assert!(storage.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn quarantine_keeps_broken_rows_out_of_the_gallery() {
+        let db_pool = fresh_db_pool().await;
+        init_storage();
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let image_id: i32 = sqlx::query(
+            r#"
+            INSERT INTO product_images (image_url, image_key, product_id, position)
+            VALUES ('', $1, $2, 1)
+            RETURNING id
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(product_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap()
+        .get("id");
+        sqlx::query("UPDATE products SET primary_image_id = $1 WHERE id = $2")
+            .bind(image_id)
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let report = check_images(&db_pool, IMAGE_CHECK_QUARANTINE)
+            .await
+            .unwrap();
+        assert_eq!(report.rows_quarantined, 1);
+        assert_eq!(report.rows_removed, 0);
+
+        let row = sqlx::query(
+            r#"
+            SELECT pi.quarantined_at IS NOT NULL AS quarantined, p.primary_image_id
+            FROM product_images pi
+            JOIN products p ON p.id = pi.product_id
+            WHERE pi.id = $1
+            "#,
+        )
+        .bind(image_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert!(row.get::<bool, &str>("quarantined"));
+        assert_eq!(row.get::<Option<i32>, &str>("primary_image_id"), None);
+
+        let again = check_images(&db_pool, IMAGE_CHECK_QUARANTINE)
+            .await
+            .unwrap();
+        assert!(again.missing_files.is_empty());
+    }
+}
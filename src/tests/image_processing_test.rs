@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod image_processing {
+    use crate::utils::image_processing::process_image;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn sample_jpeg() -> Vec<u8> {
+        let image = RgbImage::from_fn(1200, 900, |x, y| {
+            let shade = ((x * 7 + y * 13) ^ (x * y / 97)) as u8;
+            image::Rgb([shade, (x / 5) as u8, (y / 4) as u8])
+        });
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn webp_variants_are_smaller_than_jpeg() {
+        let processed = process_image("sample", &sample_jpeg()).expect("image should decode");
+        for variant in ["thumbnail", "card", "full"] {
+            let size = |format: &str| {
+                processed
+                    .iter()
+                    .find(|image| image.filename == format!("sample_{}.{}", variant, format))
+                    .map(|image| image.bytes.len())
+                    .unwrap()
+            };
+            assert!(
+                size("webp") < size("jpg"),
+                "{} webp is {} bytes, jpg {}",
+                variant,
+                size("webp"),
+                size("jpg")
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_data_that_is_not_an_image() {
+        assert!(process_image("broken", b"not an image").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod locale_negotiation {
+    use crate::data::localization::locale::Locale;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+
+    #[get("/locale")]
+    fn locale(locale: Locale) -> &'static str {
+        locale.0
+    }
+
+    fn negotiate(accept_language: Option<&str>, query: &str) -> String {
+        let client = Client::untracked(rocket::build().mount("/", routes![locale])).unwrap();
+        let mut request = client.get(format!("/locale{}", query));
+        if let Some(accept_language) = accept_language {
+            request = request.header(Header::new("Accept-Language", accept_language.to_string()));
+        }
+        request.dispatch().into_string().unwrap()
+    }
+
+    #[test]
+    fn highest_quality_supported_language_wins() {
+        assert_eq!(negotiate(Some("en"), ""), "en");
+        assert_eq!(negotiate(Some("uk;q=0.4, en;q=0.9"), ""), "en");
+        assert_eq!(negotiate(Some("fr, en;q=0.5, uk;q=0.3"), ""), "en");
+        assert_eq!(negotiate(Some("en;q=0.7, uk;q=0.7"), ""), "en");
+        assert_eq!(negotiate(Some("en;q=0"), ""), "uk");
+    }
+
+    #[test]
+    fn regional_tags_fall_back_to_their_language() {
+        assert_eq!(negotiate(Some("uk-UA"), ""), "uk");
+        assert_eq!(negotiate(Some("en-GB;q=0.9, de-DE"), ""), "en");
+        assert_eq!(negotiate(Some("EN_us"), ""), "en");
+    }
+
+    #[test]
+    fn wildcard_stands_for_unnamed_locales() {
+        assert_eq!(negotiate(Some("*"), ""), "uk");
+        assert_eq!(negotiate(Some("en;q=0.1, *;q=0.8"), ""), "uk");
+        assert_eq!(negotiate(Some("uk;q=0, *"), ""), "en");
+        assert_eq!(negotiate(Some("fr, *;q=0.5"), ""), "uk");
+    }
+
+    #[test]
+    fn malformed_headers_fall_back_to_default() {
+        assert_eq!(negotiate(None, ""), "uk");
+        assert_eq!(negotiate(Some(""), ""), "uk");
+        assert_eq!(negotiate(Some(";;,,"), ""), "uk");
+        assert_eq!(negotiate(Some("en;q=abc"), ""), "uk");
+        assert_eq!(negotiate(Some("en;q=abc, uk;q=0.2"), ""), "uk");
+        assert_eq!(negotiate(Some("fr-FR, de"), ""), "uk");
+    }
+
+    #[test]
+    fn lang_parameter_overrides_header() {
+        assert_eq!(negotiate(Some("uk"), "?lang=en"), "en");
+        assert_eq!(negotiate(Some("en"), "?lang=xx"), "en");
+        assert_eq!(negotiate(None, "?lang=en-US"), "en");
+    }
+}
@@ -1,2 +1,21 @@
+pub mod bundle_test;
+pub mod cache_test;
+pub mod catalog_import_test;
+pub mod collection_test;
+pub mod currency_test;
 pub mod database;
+pub mod feed_test;
+pub mod image_check_test;
+pub mod image_processing_test;
+pub mod locale_test;
+pub mod order_test;
+pub mod price_history_test;
+pub mod product_image_test;
+pub mod review_test;
+pub mod sale_test;
+pub mod stock_alert_test;
+pub mod stock_ledger_test;
+pub mod stock_subscription_test;
+pub mod storage_test;
 pub mod test;
+pub mod wishlist_test;
@@ -0,0 +1,312 @@
+#[cfg(test)]
+mod orders {
+    use crate::data::orders::order::DataOrder;
+    use crate::data::user_components::claims::Claims;
+    use crate::error::api_error::ApiError;
+    use crate::query::orders::orders_query::{
+        delete_order, get_order_details, place_new_order, update_order_status,
+    };
+    use crate::query::pricing::currency_query::find_currency;
+    use crate::query::pricing::sale_query::get_checkout_price;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use crate::utils::constants::orders::{ORDER_STATUS_CANCELLED, ORDER_STATUS_PENDING};
+    use crate::utils::env_configuration::CONFIG;
+    use rocket::serde::json::Json;
+    use rocket::State;
+    use serde_json::{json, Value};
+    use sqlx::{PgPool, Row};
+
+    fn order_json(product_id: i32) -> Value {
+        json!({
+            "order": { "id": null, "user_id": null, "online_payment": false, "date": null,
+                "currency": null, "exchange_rate": null, "shipping_price": null },
+            "order_items": [{ "order_id": null, "product_id": product_id, "quantity": 1,
+                "size": null }],
+            "shipping": { "order_id": 0, "city": "Київ", "branch": "1",
+                "first_name": "Тарас", "last_name": "Шевченко",
+                "phone_number": "+380000000000", "email": "buyer@example.com" },
+            "expected_total": null
+        })
+    }
+
+    fn data_order(order: Value) -> Json<DataOrder> {
+        Json(serde_json::from_value(order).unwrap())
+    }
+
+    fn order_for(product_id: i32) -> Json<DataOrder> {
+        data_order(order_json(product_id))
+    }
+
+    async fn order_count(db_pool: &PgPool) -> i64 {
+        sqlx::query("SELECT COUNT(*) AS orders FROM orders")
+            .fetch_one(db_pool)
+            .await
+            .unwrap()
+            .get("orders")
+    }
+
+    #[tokio::test]
+    async fn draft_products_cannot_be_ordered() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("UPDATE products SET draft = TRUE WHERE id = $1")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let placed = place_new_order(State::from(&db_pool), order_for(product_id)).await;
+
+        assert!(matches!(placed, Err(ApiError::ProductNotFound(id)) if id == product_id));
+        assert_eq!(order_count(&db_pool).await, 0);
+    }
+
+    /// A price read at checkout holds until the order transaction ends, so the total
+    /// cannot be computed from a price that is changed before commit.
+    #[tokio::test]
+    async fn checkout_price_is_locked_until_the_order_commits() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        let currency = find_currency(&db_pool, None).await.unwrap();
+
+        let mut tx = db_pool.begin().await.unwrap();
+        let price = get_checkout_price(&mut tx, product_id, None, &currency)
+            .await
+            .unwrap();
+        assert_eq!(price, 1500.0);
+
+        let mut other = db_pool.acquire().await.unwrap();
+        sqlx::query("SET lock_timeout = '100ms'")
+            .execute(&mut *other)
+            .await
+            .unwrap();
+        let repriced = sqlx::query("UPDATE products SET price = 1.0 WHERE id = $1")
+            .bind(product_id)
+            .execute(&mut *other)
+            .await;
+        assert!(repriced.is_err());
+
+        tx.commit().await.unwrap();
+        sqlx::query("UPDATE products SET price = 1.0 WHERE id = $1")
+            .bind(product_id)
+            .execute(&mut *other)
+            .await
+            .unwrap();
+    }
+
+    async fn stock(db_pool: &PgPool, product_id: i32) -> i32 {
+        sqlx::query("SELECT single_size FROM product_sizes WHERE product_id = $1")
+            .bind(product_id)
+            .fetch_one(db_pool)
+            .await
+            .unwrap()
+            .get("single_size")
+    }
+
+    /// Orders from before stock reservations never took stock, so cancelling,
+    /// reopening or deleting them must not move it.
+    #[tokio::test]
+    async fn legacy_orders_never_move_stock() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let order_id = place_new_order(State::from(&db_pool), order_for(product_id))
+            .await
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        sqlx::query("UPDATE orders SET stock_reserved = NULL WHERE id = $1")
+            .bind(order_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(stock(&db_pool, product_id).await, 4);
+
+        for status in [ORDER_STATUS_CANCELLED, ORDER_STATUS_PENDING] {
+            update_order_status(
+                State::from(&db_pool),
+                Json(json!({ "status": status })),
+                order_id,
+                Claims::new(admin_id, None),
+            )
+            .await
+            .unwrap();
+            assert_eq!(stock(&db_pool, product_id).await, 4);
+        }
+
+        delete_order(State::from(&db_pool), order_id, Claims::new(admin_id, None))
+            .await
+            .unwrap();
+        assert_eq!(stock(&db_pool, product_id).await, 4);
+    }
+
+    #[tokio::test]
+    async fn client_prices_and_status_are_ignored() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let mut order = order_json(product_id);
+        order["order"]["total_price"] = json!(1.0);
+        order["order"]["status"] = json!("delivered");
+        order["order_items"][0]["quantity"] = json!(2);
+        order["order_items"][0]["price"] = json!(1.0);
+        order["order_items"][0]["total_price"] = json!(2.0);
+
+        let order_id = place_new_order(State::from(&db_pool), data_order(order))
+            .await
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        let row = sqlx::query(
+            r#"
+            SELECT o.status, oi.price, oi.total_price
+            FROM orders o
+            JOIN order_items oi ON oi.order_id = o.id
+            WHERE o.id = $1
+            "#,
+        )
+        .bind(order_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert_eq!(row.get::<String, &str>("status"), ORDER_STATUS_PENDING);
+        assert_eq!(row.get::<f32, &str>("price"), 1500.0);
+        assert_eq!(row.get::<f32, &str>("total_price"), 3000.0);
+    }
+
+    /// The test configuration charges a flat shipping price and has no free-shipping
+    /// threshold.
+    #[tokio::test]
+    async fn shipping_is_added_to_the_stored_total() {
+        let db_pool = fresh_db_pool().await;
+        let shipping_price = CONFIG.get().unwrap().shipping_price;
+        assert!(shipping_price > 0.0);
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let order_id = place_new_order(State::from(&db_pool), order_for(product_id))
+            .await
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        let row = sqlx::query("SELECT total_price, shipping_price FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<f32, &str>("shipping_price"), shipping_price);
+        assert_eq!(row.get::<f32, &str>("total_price"), 1500.0 + shipping_price);
+
+        let details = get_order_details(State::from(&db_pool), order_id)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(details.shipping_price, shipping_price);
+        assert_eq!(details.total_price, 1500.0 + shipping_price);
+    }
+
+    #[tokio::test]
+    async fn expected_total_mismatch_is_rejected() {
+        let db_pool = fresh_db_pool().await;
+        let shipping_price = CONFIG.get().unwrap().shipping_price;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let mut order = order_json(product_id);
+        order["expected_total"] = json!(1500.0);
+
+        let placed = place_new_order(State::from(&db_pool), data_order(order)).await;
+
+        assert!(
+            matches!(placed, Err(ApiError::PriceMismatch(total)) if total == 1500.0 + shipping_price)
+        );
+        assert_eq!(order_count(&db_pool).await, 0);
+        assert_eq!(stock(&db_pool, product_id).await, 5);
+    }
+
+    #[tokio::test]
+    async fn out_of_stock_error_names_product_and_size() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, m, l) VALUES ($1, 1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let mut order = order_json(product_id);
+        order["order_items"][0]["size"] = json!("m");
+        order["order_items"][0]["quantity"] = json!(2);
+
+        let placed = place_new_order(State::from(&db_pool), data_order(order)).await;
+
+        assert!(matches!(placed, Err(ApiError::OutOfStock(item)) if item == "Худі (M)"));
+        assert_eq!(order_count(&db_pool).await, 0);
+    }
+
+    /// Shipping is written after the stock is taken; when it fails, the order, its
+    /// items, the stock and the ledger entries are all rolled back.
+    #[tokio::test]
+    async fn failure_after_reserving_stock_rolls_back_the_whole_order() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 5)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            r#"
+            CREATE FUNCTION reject_shipping() RETURNS trigger LANGUAGE plpgsql AS $$
+            BEGIN
+                RAISE EXCEPTION 'shipping unavailable';
+            END;
+            $$;
+            CREATE TRIGGER reject_shipping BEFORE INSERT ON shipping_addresses
+            FOR EACH ROW EXECUTE FUNCTION reject_shipping();
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let placed = place_new_order(State::from(&db_pool), order_for(product_id)).await;
+
+        assert!(matches!(placed, Err(ApiError::OrderFailed("shipping", _))));
+        assert_eq!(order_count(&db_pool).await, 0);
+        assert_eq!(stock(&db_pool, product_id).await, 5);
+        let leftovers = sqlx::query(
+            r#"
+            SELECT (SELECT COUNT(*) FROM order_items)
+                + (SELECT COUNT(*) FROM stock_movements WHERE order_id IS NOT NULL) AS rows
+            "#,
+        )
+        .fetch_one(&db_pool)
+        .await
+        .unwrap()
+        .get::<i64, &str>("rows");
+        assert_eq!(leftovers, 0);
+    }
+}
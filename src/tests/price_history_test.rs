@@ -0,0 +1,328 @@
+#[cfg(test)]
+mod price_history {
+    use crate::data::localization::locale::Locale;
+    use crate::query::caching::cache_query::last_modified;
+    use crate::query::pricing::price_history_query::{
+        get_lowest_prices, record_sale_price_changes,
+    };
+    use crate::query::products_components::product_query::products_from_rows;
+    use crate::tests::database::test_db::{create_test_product, fresh_db_pool};
+    use crate::utils::constants::locales::DEFAULT_LOCALE;
+    use crate::utils::constants::pricing::{
+        LOWEST_PRICE_WINDOW_DAYS, PRICE_SOURCE_SALE_ENDED, PRICE_SOURCE_SALE_STARTED,
+    };
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use sqlx::{PgPool, Row};
+
+    fn reference() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 31)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    fn window_start() -> NaiveDateTime {
+        reference() - Duration::days(LOWEST_PRICE_WINDOW_DAYS.into())
+    }
+
+    async fn record_history(
+        db_pool: &PgPool,
+        product_id: i32,
+        changed_at: NaiveDateTime,
+        old_price: f32,
+        new_price: f32,
+    ) {
+        sqlx::query(
+            r#"
+            INSERT INTO price_history (product_id, old_price, new_price, source, changed_at)
+            VALUES ($1, $2, $3, 'manual', $4)
+            "#,
+        )
+        .bind(product_id)
+        .bind(old_price)
+        .bind(new_price)
+        .bind(changed_at)
+        .execute(db_pool)
+        .await
+        .unwrap();
+    }
+
+    async fn lowest_price(db_pool: &PgPool, product_id: i32) -> f32 {
+        get_lowest_prices(db_pool, &[(product_id, Some(reference()))])
+            .await
+            .unwrap()[&product_id]
+    }
+
+    #[tokio::test]
+    async fn unchanged_price_is_its_own_lowest() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+
+        assert_eq!(lowest_price(&db_pool, product_id).await, 700.0);
+    }
+
+    #[tokio::test]
+    async fn change_at_window_start_opens_the_window() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        record_history(&db_pool, product_id, window_start(), 500.0, 700.0).await;
+
+        assert_eq!(lowest_price(&db_pool, product_id).await, 700.0);
+    }
+
+    #[tokio::test]
+    async fn change_just_inside_window_keeps_the_old_price() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let changed_at = window_start() + Duration::seconds(1);
+        record_history(&db_pool, product_id, changed_at, 500.0, 700.0).await;
+
+        assert_eq!(lowest_price(&db_pool, product_id).await, 500.0);
+    }
+
+    #[tokio::test]
+    async fn change_at_reference_time_is_outside_window() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 400.0).await;
+        let earlier = window_start() - Duration::days(5);
+        record_history(&db_pool, product_id, earlier, 900.0, 700.0).await;
+        record_history(&db_pool, product_id, reference(), 700.0, 400.0).await;
+
+        assert_eq!(lowest_price(&db_pool, product_id).await, 700.0);
+    }
+
+    #[tokio::test]
+    async fn lowest_change_inside_window_wins() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        record_history(
+            &db_pool,
+            product_id,
+            window_start() - Duration::days(1),
+            900.0,
+            800.0,
+        )
+        .await;
+        record_history(
+            &db_pool,
+            product_id,
+            reference() - Duration::days(20),
+            800.0,
+            600.0,
+        )
+        .await;
+        record_history(
+            &db_pool,
+            product_id,
+            reference() - Duration::days(10),
+            600.0,
+            700.0,
+        )
+        .await;
+
+        assert_eq!(lowest_price(&db_pool, product_id).await, 600.0);
+    }
+
+    async fn recorded_changes(db_pool: &PgPool, product_id: i32) -> Vec<(String, f32, f32)> {
+        sqlx::query(
+            r#"
+            SELECT source, old_price, new_price
+            FROM price_history
+            WHERE product_id = $1
+            ORDER BY changed_at, id
+            "#,
+        )
+        .bind(product_id)
+        .fetch_all(db_pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| {
+            (
+                row.get("source"),
+                row.get("old_price"),
+                row.get("new_price"),
+            )
+        })
+        .collect()
+    }
+
+    async fn record_sales(db_pool: &PgPool) {
+        let mut tx = db_pool.begin().await.unwrap();
+        record_sale_price_changes(&mut tx, None).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn scheduled_sale_is_recorded_when_it_starts_and_ends() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let sale_id: i32 = sqlx::query(
+            r#"
+            INSERT INTO product_sales (product_id, sale_price, starts_at, ends_at)
+            VALUES ($1, 500, NOW() + INTERVAL '1 hour', NOW() + INTERVAL '2 hours')
+            RETURNING id
+            "#,
+        )
+        .bind(product_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap()
+        .get("id");
+
+        record_sales(&db_pool).await;
+        assert!(recorded_changes(&db_pool, product_id).await.is_empty());
+
+        let shift_sale = |hours: i32| {
+            sqlx::query(
+                r#"
+                UPDATE product_sales
+                SET created_at = created_at - make_interval(hours => $2),
+                    starts_at = starts_at - make_interval(hours => $2),
+                    ends_at = ends_at - make_interval(hours => $2)
+                WHERE id = $1
+                "#,
+            )
+            .bind(sale_id)
+            .bind(hours)
+            .execute(&db_pool)
+        };
+
+        shift_sale(1).await.unwrap();
+        record_sales(&db_pool).await;
+        assert_eq!(
+            recorded_changes(&db_pool, product_id).await,
+            vec![(PRICE_SOURCE_SALE_STARTED.to_string(), 700.0, 500.0)]
+        );
+
+        shift_sale(1).await.unwrap();
+        record_sales(&db_pool).await;
+        record_sales(&db_pool).await;
+        assert_eq!(
+            recorded_changes(&db_pool, product_id).await,
+            vec![
+                (PRICE_SOURCE_SALE_STARTED.to_string(), 700.0, 500.0),
+                (PRICE_SOURCE_SALE_ENDED.to_string(), 500.0, 700.0),
+            ]
+        );
+
+        let dated = sqlx::query(
+            r#"
+            SELECT bool_and(h.changed_at IN (s.starts_at, s.ends_at)) AS at_schedule
+            FROM price_history h
+            JOIN product_sales s ON s.product_id = h.product_id
+            WHERE h.product_id = $1
+            "#,
+        )
+        .bind(product_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert!(dated.get::<bool, &str>("at_schedule"));
+    }
+
+    #[tokio::test]
+    async fn backdated_sale_is_recorded_from_its_creation() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        sqlx::query(
+            r#"
+            INSERT INTO product_sales (product_id, sale_price, starts_at)
+            VALUES ($1, 500, NOW() - INTERVAL '5 days')
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        record_sales(&db_pool).await;
+
+        let row = sqlx::query(
+            r#"
+            SELECT h.changed_at = s.created_at AS from_creation
+            FROM price_history h
+            JOIN product_sales s ON s.product_id = h.product_id
+            WHERE h.product_id = $1
+            "#,
+        )
+        .bind(product_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert!(row.get::<bool, &str>("from_creation"));
+    }
+
+    /// The lowest price moves when an old change leaves the window, so that moment
+    /// has to count as a modification.
+    #[tokio::test]
+    async fn change_leaving_the_window_moves_last_modified() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let left_window_at: NaiveDateTime = sqlx::query(
+            r#"
+            INSERT INTO price_history (product_id, old_price, new_price, source, changed_at)
+            VALUES ($1, 500, 700, 'manual', NOW() - make_interval(days => $2 + 1))
+            RETURNING changed_at + make_interval(days => $2) AS left_window_at
+            "#,
+        )
+        .bind(product_id)
+        .bind(LOWEST_PRICE_WINDOW_DAYS)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap()
+        .get("left_window_at");
+
+        assert_eq!(
+            last_modified(&db_pool, &["price_history"]).await.unwrap(),
+            Some(left_window_at)
+        );
+    }
+
+    /// The history is in the base currency, so it is not disclosed next to a manual
+    /// price in another currency.
+    #[tokio::test]
+    async fn lowest_price_is_not_given_next_to_a_manual_price() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        sqlx::query(
+            r#"
+            INSERT INTO currencies (code, symbol, rate, rounding_step)
+            VALUES ('USD', '$', 40, 0.01), ('EUR', '€', 50, 0.01)
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO product_prices (product_id, currency_code, price) VALUES ($1, 'USD', 19.0)",
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let lowest_in = |currency: &'static str| {
+            let db_pool = db_pool.clone();
+            async move {
+                let rows = sqlx::query("SELECT * FROM products WHERE id = $1")
+                    .bind(product_id)
+                    .fetch_all(&db_pool)
+                    .await
+                    .unwrap();
+                let products = products_from_rows(
+                    &db_pool,
+                    rows,
+                    Some(currency),
+                    None,
+                    Locale(DEFAULT_LOCALE),
+                )
+                .await
+                .unwrap();
+                (products[0].price, products[0].lowest_price_30d)
+            }
+        };
+        assert_eq!(lowest_in("USD").await, (19.0, None));
+        assert_eq!(lowest_in("EUR").await, (14.0, Some(14.0)));
+    }
+}
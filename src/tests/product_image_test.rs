@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod product_images {
+    use crate::data::products_components::product::Product;
+    use crate::data::user_components::claims::Claims;
+    use crate::query::products_components::product_image_query::{
+        create_product_images, delete_product_image_by_id,
+    };
+    use crate::query::products_components::product_query::product_update;
+    use crate::storage::init_storage;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use crate::utils::env_configuration::CONFIG;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use rocket::http::{ContentType, Header as HttpHeader, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Json;
+    use rocket::State;
+    use sqlx::{PgPool, Row};
+    use std::io::Cursor;
+
+    const BOUNDARY: &str = "image-batch-boundary";
+
+    /// A small JPEG followed by `padding` bytes, which decoders ignore after the end marker.
+    fn padded_jpeg(padding: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, image::Rgb([200, 120, 40])))
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+            .unwrap();
+        bytes.resize(bytes.len() + padding, 0);
+        bytes
+    }
+
+    fn multipart_body(files: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"images\"; filename=\"{}.jpg\"\r\nContent-Type: image/jpeg\r\n\r\n",
+                    BOUNDARY, index
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(file);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+        body
+    }
+
+    async fn upload(db_pool: &PgPool, admin_id: i32, product_id: i32, files: &[Vec<u8>]) -> Status {
+        init_storage();
+        let token = encode(
+            &Header::new(Algorithm::HS512),
+            &Claims::new(admin_id, None),
+            &EncodingKey::from_secret(CONFIG.get().unwrap().jwt_secret.as_ref()),
+        )
+        .unwrap();
+        let client = Client::untracked(
+            rocket::build()
+                .manage(db_pool.clone())
+                .mount("/", routes![create_product_images]),
+        )
+        .await
+        .unwrap();
+
+        let response = client
+            .post(format!("/product/{}/images", product_id))
+            .header(ContentType::new("multipart", "form-data").with_params(("boundary", BOUNDARY)))
+            .header(HttpHeader::new(
+                "Authorization",
+                format!("Bearer {}", token),
+            ))
+            .body(multipart_body(files))
+            .dispatch()
+            .await;
+        response.status()
+    }
+
+    async fn image_count(db_pool: &PgPool) -> i64 {
+        sqlx::query("SELECT COUNT(*) AS images FROM product_images")
+            .fetch_one(db_pool)
+            .await
+            .unwrap()
+            .get("images")
+    }
+
+    /// Two files of 1.5 MiB each are more than the default form limit of 2 MiB.
+    #[tokio::test]
+    async fn batch_upload_has_its_own_size_limit() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Hoodie", 1500.0).await;
+        let image = padded_jpeg(1536 * 1024);
+
+        let status = upload(&db_pool, admin_id, product_id, &[image.clone(), image]).await;
+
+        assert_eq!(status, Status::Ok);
+        assert_eq!(image_count(&db_pool).await, 2);
+    }
+
+    /// The product is checked before the body is read, so even an unreadable file gets a 404.
+    #[tokio::test]
+    async fn unknown_product_is_rejected_before_processing() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+
+        assert_eq!(
+            upload(&db_pool, admin_id, 4242, &[padded_jpeg(0)]).await,
+            Status::NotFound
+        );
+        assert_eq!(
+            upload(&db_pool, admin_id, 4242, &[b"not an image".to_vec()]).await,
+            Status::NotFound
+        );
+        assert_eq!(image_count(&db_pool).await, 0);
+    }
+
+    async fn add_image(db_pool: &PgPool, product_id: i32, position: i32) -> i32 {
+        sqlx::query(
+            r#"
+            INSERT INTO product_images (image_url, product_id, position)
+            VALUES ('image.jpg', $1, $2)
+            RETURNING id
+            "#,
+        )
+        .bind(product_id)
+        .bind(position)
+        .fetch_one(db_pool)
+        .await
+        .unwrap()
+        .get("id")
+    }
+
+    async fn gallery(db_pool: &PgPool, product_id: i32) -> (Option<i32>, Vec<i32>) {
+        let primary_image_id = sqlx::query("SELECT primary_image_id FROM products WHERE id = $1")
+            .bind(product_id)
+            .fetch_one(db_pool)
+            .await
+            .unwrap()
+            .get("primary_image_id");
+        let order =
+            sqlx::query("SELECT id FROM product_images WHERE product_id = $1 ORDER BY position")
+                .bind(product_id)
+                .fetch_all(db_pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get("id"))
+                .collect();
+        (primary_image_id, order)
+    }
+
+    #[tokio::test]
+    async fn product_update_moves_primary_image_to_front_of_gallery() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Hoodie", 1500.0).await;
+        let other_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        let first = add_image(&db_pool, product_id, 1).await;
+        let second = add_image(&db_pool, product_id, 2).await;
+        let foreign = add_image(&db_pool, other_id, 1).await;
+
+        let update = |primary_image_id: i32| {
+            product_update(
+                State::from(&db_pool),
+                Json(Product {
+                    id: Some(product_id),
+                    name: "Hoodie".to_string(),
+                    price: 1500.0,
+                    primary_image_id: Some(primary_image_id),
+                    ..Default::default()
+                }),
+                Claims::new(admin_id, None),
+            )
+        };
+
+        update(second).await.unwrap();
+        assert_eq!(
+            gallery(&db_pool, product_id).await,
+            (Some(second), vec![second, first])
+        );
+
+        update(foreign).await.unwrap();
+        assert_eq!(
+            gallery(&db_pool, product_id).await,
+            (Some(second), vec![second, first])
+        );
+    }
+
+    /// The image check reports images of deleted products; they must stay deletable.
+    #[tokio::test]
+    async fn image_of_a_deleted_product_can_be_deleted() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let image_id = add_image(&db_pool, 4242, 1).await;
+
+        delete_product_image_by_id(State::from(&db_pool), image_id, Claims::new(admin_id, None))
+            .await
+            .unwrap();
+
+        assert_eq!(image_count(&db_pool).await, 0);
+    }
+}
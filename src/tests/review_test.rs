@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod reviews {
+    use crate::data::user_components::claims::Claims;
+    use crate::error::api_error::ApiError;
+    use crate::query::reviews::review_query::{delete_review, moderate_review, reply_to_review};
+    use crate::storage::init_storage;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use crate::utils::constants::reviews::REVIEW_APPROVED;
+    use rocket::serde::json::Json;
+    use rocket::State;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn unknown_review_is_not_found() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let admin = || Claims::new(admin_id, None);
+
+        let moderated = moderate_review(
+            State::from(&db_pool),
+            4242,
+            Json(json!({ "status": REVIEW_APPROVED })),
+            admin(),
+        )
+        .await;
+        let replied = reply_to_review(
+            State::from(&db_pool),
+            4242,
+            Json(json!({ "reply": "Дякуємо!" })),
+            admin(),
+        )
+        .await;
+        let deleted = delete_review(State::from(&db_pool), 4242, admin()).await;
+
+        assert!(matches!(moderated, Err(ApiError::NotFound)));
+        assert!(matches!(replied, Err(ApiError::NotFound)));
+        assert!(matches!(deleted, Err(ApiError::NotFound)));
+    }
+
+    /// The photo files were never written, so removing them fails after the commit.
+    #[tokio::test]
+    async fn review_is_deleted_even_if_its_photos_cannot_be_removed() {
+        let db_pool = fresh_db_pool().await;
+        init_storage();
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        let review_id: i32 = sqlx::query_scalar(
+            "INSERT INTO reviews (product_id, user_id, rating) VALUES ($1, $2, 5) RETURNING id",
+        )
+        .bind(product_id)
+        .bind(admin_id)
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO review_photos (review_id, image_key) VALUES ($1, 'missing')")
+            .bind(review_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        delete_review(
+            State::from(&db_pool),
+            review_id,
+            Claims::new(admin_id, None),
+        )
+        .await
+        .unwrap();
+
+        let reviews: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM reviews")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(reviews, 0);
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod sales {
+    use crate::data::pricing::sale::Sale;
+    use crate::data::user_components::claims::Claims;
+    use crate::query::pricing::currency_query::find_currency;
+    use crate::query::pricing::sale_query::{create_sale, get_checkout_price, get_sales};
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use chrono::{Duration, Utc};
+    use rocket::serde::json::Json;
+    use rocket::State;
+
+    fn sale(product_id: i32, size: Option<&str>, starts_in: Duration) -> Json<Sale> {
+        Json(Sale {
+            id: None,
+            product_id,
+            size: size.map(str::to_string),
+            sale_price: 1000.0,
+            starts_at: Utc::now().naive_utc() + starts_in,
+            ends_at: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn scheduled_sales_are_listed_only_for_admins() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Худі", 1500.0).await;
+        let running = create_sale(
+            State::from(&db_pool),
+            sale(product_id, None, Duration::hours(-1)),
+            Claims::new(admin_id, None),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        let scheduled = create_sale(
+            State::from(&db_pool),
+            sale(product_id, None, Duration::days(7)),
+            Claims::new(admin_id, None),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+
+        let listed = |claims: Option<Claims>| async {
+            let mut ids = get_sales(State::from(&db_pool), Some(product_id), None, claims)
+                .await
+                .unwrap()
+                .into_inner()
+                .into_iter()
+                .filter_map(|sale| sale.id)
+                .collect::<Vec<i32>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(listed(None).await, vec![running]);
+        assert_eq!(
+            listed(Some(Claims::new(admin_id, None))).await,
+            vec![running, scheduled]
+        );
+    }
+
+    #[tokio::test]
+    async fn single_size_sale_applies_at_checkout() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Кепка", 1500.0).await;
+        create_sale(
+            State::from(&db_pool),
+            sale(product_id, Some("single_size"), Duration::hours(-1)),
+            Claims::new(admin_id, None),
+        )
+        .await
+        .unwrap();
+        let currency = find_currency(&db_pool, None).await.unwrap();
+
+        let mut conn = db_pool.acquire().await.unwrap();
+        let price = get_checkout_price(&mut conn, product_id, None, &currency)
+            .await
+            .unwrap();
+
+        assert_eq!(price, 1000.0);
+    }
+}
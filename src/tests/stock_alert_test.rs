@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod stock_alert {
+    use crate::error::api_error::ApiError;
+    use crate::query::products_components::stock_alert_query::send_low_stock_digest;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use sqlx::Row;
+
+    /// The test admin has no deliverable address, so the digest fails after claiming.
+    #[tokio::test]
+    async fn failed_digest_releases_claimed_alerts() {
+        let db_pool = fresh_db_pool().await;
+        create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        sqlx::query(
+            r#"
+            INSERT INTO stock_alerts (product_id, size, stock, threshold)
+            VALUES ($1, 'single_size', 1, 2)
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            send_low_stock_digest(&db_pool).await,
+            Err(ApiError::EmailError)
+        ));
+
+        let pending: i64 =
+            sqlx::query("SELECT COUNT(*) AS pending FROM stock_alerts WHERE notified_at IS NULL")
+                .fetch_one(&db_pool)
+                .await
+                .unwrap()
+                .get("pending");
+        assert_eq!(pending, 1);
+    }
+}
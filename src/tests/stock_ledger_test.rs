@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod stock_ledger {
+    use crate::data::user_components::claims::Claims;
+    use crate::database::open_db_pool;
+    use crate::query::products_components::stock_query::get_stock_discrepancies;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use rocket::State;
+    use sqlx::{PgPool, Row};
+
+    async fn run_migrations(db_pool: &PgPool) {
+        let database_name: String = sqlx::query("SELECT current_database() AS name")
+            .fetch_one(db_pool)
+            .await
+            .unwrap()
+            .get("name");
+        open_db_pool(&database_name).await.unwrap();
+    }
+
+    async fn opening_balances(db_pool: &PgPool, product_id: i32) -> Vec<(String, i32)> {
+        sqlx::query(
+            r#"
+            SELECT size, delta FROM stock_movements
+            WHERE product_id = $1 AND reason = 'opening_balance'
+            ORDER BY size
+            "#,
+        )
+        .bind(product_id)
+        .fetch_all(db_pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| (row.get("size"), row.get("delta")))
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn stock_from_before_the_ledger_gets_an_opening_balance() {
+        let db_pool = fresh_db_pool().await;
+        let admin_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Hoodie", 1500.0).await;
+
+        // Stock counted before the ledger existed, then one sale recorded after it.
+        sqlx::query(
+            r#"
+            INSERT INTO product_sizes (product_id, s, m, opening_recorded)
+            VALUES ($1, 10, 3, FALSE)
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO stock_movements (product_id, size, delta, reason)
+            VALUES ($1, 'm', -1, 'sale')
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        run_migrations(&db_pool).await;
+        assert_eq!(
+            opening_balances(&db_pool, product_id).await,
+            vec![("m".to_string(), 4), ("s".to_string(), 10)]
+        );
+        let discrepancies =
+            get_stock_discrepancies(State::from(&db_pool), Claims::new(admin_id, None))
+                .await
+                .unwrap();
+        assert!(discrepancies.is_empty(), "{:?}", discrepancies.0);
+
+        run_migrations(&db_pool).await;
+        assert_eq!(opening_balances(&db_pool, product_id).await.len(), 2);
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod stock_subscription {
+    use crate::data::products_components::stock_subscription::NewStockSubscription;
+    use crate::data::user_components::claims::Claims;
+    use crate::query::products_components::stock_subscription_query::{
+        notify_back_in_stock, subscribe_to_stock,
+    };
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use rocket::serde::json::Json;
+    use rocket::State;
+    use sqlx::Row;
+
+    /// The test admin's email is `admin`, the same as the unconfirmed guest subscription.
+    #[tokio::test]
+    async fn logged_in_subscriber_takes_over_unconfirmed_guest_subscription() {
+        let db_pool = fresh_db_pool().await;
+        let user_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        sqlx::query(
+            r#"
+            INSERT INTO stock_subscriptions (product_id, size, email, token)
+            VALUES ($1, 'single_size', 'ADMIN', 'guest-token')
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let subscribed = subscribe_to_stock(
+            State::from(&db_pool),
+            product_id,
+            Json(NewStockSubscription {
+                size: None,
+                email: None,
+            }),
+            Some(Claims::new(user_id, None)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(subscribed, "Subscribed successfully");
+
+        let row = sqlx::query(
+            "SELECT user_id, confirmed_at IS NOT NULL AS confirmed FROM stock_subscriptions",
+        )
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert_eq!(row.get::<Option<i32>, &str>("user_id"), Some(user_id));
+        assert!(row.get::<bool, &str>("confirmed"));
+    }
+
+    /// `admin` is not a deliverable address, so the claimed subscription has to be released.
+    #[tokio::test]
+    async fn failed_email_leaves_subscription_pending() {
+        let db_pool = fresh_db_pool().await;
+        let product_id = create_test_product(&db_pool, "Cap", 700.0).await;
+        sqlx::query("INSERT INTO product_sizes (product_id, single_size) VALUES ($1, 3)")
+            .bind(product_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO stock_subscriptions (product_id, size, email, token, confirmed_at)
+            VALUES ($1, 'single_size', 'admin', 'token', NOW())
+            "#,
+        )
+        .bind(product_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        notify_back_in_stock(&db_pool, Some(product_id))
+            .await
+            .unwrap();
+
+        let pending: i64 = sqlx::query(
+            "SELECT COUNT(*) AS pending FROM stock_subscriptions WHERE notified_at IS NULL",
+        )
+        .fetch_one(&db_pool)
+        .await
+        .unwrap()
+        .get("pending");
+        assert_eq!(pending, 1);
+    }
+}
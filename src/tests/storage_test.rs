@@ -0,0 +1,406 @@
+#[cfg(test)]
+mod s3_storage {
+    use crate::storage::s3::S3Storage;
+    use crate::storage::BlobStorage;
+    use hmac::{Hmac, Mac};
+    use rocket::data::{Data, ToByteUnit};
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::request::{FromRequest, Outcome};
+    use rocket::{Request, State};
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use tokio::sync::oneshot;
+
+    type Bucket = Mutex<HashMap<String, Vec<u8>>>;
+
+    const ACCESS_KEY: &str = "key";
+    const SECRET_KEY: &str = "secret";
+    const REGION: &str = "us-east-1";
+
+    /// Passes only requests carrying a valid AWS Signature V4, either in the
+    /// `Authorization` header or presigned in the query. The signature is recomputed
+    /// here from the request as received, independently of the client.
+    struct SignedRequest;
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for SignedRequest {
+        type Error = ();
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            let valid = match req.headers().get_one("Authorization") {
+                Some(authorization) => header_signature_valid(req, authorization),
+                None => presigned_signature_valid(req),
+            };
+            match valid {
+                true => Outcome::Success(SignedRequest),
+                false => Outcome::Error((Status::Forbidden, ())),
+            }
+        }
+    }
+
+    fn header_signature_valid(req: &Request<'_>, authorization: &str) -> bool {
+        let Some(fields) = authorization.strip_prefix("AWS4-HMAC-SHA256 ") else {
+            return false;
+        };
+        let fields = fields
+            .split(", ")
+            .filter_map(|field| field.split_once('='))
+            .collect::<HashMap<&str, &str>>();
+        let (Some(credential), Some(signed_headers), Some(signature)) = (
+            fields.get("Credential"),
+            fields.get("SignedHeaders"),
+            fields.get("Signature"),
+        ) else {
+            return false;
+        };
+        let (Some(amz_date), Some(payload_hash)) = (
+            req.headers().get_one("x-amz-date"),
+            req.headers().get_one("x-amz-content-sha256"),
+        ) else {
+            return false;
+        };
+        if !signed_headers.split(';').any(|name| name == "host")
+            || !signed_headers.split(';').any(|name| name == "x-amz-date")
+        {
+            return false;
+        }
+
+        let canonical_headers = signed_headers
+            .split(';')
+            .map(|name| {
+                let value = req.headers().get_one(name).unwrap_or_default();
+                format!("{}:{}\n", name, value.trim())
+            })
+            .collect::<String>();
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            req.method(),
+            req.uri().path(),
+            canonical_query(req, None),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+        signature_matches(credential, amz_date, &canonical_request, signature)
+    }
+
+    fn presigned_signature_valid(req: &Request<'_>) -> bool {
+        let param = |name: &str| req.query_value::<&str>(name).and_then(Result::ok);
+        let (
+            Some("AWS4-HMAC-SHA256"),
+            Some(credential),
+            Some(amz_date),
+            Some("host"),
+            Some(signature),
+        ) = (
+            param("X-Amz-Algorithm"),
+            param("X-Amz-Credential"),
+            param("X-Amz-Date"),
+            param("X-Amz-SignedHeaders"),
+            param("X-Amz-Signature"),
+        )
+        else {
+            return false;
+        };
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            req.uri().path(),
+            canonical_query(req, Some("X-Amz-Signature")),
+            req.headers().get_one("Host").unwrap_or_default()
+        );
+        signature_matches(credential, amz_date, &canonical_request, signature)
+    }
+
+    /// The query as sent, minus `skip`, with parameters sorted.
+    fn canonical_query(req: &Request<'_>, skip: Option<&str>) -> String {
+        let mut params = req
+            .uri()
+            .query()
+            .map(|query| query.as_str().split('&').collect::<Vec<&str>>())
+            .unwrap_or_default();
+        params.retain(|param| skip.is_none_or(|skip| param.split('=').next() != Some(skip)));
+        params.sort();
+        params.join("&")
+    }
+
+    fn signature_matches(
+        credential: &str,
+        amz_date: &str,
+        canonical_request: &str,
+        signature: &str,
+    ) -> bool {
+        let Some((ACCESS_KEY, scope)) = credential.split_once('/') else {
+            return false;
+        };
+        let [date, REGION, "s3", "aws4_request"] = scope.split('/').collect::<Vec<&str>>()[..]
+        else {
+            return false;
+        };
+        if !amz_date.starts_with(date) {
+            return false;
+        }
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac(format!("AWS4{}", SECRET_KEY).as_bytes(), date.as_bytes());
+        for part in [REGION, "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        hex::encode(hmac(&key, string_to_sign.as_bytes())) == signature
+    }
+
+    fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    struct CopySource(Option<String>);
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for CopySource {
+        type Error = ();
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            let source = req.headers().get_one("x-amz-copy-source");
+            Outcome::Success(CopySource(
+                source.map(|source| source.trim_start_matches('/').to_string()),
+            ))
+        }
+    }
+
+    struct PayloadHash(String);
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for PayloadHash {
+        type Error = ();
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            match req.headers().get_one("x-amz-content-sha256") {
+                Some(hash) => Outcome::Success(PayloadHash(hash.to_string())),
+                None => Outcome::Error((Status::BadRequest, ())),
+            }
+        }
+    }
+
+    #[put("/<path..>", data = "<data>")]
+    async fn put_object(
+        bucket: &State<Bucket>,
+        path: PathBuf,
+        data: Data<'_>,
+        copy_source: CopySource,
+        payload_hash: PayloadHash,
+        _signed: SignedRequest,
+    ) -> Status {
+        let body = data
+            .open(1.mebibytes())
+            .into_bytes()
+            .await
+            .unwrap()
+            .into_inner();
+        if hex::encode(Sha256::digest(&body)) != payload_hash.0 {
+            return Status::BadRequest;
+        }
+        let bytes = match copy_source.0 {
+            Some(source) => match bucket.lock().unwrap().get(&source) {
+                Some(bytes) => bytes.clone(),
+                None => return Status::NotFound,
+            },
+            None => body,
+        };
+        bucket
+            .lock()
+            .unwrap()
+            .insert(path.display().to_string(), bytes);
+        Status::Ok
+    }
+
+    struct ListRequest;
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for ListRequest {
+        type Error = ();
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            match req.query_value::<u8>("list-type") {
+                Some(Ok(2)) => Outcome::Success(ListRequest),
+                _ => Outcome::Forward(Status::NotFound),
+            }
+        }
+    }
+
+    #[get("/<name>", rank = 1)]
+    fn list_objects(
+        bucket: &State<Bucket>,
+        name: &str,
+        _list: ListRequest,
+        _signed: SignedRequest,
+    ) -> String {
+        let prefix = format!("{}/", name);
+        let contents = bucket
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .map(|key| {
+                format!(
+                    "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified></Contents>",
+                    key
+                )
+            })
+            .collect::<String>();
+        format!("<ListBucketResult>{}</ListBucketResult>", contents)
+    }
+
+    #[get("/<path..>", rank = 2)]
+    fn get_object(
+        bucket: &State<Bucket>,
+        path: PathBuf,
+        _signed: SignedRequest,
+    ) -> Option<Vec<u8>> {
+        bucket
+            .lock()
+            .unwrap()
+            .get(&path.display().to_string())
+            .cloned()
+    }
+
+    #[delete("/<path..>")]
+    fn delete_object(bucket: &State<Bucket>, path: PathBuf, _signed: SignedRequest) -> Status {
+        bucket.lock().unwrap().remove(&path.display().to_string());
+        Status::NoContent
+    }
+
+    /// Starts the stand-in on a free port and returns its endpoint once it is listening.
+    async fn start_stand_in() -> String {
+        let config = rocket::Config {
+            port: 0,
+            log_level: rocket::config::LogLevel::Off,
+            ..rocket::Config::debug_default()
+        };
+        let (port_sender, port_receiver) = oneshot::channel();
+        let port_sender = Mutex::new(Some(port_sender));
+        tokio::spawn(
+            rocket::custom(config)
+                .manage(Bucket::default())
+                .mount(
+                    "/",
+                    routes![put_object, get_object, delete_object, list_objects],
+                )
+                .attach(AdHoc::on_liftoff("Stand-in port", move |rocket| {
+                    Box::pin(async move {
+                        if let Some(sender) = port_sender.lock().unwrap().take() {
+                            let _ = sender.send(rocket.config().port);
+                        }
+                    })
+                }))
+                .launch(),
+        );
+        let port = port_receiver.await.expect("stand-in should start");
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    #[tokio::test]
+    async fn s3_storage_rejects_bad_signatures() {
+        let endpoint = start_stand_in().await;
+
+        let wrong_secret = S3Storage::new(&endpoint, "images", REGION, ACCESS_KEY, "wrong");
+        assert!(wrong_secret.put("a.webp", b"bytes".to_vec()).await.is_err());
+        let wrong_key = S3Storage::new(&endpoint, "images", REGION, "other", SECRET_KEY);
+        assert!(wrong_key.put("a.webp", b"bytes".to_vec()).await.is_err());
+
+        let storage = S3Storage::new(&endpoint, "images", REGION, ACCESS_KEY, SECRET_KEY);
+        storage
+            .put("a.webp", b"bytes".to_vec())
+            .await
+            .expect("upload should succeed");
+
+        let unsigned = reqwest::get(format!("{}/images/a.webp", endpoint))
+            .await
+            .unwrap();
+        assert_eq!(unsigned.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let presigned = storage.presigned_url("a.webp", 60);
+        let tampered = presigned.replace("X-Amz-Expires=60", "X-Amz-Expires=6000");
+        let status = reqwest::get(&tampered).await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+        let other_object = presigned.replace("/a.webp?", "/b.webp?");
+        let status = reqwest::get(&other_object).await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+
+        let forged = reqwest::Client::new()
+            .put(format!("{}/images/a.webp", endpoint))
+            .header(
+                "Authorization",
+                "AWS4-HMAC-SHA256 Credential=key/20240101/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-date, Signature=00",
+            )
+            .header("x-amz-date", "20240101T000000Z")
+            .header("x-amz-content-sha256", hex::encode(Sha256::digest(b"forged")))
+            .body("forged")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(forged.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn s3_storage_round_trip() {
+        let endpoint = start_stand_in().await;
+        let storage = S3Storage::new(&endpoint, "images", REGION, ACCESS_KEY, SECRET_KEY);
+
+        storage
+            .put("test_card.webp", b"image bytes".to_vec())
+            .await
+            .expect("upload should succeed");
+
+        let presigned = storage.presigned_url("test_card.webp", 60);
+        assert!(presigned.starts_with(&format!("{}/images/test_card.webp?", endpoint)));
+        let body = reqwest::get(&presigned)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), b"image bytes");
+
+        storage
+            .put("orphan_card.webp", b"orphan bytes".to_vec())
+            .await
+            .expect("upload should succeed");
+        storage
+            .rename("orphan_card.webp", "quarantine/orphan_card.webp")
+            .await
+            .expect("rename should succeed");
+        let mut keys = storage
+            .list()
+            .await
+            .expect("list should succeed")
+            .into_iter()
+            .map(|blob| blob.key)
+            .collect::<Vec<String>>();
+        keys.sort();
+        assert_eq!(keys, vec!["test_card.webp".to_string()]);
+        let quarantined = reqwest::get(storage.presigned_url("quarantine/orphan_card.webp", 60))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(quarantined.as_ref(), b"orphan bytes");
+
+        storage
+            .delete("test_card.webp")
+            .await
+            .expect("delete should succeed");
+        let status = reqwest::get(&presigned).await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,35 +1,35 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod test {
     use crate::database::init_db_pool;
     use crate::error::api_error::ApiError;
     use crate::server::set_up_rocket;
+    use crate::tests::database::pricing::currency_test_db::{create_currency, get_product_price};
     use crate::tests::database::products::cap_test_db::*;
     use crate::tests::database::products::hoodie_test_db::create_hoodie_black;
     use crate::tests::database::products::product_test_db::*;
     use crate::tests::database::products::property::category_test_db::*;
     use crate::tests::database::products::t_shirt_test_db::*;
+    use crate::tests::database::test_db::init_test_config;
     use crate::tests::database::user_test_db::*;
-    use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
-    use crate::utils::env_configuration::{EnvConfiguration, CONFIG};
+    use crate::utils::env_configuration::CONFIG;
     use reqwest::Client;
     use rocket::State;
     use sqlx::Error;
     use std::fs;
-    use std::path::Path;
     use std::time::Duration;
     use tokio::time::sleep;
 
     #[tokio::test]
     async fn bootstrap_test() -> Result<(), ApiError> {
-        EnvConfiguration::init_config();
+        init_test_config();
         let db_pool = init_db_pool()
             .await
             .map_err(|_| ApiError::DatabaseError(Error::RowNotFound))?;
         let db_ref = &db_pool;
 
-        if !Path::new(PATH_PRODUCT_IMAGES).exists() {
-            fs::create_dir(PATH_PRODUCT_IMAGES).expect("Failed to create images directory");
-        }
+        fs::create_dir_all(&CONFIG.get().unwrap().images_dir)
+            .expect("Failed to create images directory");
 
         tokio::spawn({
             let db_pool_clone = db_pool.clone();
@@ -55,6 +55,9 @@ mod test {
         user_test.update_user_profile().await?;
         user_test.get_user_profile().await?;
 
+        create_currency(&user_test, "USD", "$", 41.5).await?;
+        create_currency(&user_test, "EUR", "€", 45.0).await?;
+
         create_category(&user_test, "Кепки").await?;
         create_cap_black(&user_test).await?;
         create_cap_red(&user_test).await?;
@@ -66,6 +69,17 @@ mod test {
         create_hoodie_black(&user_test).await?;
         get_product_by_id(&user_test).await?;
 
+        let usd_price = get_product_price(&user_test, 1, "USD").await?;
+        assert!(
+            (usd_price - 16.87).abs() < 0.001,
+            "700 UAH at 41.5 is 16.87 USD"
+        );
+        let eur_price = get_product_price(&user_test, 1, "EUR").await?;
+        assert!(
+            (eur_price - 15.56).abs() < 0.001,
+            "700 UAH at 45.0 is 15.56 EUR"
+        );
+
         Ok(())
     }
 }
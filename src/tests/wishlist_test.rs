@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod wishlists {
+    use crate::data::wishlists::wishlist::NewWishlistItem;
+    use crate::query::wishlists::wishlist_query::merge_wishlist;
+    use crate::tests::database::test_db::{create_test_admin, create_test_product, fresh_db_pool};
+    use sqlx::Row;
+
+    fn item(product_id: i32, size: Option<&str>) -> NewWishlistItem {
+        NewWishlistItem {
+            product_id,
+            size: size.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_skips_unknown_sizes_drafts_and_missing_products() {
+        let db_pool = fresh_db_pool().await;
+        let user_id = create_test_admin(&db_pool).await;
+        let product_id = create_test_product(&db_pool, "Футболка", 500.0).await;
+        let draft_id = create_test_product(&db_pool, "Чернетка", 500.0).await;
+        sqlx::query("UPDATE products SET draft = TRUE WHERE id = $1")
+            .bind(draft_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        merge_wishlist(
+            &db_pool,
+            user_id,
+            vec![
+                item(product_id, Some("M")),
+                item(product_id, Some("huge")),
+                item(draft_id, None),
+                item(product_id + 1000, None),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let saved = sqlx::query("SELECT product_id, size FROM wishlist_items WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&db_pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.get("product_id"), row.get("size")))
+            .collect::<Vec<(i32, Option<String>)>>();
+        assert_eq!(saved, vec![(product_id, Some("m".to_string()))]);
+    }
+}
@@ -0,0 +1,17 @@
+pub const CATALOG_CACHE_CONTROL: &str = "public, no-cache";
+pub const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+pub const IMAGE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+pub const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Tables behind cached responses; each one needs a deletion trigger in `database.rs`.
+pub const CATEGORY_SOURCES: &[&str] = &["categories", "category_translations"];
+pub const PRODUCT_SOURCES: &[&str] = &[
+    "products",
+    "product_prices",
+    "currencies",
+    "product_sales",
+    "product_translations",
+    "reviews",
+    "price_history",
+];
+pub const PRODUCT_IMAGE_SOURCES: &[&str] = &["products", "product_images"];
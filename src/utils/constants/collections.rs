@@ -0,0 +1,14 @@
+pub const COLLECTION_MANUAL: &str = "manual";
+pub const COLLECTION_RULE: &str = "rule";
+pub const COLLECTION_SORT_NEWEST: &str = "newest";
+pub const COLLECTION_SORT_PRICE_ASC: &str = "price_asc";
+pub const COLLECTION_SORT_PRICE_DESC: &str = "price_desc";
+pub const COLLECTION_SORT_BESTSELLING: &str = "bestselling";
+pub const COLLECTION_SORTS: [&str; 4] = [
+    COLLECTION_SORT_NEWEST,
+    COLLECTION_SORT_PRICE_ASC,
+    COLLECTION_SORT_PRICE_DESC,
+    COLLECTION_SORT_BESTSELLING,
+];
+pub const COLLECTION_PRODUCT_LIMIT: i32 = 24;
+pub const COLLECTION_MAX_PRODUCT_LIMIT: i32 = 100;
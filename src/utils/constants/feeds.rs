@@ -0,0 +1,7 @@
+pub const SHOP_NAME: &str = "Tyutyun Shop";
+pub const PRODUCT_PAGE: &str = "product";
+pub const CATEGORY_PAGE: &str = "category";
+pub const FEED_SITEMAP: &str = "sitemap";
+pub const FEED_GOOGLE: &str = "google";
+pub const FEED_YML: &str = "yml";
+pub const GOOGLE_FEED_MAX_ADDITIONAL_IMAGES: usize = 10;
@@ -0,0 +1,14 @@
+pub const IMAGE_VARIANTS: [(&str, u32); 3] = [("thumbnail", 240), ("card", 720), ("full", 1600)];
+pub const IMAGE_FORMATS: [&str; 2] = ["webp", "jpg"];
+pub const FULL_IMAGE_VARIANT: &str = "full";
+pub const JPEG_QUALITY: u8 = 85;
+pub const WEBP_QUALITY: f32 = 80.0;
+pub const IMAGE_BATCH_MAX_FILES: usize = 20;
+pub const IMAGE_FILE_LIMIT_MIB: u64 = 10;
+pub const IMAGE_BATCH_LIMIT_MIB: u64 = 100;
+pub const QUARANTINE_PREFIX: &str = "quarantine/";
+pub const IMAGE_CHECK_REPORT: &str = "report";
+pub const IMAGE_CHECK_QUARANTINE: &str = "quarantine";
+pub const IMAGE_CHECK_REMOVE: &str = "remove";
+pub const IMAGE_CHECK_COMMAND: &str = "check-images";
+pub const ORPHAN_IMAGE_GRACE_MINUTES: i64 = 60;
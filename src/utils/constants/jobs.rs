@@ -0,0 +1,7 @@
+use std::time::Duration;
+
+pub const RECOMMENDATIONS_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub const LOW_STOCK_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+pub const BACK_IN_STOCK_NOTIFY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+pub const IMAGE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+pub const SALE_PRICE_HISTORY_INTERVAL: Duration = Duration::from_secs(60);
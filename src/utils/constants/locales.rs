@@ -0,0 +1,3 @@
+/// Locale stored in the base `name`/`description` columns and used when nothing else matches.
+pub const DEFAULT_LOCALE: &str = "uk";
+pub const SUPPORTED_LOCALES: [&str; 2] = ["uk", "en"];
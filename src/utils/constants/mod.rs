@@ -1 +1,11 @@
+pub mod caching;
+pub mod collections;
+pub mod feeds;
+pub mod images;
+pub mod jobs;
+pub mod locales;
+pub mod orders;
+pub mod pricing;
+pub mod products;
+pub mod reviews;
 pub mod routes;
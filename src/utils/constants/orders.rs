@@ -0,0 +1,16 @@
+pub const ORDER_STATUS_PENDING: &str = "pending";
+pub const ORDER_STATUS_PROCESSING: &str = "processing";
+pub const ORDER_STATUS_SHIPPED: &str = "shipped";
+pub const ORDER_STATUS_DELIVERED: &str = "delivered";
+pub const ORDER_STATUS_CANCELLED: &str = "cancelled";
+pub const ORDER_STATUSES: [&str; 5] = [
+    ORDER_STATUS_PENDING,
+    ORDER_STATUS_PROCESSING,
+    ORDER_STATUS_SHIPPED,
+    ORDER_STATUS_DELIVERED,
+    ORDER_STATUS_CANCELLED,
+];
+pub const ORDER_TOTAL_TOLERANCE: f32 = 0.01;
+pub const SHIPPING_FIELD_MAX_LEN: usize = 255;
+pub const SHIPPING_NAME_MAX_LEN: usize = 100;
+pub const SHIPPING_PHONE_MAX_LEN: usize = 20;
@@ -0,0 +1,10 @@
+pub const BASE_CURRENCY: &str = "UAH";
+pub const ROUNDING_NEAREST: &str = "nearest";
+pub const ROUNDING_UP: &str = "up";
+pub const ROUNDING_DOWN: &str = "down";
+pub const PRICE_SOURCE_MANUAL: &str = "manual";
+pub const PRICE_SOURCE_SALE_STARTED: &str = "sale_started";
+pub const PRICE_SOURCE_SALE_ENDED: &str = "sale_ended";
+pub const PRICE_SOURCE_SALE_DELETED: &str = "sale_deleted";
+pub const PRICE_SOURCE_IMPORT: &str = "import";
+pub const LOWEST_PRICE_WINDOW_DAYS: i32 = 30;
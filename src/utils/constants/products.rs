@@ -0,0 +1,20 @@
+pub const PRODUCT_SIZES: [&str; 6] = ["single_size", "s", "m", "l", "xl", "xxl"];
+pub const ATTRIBUTE_TEXT: &str = "text";
+pub const ATTRIBUTE_ENUM: &str = "enum";
+pub const ATTRIBUTE_NUMBER: &str = "number";
+pub const CATALOG_IMPORT_LIMIT_MIB: u64 = 10;
+pub const CATALOG_COLUMNS: [&str; 6] = [
+    "id",
+    "name",
+    "description",
+    "price",
+    "compare_at_price",
+    "category",
+];
+pub const RECOMMENDATIONS_LIMIT: i64 = 8;
+pub const RECOMMENDATIONS_MAX_LIMIT: i64 = 50;
+pub const STOCK_SALE: &str = "sale";
+pub const STOCK_RETURN: &str = "return";
+pub const STOCK_ADJUSTMENT: &str = "adjustment";
+pub const STOCK_RESTOCK: &str = "restock";
+pub const STOCK_STOCKTAKE: &str = "stocktake";
@@ -0,0 +1,6 @@
+pub const REVIEW_PENDING: &str = "pending";
+pub const REVIEW_APPROVED: &str = "approved";
+pub const REVIEW_HIDDEN: &str = "hidden";
+pub const REVIEWS_PER_PAGE: i64 = 20;
+pub const REVIEWS_MAX_PER_PAGE: i64 = 100;
+pub const REVIEW_MAX_PHOTOS: i64 = 5;
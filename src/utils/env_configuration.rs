@@ -1,3 +1,4 @@
+use crate::utils::constants::routes::PATH_PRODUCT_IMAGES;
 use once_cell::sync::OnceCell;
 use std::env;
 
@@ -19,11 +20,25 @@ pub struct EnvConfiguration {
     pub admin_password: String,
     pub local: bool,
     pub server_url: String,
+    pub storage_backend: String,
+    pub images_dir: String,
+    pub quarantine_dir: String,
+    pub image_check_action: String,
+    pub s3_endpoint: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    pub s3_public_url: Option<String>,
+    pub s3_presigned_ttl: u64,
+    pub shipping_price: f32,
+    pub free_shipping_threshold: Option<f32>,
 }
 
 impl EnvConfiguration {
     pub fn init_config() {
         dotenv::dotenv().ok();
+        let images_dir = env::var("IMAGES_DIR").unwrap_or(PATH_PRODUCT_IMAGES.to_string());
         CONFIG.get_or_init(|| EnvConfiguration {
             database_name: env::var("DATABASE_NAME").unwrap_or("postgres".to_string()),
             database_host: env::var("DATABASE_HOST").unwrap_or("localhost".to_string()),
@@ -45,6 +60,30 @@ impl EnvConfiguration {
                 .unwrap_or("false".to_string())
                 .parse::<bool>()
                 .unwrap_or(false),
+            storage_backend: env::var("STORAGE_BACKEND").unwrap_or("local".to_string()),
+            quarantine_dir: env::var("QUARANTINE_DIR")
+                .unwrap_or(format!("{}_quarantine", images_dir)),
+            images_dir,
+            image_check_action: env::var("IMAGE_CHECK_ACTION").unwrap_or("report".to_string()),
+            s3_endpoint: env::var("S3_ENDPOINT").unwrap_or("http://localhost:9000".to_string()),
+            s3_bucket: env::var("S3_BUCKET").unwrap_or("product-images".to_string()),
+            s3_region: env::var("S3_REGION").unwrap_or("us-east-1".to_string()),
+            s3_access_key: env::var("S3_ACCESS_KEY").unwrap_or("minioadmin".to_string()),
+            s3_secret_key: env::var("S3_SECRET_KEY").unwrap_or("minioadmin".to_string()),
+            s3_public_url: env::var("S3_PUBLIC_URL").ok(),
+            s3_presigned_ttl: env::var("S3_PRESIGNED_TTL")
+                .unwrap_or("0".to_string())
+                .parse::<u64>()
+                .unwrap_or(0),
+            shipping_price: env::var("SHIPPING_PRICE")
+                .unwrap_or("0".to_string())
+                .parse::<f32>()
+                .expect("SHIPPING_PRICE must be a number"),
+            free_shipping_threshold: env::var("FREE_SHIPPING_THRESHOLD").ok().map(|threshold| {
+                threshold
+                    .parse::<f32>()
+                    .expect("FREE_SHIPPING_THRESHOLD must be a number")
+            }),
         });
     }
 }
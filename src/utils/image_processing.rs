@@ -0,0 +1,88 @@
+use crate::error::api_error::ApiError;
+use crate::utils::constants::images::{
+    FULL_IMAGE_VARIANT, IMAGE_FORMATS, IMAGE_VARIANTS, JPEG_QUALITY, WEBP_QUALITY,
+};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::io::Cursor;
+
+pub struct ProcessedImage {
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Decodes an upload, applies its EXIF orientation and re-encodes every variant. Nothing
+/// from the original container survives re-encoding, so metadata is stripped as a side effect.
+pub fn process_image(image_key: &str, data: &[u8]) -> Result<Vec<ProcessedImage>, ApiError> {
+    let image = decode_image(data)?;
+    let mut processed = Vec::new();
+
+    for (variant, max_side) in IMAGE_VARIANTS {
+        let resized = if image.width() > max_side || image.height() > max_side {
+            image.resize(max_side, max_side, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+
+        for format in IMAGE_FORMATS {
+            processed.push(ProcessedImage {
+                filename: variant_filename(image_key, variant, format),
+                bytes: encode_image(&resized, format)?,
+            });
+        }
+    }
+
+    Ok(processed)
+}
+
+pub fn variant_filename(image_key: &str, variant: &str, format: &str) -> String {
+    format!("{}_{}.{}", image_key, variant, format)
+}
+
+pub fn variant_filenames(image_key: &str) -> Vec<String> {
+    IMAGE_VARIANTS
+        .iter()
+        .flat_map(|(variant, _)| {
+            IMAGE_FORMATS
+                .iter()
+                .map(move |format| variant_filename(image_key, variant, format))
+        })
+        .collect()
+}
+
+pub fn primary_filename(image_key: &str) -> String {
+    variant_filename(image_key, FULL_IMAGE_VARIANT, "jpg")
+}
+
+fn decode_image(data: &[u8]) -> Result<DynamicImage, ApiError> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|_| ApiError::InvalidImage)?
+        .into_decoder()
+        .map_err(|_| ApiError::InvalidImage)?;
+    let orientation = decoder.orientation().map_err(|_| ApiError::InvalidImage)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| ApiError::InvalidImage)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// WebP is encoded lossy through libwebp; the `image` crate only writes lossless WebP,
+/// which comes out larger than the JPEG it is meant to replace.
+fn encode_image(image: &DynamicImage, format: &str) -> Result<Vec<u8>, ApiError> {
+    match format {
+        "webp" => {
+            let rgba = image.to_rgba8();
+            let encoded =
+                webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height()).encode(WEBP_QUALITY);
+            Ok(encoded.to_vec())
+        }
+        _ => {
+            let mut bytes = Vec::new();
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))
+                .map_err(|_| ApiError::InternalServerError)?;
+            Ok(bytes)
+        }
+    }
+}
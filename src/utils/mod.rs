@@ -1,2 +1,3 @@
 pub mod constants;
 pub mod env_configuration;
+pub mod image_processing;